@@ -0,0 +1,158 @@
+// Cloze deletions: `The capital of {{c1::France}} is {{c2::Paris::city}}`.
+//
+// Every number is one review item of the card. When it is asked, its
+// deletions are hidden (showing the hint, if any) and all others are shown.
+
+use crate::models::Block;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ClozePart {
+    Text(String),
+    Deletion {
+        ordinal: u32,
+        answer: String,
+        hint: Option<String>,
+    },
+}
+
+/// Splits cloze text into plain text and deletions. Anything that does not
+/// look like a complete `{{cN::...}}` is kept as text.
+pub fn parse_cloze(text: &str) -> Vec<ClozePart> {
+    let mut parts = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{c") {
+        plain.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+
+        match parse_deletion(candidate) {
+            Some((deletion, len)) => {
+                if !plain.is_empty() {
+                    parts.push(ClozePart::Text(std::mem::take(&mut plain)));
+                }
+                parts.push(deletion);
+                rest = &candidate[len..];
+            }
+            None => {
+                plain.push_str("{{c");
+                rest = &candidate[3..];
+            }
+        }
+    }
+
+    plain.push_str(rest);
+    if !plain.is_empty() {
+        parts.push(ClozePart::Text(plain));
+    }
+
+    parts
+}
+
+/// A deletion at the start of `text` and its length in bytes.
+fn parse_deletion(text: &str) -> Option<(ClozePart, usize)> {
+    let body = text.strip_prefix("{{c")?;
+    let digits = body.find(|c: char| !c.is_ascii_digit())?;
+    let ordinal: u32 = body[..digits].parse().ok().filter(|&n| n > 0)?;
+
+    let inner = body[digits..].strip_prefix("::")?;
+    let end = inner.find("}}")?;
+    let content = &inner[..end];
+
+    let (answer, hint) = match content.split_once("::") {
+        Some((answer, hint)) => (answer, Some(hint.to_string())),
+        None => (content, None),
+    };
+
+    // "{{c" + digits + "::" + content + "}}"
+    let len = 3 + digits + 2 + end + 2;
+    Some((ClozePart::Deletion { ordinal, answer: answer.to_string(), hint }, len))
+}
+
+/// Cloze numbers used in the given blocks, sorted and without duplicates.
+/// The numbers of occlusion masks count as well, they are studied the same way.
+pub fn cloze_ordinals<'a>(blocks: impl IntoIterator<Item = &'a Block>) -> Vec<u32> {
+    let mut ordinals: Vec<u32> = blocks
+        .into_iter()
+        .flat_map(|block| match block {
+            Block::Cloze { value } => parse_cloze(value)
+                .into_iter()
+                .filter_map(|part| match part {
+                    ClozePart::Deletion { ordinal, .. } => Some(ordinal),
+                    ClozePart::Text(_) => None,
+                })
+                .collect(),
+            Block::Occlusion { masks, .. } => masks.iter().map(|mask| mask.ordinal).collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+
+    ordinals.sort_unstable();
+    ordinals.dedup();
+    ordinals
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Rect;
+
+    fn text(s: &str) -> ClozePart {
+        ClozePart::Text(s.to_string())
+    }
+
+    fn deletion(ordinal: u32, answer: &str, hint: Option<&str>) -> ClozePart {
+        ClozePart::Deletion { ordinal, answer: answer.to_string(), hint: hint.map(str::to_string) }
+    }
+
+    #[test]
+    fn parses_deletions_and_hints() {
+        assert_eq!(
+            parse_cloze("The capital of {{c1::France}} is {{c2::Paris::city}}."),
+            vec![
+                text("The capital of "),
+                deletion(1, "France", None),
+                text(" is "),
+                deletion(2, "Paris", Some("city")),
+                text("."),
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete_deletions_stay_text() {
+        for input in ["{{c1::open", "{{c::x}}", "{{c0::x}}", "{{cx::y}}", "{{c1:x}}"] {
+            assert_eq!(parse_cloze(input), vec![text(input)], "{input}");
+        }
+        assert_eq!(parse_cloze(""), vec![]);
+    }
+
+    #[test]
+    fn text_around_a_broken_deletion_is_kept() {
+        assert_eq!(
+            parse_cloze("{{c1 {{c1::a}}"),
+            vec![text("{{c1 "), deletion(1, "a", None)]
+        );
+    }
+
+    #[test]
+    fn non_ascii_text() {
+        assert_eq!(
+            parse_cloze("Ä {{c3::ö}} ü"),
+            vec![text("Ä "), deletion(3, "ö", None), text(" ü")]
+        );
+    }
+
+    #[test]
+    fn ordinals_of_cloze_and_occlusion_blocks() {
+        let mask = |ordinal| Rect { x: 0.0, y: 0.0, width: 0.1, height: 0.1, ordinal };
+        let blocks = [
+            Block::Cloze { value: "{{c2::b}} {{c1::a}} {{c2::c}}".into() },
+            Block::Text { value: "{{c9::not a cloze block}}".into() },
+            Block::Occlusion { src: "files/a.png".into(), masks: vec![mask(4), mask(1)] },
+        ];
+
+        assert_eq!(cloze_ordinals(&blocks), vec![1, 2, 4]);
+    }
+}
@@ -0,0 +1,117 @@
+// Line based diff between two versions of a card, for the edit history.
+//
+// A card is flattened into lines (name, tags, then one or more lines per
+// block) and the lines are compared with a longest common subsequence.
+
+use crate::models::{ Block, CardRevision };
+use crate::table::to_markdown;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+fn block_lines(side: &str, block: &Block) -> Vec<String> {
+    match block {
+        Block::Text { value } => value.lines().map(|l| format!("{side} text: {l}")).collect(),
+        Block::Math { value } => value.lines().map(|l| format!("{side} math: {l}")).collect(),
+        Block::Image { src } => vec![format!("{side} image: {src}")],
+        Block::File { path } => vec![format!("{side} file: {path}")],
+        Block::Audio { src, autoplay } => {
+            let autoplay = if *autoplay { " (autoplay)" } else { "" };
+            vec![format!("{side} audio: {src}{autoplay}")]
+        }
+        Block::Video { src } => vec![format!("{side} video: {src}")],
+        Block::Cloze { value } => value.lines().map(|l| format!("{side} cloze: {l}")).collect(),
+        Block::Markdown { value } => value.lines().map(|l| format!("{side} markdown: {l}")).collect(),
+        Block::Code { language, source, .. } => {
+            source.lines().map(|l| format!("{side} code ({language}): {l}")).collect()
+        }
+        Block::MultipleChoice { question, options, correct, shuffle } => {
+            let shuffle = if *shuffle { " (shuffled)" } else { "" };
+            let mut lines = vec![format!("{side} question: {question}{shuffle}")];
+            lines.extend(options.iter().enumerate().map(|(i, option)| {
+                let mark = if correct.contains(&i) { "x" } else { " " };
+                format!("{side} option [{mark}]: {option}")
+            }));
+            lines
+        }
+        Block::TypeAnswer { expected, case_sensitive, accept } => {
+            let case = if *case_sensitive { " (case sensitive)" } else { "" };
+            let mut lines = vec![format!("{side} answer: {expected}{case}")];
+            lines.extend(accept.iter().map(|answer| format!("{side} also accepted: {answer}")));
+            lines
+        }
+        Block::Occlusion { src, masks } => {
+            let mut lines = vec![format!("{side} occlusion: {src}")];
+            lines.extend(masks.iter().map(|mask| {
+                format!(
+                    "{side} mask {}: {:.3} {:.3} {:.3}x{:.3}",
+                    mask.ordinal, mask.x, mask.y, mask.width, mask.height
+                )
+            }));
+            lines
+        }
+        Block::Table { headers, rows } => {
+            to_markdown(headers, rows).lines().map(|l| format!("{side} table: {l}")).collect()
+        }
+    }
+}
+
+impl CardRevision {
+    /// The revision as lines of text, the unit `diff_lines` compares.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("name: {}", self.name),
+            format!("tags: {}", self.tags.as_deref().unwrap_or_default()),
+        ];
+
+        for block in &self.front_blocks {
+            lines.extend(block_lines("front", block));
+        }
+        for block in &self.back_blocks {
+            lines.extend(block_lines("back", block));
+        }
+
+        lines
+    }
+}
+
+/// How to get from `old` to `new`, line by line.
+pub fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    // common[i][j] = length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i].clone()));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            diff.push(DiffLine::Removed(old[i].clone()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j].clone()));
+            j += 1;
+        }
+    }
+
+    diff.extend(old[i..].iter().cloned().map(DiffLine::Removed));
+    diff.extend(new[j..].iter().cloned().map(DiffLine::Added));
+
+    diff
+}
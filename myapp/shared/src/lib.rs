@@ -1,4 +1,14 @@
+pub mod cloze;
+pub mod diff;
+pub mod markdown;
 pub mod models;
+pub mod notes;
+pub mod quiz;
+pub mod search;
+pub mod table;
+pub mod tags;
+pub mod tex;
+pub mod text_export;
 
 use serde::{Serialize, Deserialize};
 
@@ -0,0 +1,112 @@
+// Markdown blocks, rendered to HTML in the web view.
+//
+// Only Markdown itself is supported: raw HTML is shown as text, images are
+// replaced by their description and only web and mail links are kept.
+// `$...$` and `$$...$$` are passed on to MathJax.
+
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn is_safe_url(url: &str) -> bool {
+    let url = url.trim().to_lowercase();
+    ["http://", "https://", "mailto:"].iter().any(|scheme| url.starts_with(scheme))
+}
+
+pub fn render_markdown(text: &str) -> String {
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_MATH;
+
+    let events = Parser::new_ext(text, options).filter_map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Some(Event::Text(raw)),
+        Event::InlineMath(math) => Some(Event::InlineHtml(CowStr::from(format!(
+            "\\({}\\)",
+            escape_html(&math)
+        )))),
+        Event::DisplayMath(math) => Some(Event::InlineHtml(CowStr::from(format!(
+            "\\[{}\\]",
+            escape_html(&math)
+        )))),
+        // the text inside an image is its description and stays
+        Event::Start(Tag::Image { .. }) | Event::End(TagEnd::Image) => None,
+        Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+            let dest_url = if is_safe_url(&dest_url) { dest_url } else { CowStr::from("") };
+            Some(Event::Start(Tag::Link { link_type, dest_url, title, id }))
+        }
+        other => Some(other),
+    });
+
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+/// Markdown of a single line (e.g. a table cell) without the paragraph
+/// around it.
+pub fn render_inline_markdown(text: &str) -> String {
+    let html = render_markdown(&text.replace('\n', " "));
+    let trimmed = html.trim_end();
+    trimmed
+        .strip_prefix("<p>")
+        .and_then(|inner| inner.strip_suffix("</p>"))
+        .filter(|inner| !inner.contains("<p>"))
+        .unwrap_or(trimmed)
+        .to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_html_is_shown_as_text() {
+        let html = render_markdown("<script>alert(1)</script>");
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+
+        let html = render_markdown("a <b onclick=\"x()\">bold</b> word");
+        assert!(!html.contains("<b"));
+        assert!(html.contains("&lt;b onclick=\"x()\"&gt;bold&lt;/b&gt;"));
+    }
+
+    #[test]
+    fn unsafe_links_lose_their_target() {
+        let html = render_markdown("[click](javascript:alert(1))");
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("<a href=\"\">click</a>"));
+
+        let html = render_markdown("<javascript:alert(1)>");
+        assert!(!html.contains("href=\"javascript:"));
+    }
+
+    #[test]
+    fn web_and_mail_links_are_kept() {
+        assert!(render_markdown("[a](https://example.com)").contains("href=\"https://example.com\""));
+        assert!(render_markdown("<https://example.com>").contains("href=\"https://example.com\""));
+        assert!(render_markdown("<mailto:me@example.com>").contains("href=\"mailto:me@example.com\""));
+    }
+
+    #[test]
+    fn images_are_replaced_by_their_description() {
+        let html = render_markdown("![a cat](https://example.com/cat.png)");
+        assert!(!html.contains("<img"));
+        assert!(!html.contains("cat.png"));
+        assert!(html.contains("a cat"));
+    }
+
+    #[test]
+    fn math_is_escaped_for_mathjax() {
+        assert_eq!(render_markdown("$<b>$"), "<p>\\(&lt;b&gt;\\)</p>\n");
+        assert_eq!(render_markdown("$$a<b$$"), "<p>\\[a&lt;b\\]</p>\n");
+    }
+
+    #[test]
+    fn inline_markdown_has_no_paragraph() {
+        assert_eq!(render_inline_markdown("**a**\nb"), "<strong>a</strong> b");
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Serialize, Deserialize};
+use crate::tex::BlockTexError;
 
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
@@ -8,8 +9,80 @@ pub enum Block {
     Math { value: String },
     Image { src: String },
     File { path: String },
+    Audio {
+        src: String,
+        /// Play as soon as the block is shown, i.e. with the question on the
+        /// front or when the answer is revealed on the back.
+        #[serde(default)]
+        autoplay: bool,
+    },
+    Video { src: String },
+    /// Text with cloze deletions like `{{c1::answer}}`, see `crate::cloze`.
+    Cloze { value: String },
+    /// Markdown with inline `$...$` math, see `crate::markdown`.
+    Markdown { value: String },
+    Code {
+        /// Syntax name or file extension, e.g. `rust` or `py`.
+        language: String,
+        source: String,
+        /// Highlighted HTML, filled in by the backend when the block is saved.
+        #[serde(default)]
+        html: String,
+    },
+    /// A question answered by picking options, graded automatically while
+    /// studying, see `crate::quiz`.
+    MultipleChoice {
+        question: String,
+        options: Vec<String>,
+        /// Indices into `options`; more than one lets several be picked.
+        correct: Vec<usize>,
+        /// Show the options in a random order every time.
+        #[serde(default)]
+        shuffle: bool,
+    },
+    /// An answer typed while studying and compared with `expected`, see
+    /// `crate::quiz::check_answer`.
+    TypeAnswer {
+        expected: String,
+        #[serde(default)]
+        case_sensitive: bool,
+        /// Other answers that count as correct, e.g. synonyms.
+        #[serde(default)]
+        accept: Vec<String>,
+    },
+    /// An image with parts hidden by masks. Masks are numbered like cloze
+    /// deletions: each number is one review item that hides its masks.
+    Occlusion { src: String, masks: Vec<Rect> },
+    /// Cells hold inline Markdown with `$math$`, see `crate::table`.
+    Table {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
 }
 
+/// A mask of an `Occlusion` block, as fractions of the image size so it
+/// fits the image at any size.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default = "first_ordinal")]
+    pub ordinal: u32,
+}
+
+fn first_ordinal() -> u32 {
+    1
+}
+
+/// Every value of `Block::block_type`, e.g. for the `type:` search term.
+/// A new block type is added here and in the match below.
+pub const BLOCK_TYPES: &[&str] = &[
+    "text", "math", "image", "file", "audio", "video", "cloze", "markdown", "code",
+    "multiple_choice", "type_answer", "occlusion", "table",
+];
+
 impl Block {
     pub fn block_type(&self) -> &'static str {
         match self {
@@ -17,6 +90,15 @@ impl Block {
             Block::Math { .. } => "math",
             Block::Image { .. } => "image",
             Block::File { .. } => "file",
+            Block::Audio { .. } => "audio",
+            Block::Video { .. } => "video",
+            Block::Cloze { .. } => "cloze",
+            Block::Markdown { .. } => "markdown",
+            Block::Code { .. } => "code",
+            Block::MultipleChoice { .. } => "multiple_choice",
+            Block::TypeAnswer { .. } => "type_answer",
+            Block::Occlusion { .. } => "occlusion",
+            Block::Table { .. } => "table",
         }
     }
 
@@ -24,6 +106,9 @@ impl Block {
         match self {
             Block::Image { src } => Some(src),
             Block::File { path } => Some(path),
+            Block::Audio { src, .. } => Some(src),
+            Block::Video { src } => Some(src),
+            Block::Occlusion { src, .. } => Some(src),
             _ => None,
         }
     }
@@ -32,16 +117,57 @@ impl Block {
         match self {
             Block::Image { src } => Some(src),
             Block::File { path } => Some(path),
+            Block::Audio { src, .. } => Some(src),
+            Block::Video { src } => Some(src),
+            Block::Occlusion { src, .. } => Some(src),
             _ => None,
         }
     }
 }
 
 
+/// A block together with the id of its row, as edited in the card editor.
+/// Saving updates the rows in place instead of rewriting the whole card.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct EditorBlock {
+    /// `None` for blocks that were not saved yet.
+    pub id: Option<i64>,
+    pub block: Block,
+}
+
+impl EditorBlock {
+    pub fn new(block: Block) -> Self {
+        Self { id: None, block }
+    }
+}
+
+/// Both sides of a card with their block ids.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct CardBlocks {
+    pub front: Vec<EditorBlock>,
+    pub back: Vec<EditorBlock>,
+}
+
+/// What saving a card from the editor found.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct SaveReport {
+    /// The card as changed elsewhere, when saving was refused because of it.
+    pub conflict: Option<Card>,
+    /// Problems in the TeX of math blocks; the card is saved anyway.
+    pub math_errors: Vec<BlockTexError>,
+}
+
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Card {
     pub id: i64,
     pub deck_id: i64,
+
+    /// Stable identity that survives export/import, used to match cards
+    /// when a revised deck is merged into an existing one.
+    #[serde(default)]
+    pub uid: String,
+
     pub name: String,
 
     /// Blocks that belong to the question side (front)
@@ -53,7 +179,40 @@ pub struct Card {
     pub created_at: i64,
     pub times_seen: u32,
     pub times_correct: u32,
+
+    /// Comma separated, normalized (see `crate::tags`).
     pub tags: Option<String>,
+
+    /// Unix timestamp when the card is due again; `None` for cards never studied.
+    #[serde(default)]
+    pub due_at: Option<i64>,
+
+    /// Current review interval in days (0 while the card is being (re)learned).
+    #[serde(default)]
+    pub interval_days: u32,
+
+    /// Suspended cards are left out of studying until they are unsuspended.
+    #[serde(default)]
+    pub suspended: bool,
+
+    /// Buried cards are left out of studying until this Unix timestamp.
+    #[serde(default)]
+    pub buried_until: Option<i64>,
+
+    /// Goes up whenever name, tags or blocks change, see `Draft::base_version`.
+    #[serde(default)]
+    pub version: i64,
+
+    /// The note this card was generated from, see `NoteType`. Its blocks are
+    /// then rewritten whenever the note is saved.
+    #[serde(default)]
+    pub note_id: Option<i64>,
+
+    /// In a study queue: the cloze deletion (or occlusion mask number) this
+    /// review item asks for. The scores and scheduling above are then those
+    /// of the deletion.
+    #[serde(default)]
+    pub cloze: Option<u32>,
 }
 
 impl Card {
@@ -61,6 +220,7 @@ impl Card {
         Self {
             id: -1, // temporary ID; backend will assign real ID
             deck_id,
+            uid: String::new(), // backend assigns a uid on insert
             name: "Neue Karte".into(),
             front_blocks: vec![],
             back_blocks: vec![],
@@ -68,42 +228,397 @@ impl Card {
             times_seen: 0,
             times_correct: 0,
             tags: None,
+            due_at: None,
+            interval_days: 0,
+            suspended: false,
+            buried_until: None,
+            version: 0,
+            note_id: None,
+            cloze: None,
         }
     }
 
-    pub fn progress_percent(&self) -> u8 {
-        let good = self.times_correct as f64;
-        let bad = (self.times_seen - self.times_correct) as f64;
-
-        if good == 0.0 {
-            return 0;
-        }
+    /// Whether the card should show up in a study session at `now`.
+    pub fn is_due(&self, now: i64) -> bool {
+        self.is_active(now) && self.due_at.is_none_or(|due| due <= now)
+    }
 
-        let alpha = 2.0; // BAD penalty
-        let k = 0.6;     // curve speed
+    /// Neither suspended nor buried at `now`.
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.suspended && self.buried_until.is_none_or(|until| until <= now)
+    }
 
-        let score = (good - alpha * bad).max(0.0);
+    pub fn progress_percent(&self) -> u8 {
+        progress_percent(self.times_seen, self.times_correct)
+    }
 
-        let confidence = 1.0 - (-k * score).exp();
+    /// Interval after a review: doubled on GOOD, back to relearning on BAD.
+    pub fn next_interval(&self, correct: bool) -> u32 {
+        if correct {
+            (self.interval_days * 2).max(1)
+        } else {
+            0
+        }
+    }
 
-        (confidence * 100.0).round().clamp(0.0, 100.0) as u8
+    pub fn tag_list(&self) -> Vec<String> {
+        crate::tags::parse_tags(self.tags.as_deref().unwrap_or_default())
     }
 
     pub fn all_blocks(&self) -> impl Iterator<Item = &Block> {
         self.front_blocks.iter().chain(self.back_blocks.iter())
     }
 
+    /// Cloze and mask numbers used on the card; each one is studied separately.
+    pub fn cloze_ordinals(&self) -> Vec<u32> {
+        crate::cloze::cloze_ordinals(self.all_blocks())
+    }
+
     pub fn all_blocks_mut(&mut self) -> impl Iterator<Item = &mut Block> {
         self.front_blocks.iter_mut().chain(self.back_blocks.iter_mut())
     }
 }
 
+/// Confidence in percent that a card is learned. Also available in SQL as
+/// `progress(times_seen, times_correct)`, see `open_db`.
+pub fn progress_percent(times_seen: u32, times_correct: u32) -> u8 {
+    let good = times_correct as f64;
+    let bad = (times_seen - times_correct) as f64;
+
+    if good == 0.0 {
+        return 0;
+    }
+
+    let alpha = 2.0; // BAD penalty
+    let k = 0.6;     // curve speed
+
+    let score = (good - alpha * bad).max(0.0);
+
+    let confidence = 1.0 - (-k * score).exp();
+
+    (confidence * 100.0).round().clamp(0.0, 100.0) as u8
+}
+
+/// A tag with the number of cards carrying it directly.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TagInfo {
+    pub name: String,
+    pub card_count: u32,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Deck {
     pub id: i64,
+    /// Name of this deck only; the full path is built from the parents.
     pub name: String,
     pub created_at: i64,
     pub card_count: u32,
+
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+
+    // The following counts include all sub-decks and add up to `card_count`.
+    // Suspended and buried cards only count as inactive.
+
+    /// Cards that were never studied.
+    #[serde(default)]
+    pub new_count: u32,
+
+    /// Studied cards that are due again.
+    #[serde(default)]
+    pub due_count: u32,
+
+    /// Studied cards that are not due yet.
+    #[serde(default)]
+    pub learned_count: u32,
+
+    /// Suspended or currently buried cards.
+    #[serde(default)]
+    pub inactive_count: u32,
+}
+
+/// Separator between the segments of a deck path, e.g. `Math::Linear Algebra`.
+pub const DECK_PATH_SEPARATOR: &str = "::";
+
+pub fn split_deck_path(path: &str) -> Vec<&str> {
+    path.split(DECK_PATH_SEPARATOR)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Full `Parent::Child` name of a deck, given all decks.
+pub fn deck_full_name(decks: &[Deck], deck_id: i64) -> String {
+    let mut parts = Vec::new();
+    let mut current = decks.iter().find(|d| d.id == deck_id);
+
+    while let Some(deck) = current {
+        parts.push(deck.name.as_str());
+        current = deck
+            .parent_id
+            .and_then(|parent| decks.iter().find(|d| d.id == parent));
+
+        if parts.len() > decks.len() {
+            break; // defensive: never loop on a broken parent chain
+        }
+    }
+
+    parts.reverse();
+    parts.join(DECK_PATH_SEPARATOR)
+}
+
+/// Decks in tree order (parents before their children) with their depth.
+pub fn deck_tree(decks: &[Deck]) -> Vec<(usize, &Deck)> {
+    fn visit<'a>(decks: &'a [Deck], parent: i64, depth: usize, out: &mut Vec<(usize, &'a Deck)>) {
+        for deck in decks.iter().filter(|d| d.parent_id == Some(parent)) {
+            out.push((depth, deck));
+            visit(decks, deck.id, depth + 1, out);
+        }
+    }
+
+    let mut out = Vec::new();
+
+    // decks whose parent no longer exists are shown at the top level
+    let roots = decks.iter().filter(|d| {
+        d.parent_id.is_none_or(|parent| !decks.iter().any(|p| p.id == parent))
+    });
+
+    for deck in roots {
+        out.push((0, deck));
+        visit(decks, deck.id, 1, &mut out);
+    }
+
+    out
+}
+
+
+/// Text formats a deck can be exported to besides the archive, see
+/// `crate::text_export`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum TextExportFormat {
+    /// Comma separated, one line per card, importable by Anki.
+    AnkiCsv,
+    Markdown,
+}
+
+/// How an archive should be applied when it is imported.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "kind")]
+pub enum ImportMode {
+    /// Create a brand-new deck from the archive.
+    NewDeck,
+    /// Update an existing deck: add new cards, update changed ones and keep
+    /// the learner's scores and tags.
+    Merge { deck_id: i64, removed: RemovedCards },
+    /// Throw away the cards of an existing deck and use the archive instead.
+    Replace { deck_id: i64 },
+}
+
+/// What happens to cards of the target deck that are missing from the archive.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum RemovedCards {
+    Keep,
+    Archive,
+    Delete,
+}
+
+/// Summary of a staged archive, shown before the user picks an import mode.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ImportSummary {
+    pub deck_name: String,
+    pub card_count: u32,
+}
+
+/// What an import would do to the target deck (card names per category).
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct ImportPreview {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: u32,
+}
+
+
+/// Which cards a filtered deck contains. Empty lists and `None` mean
+/// "no restriction"; all given conditions must hold.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct FilterQuery {
+    /// Cards carrying any of these tags (or one of their descendants).
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Cards in any of these decks (including their sub-decks).
+    #[serde(default)]
+    pub deck_ids: Vec<i64>,
+
+    /// Inclusive range of `Card::progress_percent`.
+    #[serde(default)]
+    pub min_progress: Option<u8>,
+    #[serde(default)]
+    pub max_progress: Option<u8>,
+
+    /// Cards reviewed within the last N days.
+    #[serde(default)]
+    pub seen_within_days: Option<u32>,
+
+    /// Cards answered wrong at least once within the last N days.
+    #[serde(default)]
+    pub failed_within_days: Option<u32>,
+
+    #[serde(default)]
+    pub due: DueFilter,
+    /// Additional search query (see `crate::search`).
+    #[serde(default)]
+    pub search: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum DueFilter {
+    #[default]
+    Any,
+    /// Never studied or due again.
+    Due,
+    /// Never studied.
+    New,
+    /// Studied and not due yet.
+    Learned,
+}
+
+/// A saved query that can be studied like a deck. Cards stay in their decks.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct FilteredDeck {
+    pub id: i64,
+    pub name: String,
+    pub query: FilterQuery,
+    pub created_at: i64,
+
+    /// Number of cards currently matching the query.
+    #[serde(default)]
+    pub card_count: u32,
+}
+
+
+/// Column the card browser is sorted by.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum CardSort {
+    Name,
+    #[default]
+    Created,
+    Progress,
+    TimesSeen,
+    Due,
+}
+
+/// Number of cards `list_cards` returns per page.
+pub const CARD_PAGE_SIZE: u32 = 50;
+
+/// One page of the card browser.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct CardPage {
+    pub cards: Vec<Card>,
+    /// Number of cards matching the query on all pages.
+    pub total: u32,
+    pub page: u32,
+}
+
+impl CardPage {
+    pub fn page_count(&self) -> u32 {
+        self.total.div_ceil(CARD_PAGE_SIZE).max(1)
+    }
+}
+
+/// Operation applied to all selected cards of the card browser
+/// (moving and copying have their own commands).
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "kind")]
+pub enum BulkAction {
+    AddTag { tag: String },
+    RemoveTag { tag: String },
+    Delete,
+    ResetProgress,
+}
+
+
+/// Unsaved state of the card editor, autosaved while typing.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Draft {
+    /// 0 until the draft is stored for the first time.
+    pub id: i64,
+    /// `None` for a new card that was never saved.
+    pub card_id: Option<i64>,
+    pub deck_id: i64,
+    /// `Card::version` when editing started. If the card's version differs
+    /// on save, it was changed elsewhere in the meantime.
+    pub base_version: Option<i64>,
+    pub name: String,
+    /// As typed, not yet normalized.
+    pub tags: String,
+    pub blocks: CardBlocks,
+    pub updated_at: i64,
+}
+
+
+/// A saved version of a card, recorded every time the card is saved.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CardRevision {
+    pub id: i64,
+    pub card_id: i64,
+    pub created_at: i64,
+    pub name: String,
+    pub tags: Option<String>,
+    pub front_blocks: Vec<Block>,
+    pub back_blocks: Vec<Block>,
+}
+
+
+/// A deck or card in the trash.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TrashedItem {
+    pub id: i64,
+    pub name: String,
+    pub deleted_at: i64,
+}
+
+/// Contents of the trash. Cards deleted together with their deck are only
+/// listed as part of the deck.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct Trash {
+    pub decks: Vec<TrashedItem>,
+    pub cards: Vec<TrashedItem>,
+    /// Days after which trashed items are deleted for good.
+    pub retention_days: u32,
+}
+
+
+/// Generates cards from the fields of notes, e.g. "Basic + Reverse" makes a
+/// card asking for the back and one asking for the front.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct NoteType {
+    pub id: i64,
+    pub name: String,
+    pub fields: Vec<String>,
+    /// One card per template, see `crate::notes`.
+    pub templates: Vec<CardTemplate>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CardTemplate {
+    pub name: String,
+    /// Markdown with `{{Field}}` for the values of the note's fields.
+    pub front: String,
+    /// Like `front`; `{{FrontSide}}` repeats the front.
+    pub back: String,
+}
+
+/// Field values of one note, in the order of its type's fields.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Note {
+    pub id: i64,
+    pub note_type_id: i64,
+    /// Where cards generated later (e.g. when a field is filled in) go.
+    pub deck_id: i64,
+    pub fields: Vec<String>,
+    pub created_at: i64,
 }
 
 
@@ -141,4 +656,13 @@ pub enum InsertBlockKind {
     Math,
     Image,
     File,
+    Audio,
+    Video,
+    Cloze,
+    Markdown,
+    Code,
+    MultipleChoice,
+    TypeAnswer,
+    Occlusion,
+    Table,
 }
\ No newline at end of file
@@ -0,0 +1,72 @@
+// Cards generated from notes. A note type lists named fields and one
+// template per card; templates are Markdown with `{{Field}}` placeholders:
+//
+//   front: "{{Word}}"
+//   back:  "{{FrontSide}}\n\n---\n\n{{Definition}}"
+//
+// A template whose front comes out empty makes no card, so e.g. an
+// optional "Example" field only gets its own card once it is filled in.
+
+use crate::models::{Block, CardTemplate, NoteType};
+
+/// The template with every `{{Field}}` replaced by the field's value.
+/// Unknown names are left out, like empty fields.
+pub fn render_template(template: &str, field_names: &[String], values: &[String], front_side: &str) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+
+        let name = rest[start + 2..start + 2 + end].trim();
+        if name == "FrontSide" {
+            out.push_str(front_side);
+        } else if let Some(index) = field_names.iter().position(|field| field == name) {
+            out.push_str(values.get(index).map(String::as_str).unwrap_or_default());
+        }
+
+        rest = &rest[start + 2 + end + 2..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Front and back blocks of the card made by `template`, or `None` if its
+/// front is empty for these values.
+pub fn generate_card(note_type: &NoteType, template: &CardTemplate, values: &[String]) -> Option<(Vec<Block>, Vec<Block>)> {
+    let front = render_template(&template.front, &note_type.fields, values, "");
+    if front.trim().is_empty() {
+        return None;
+    }
+    let back = render_template(&template.back, &note_type.fields, values, &front);
+
+    let blocks = |text: String| {
+        if text.trim().is_empty() {
+            Vec::new()
+        } else {
+            vec![Block::Markdown { value: text.trim().to_string() }]
+        }
+    };
+
+    Some((blocks(front), blocks(back)))
+}
+
+/// Name of a generated card: the first field, plus the template's name if
+/// the type makes several cards.
+pub fn card_name(note_type: &NoteType, template: &CardTemplate, values: &[String]) -> String {
+    let first = values
+        .iter()
+        .map(|value| value.lines().next().unwrap_or_default().trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or("Note");
+
+    if note_type.templates.len() > 1 {
+        format!("{} ({})", first, template.name)
+    } else {
+        first.to_string()
+    }
+}
@@ -0,0 +1,252 @@
+// Blocks that grade the answer themselves instead of asking the learner
+// whether they knew it.
+
+use crate::diff::{diff_lines, DiffLine};
+use crate::models::Block;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Typed answers at least this similar to an expected one count as a typo.
+pub const CLOSE_SIMILARITY: f64 = 0.7;
+
+/// Whether exactly the correct options were chosen; the order they were
+/// picked in does not matter.
+pub fn is_correct_choice(correct: &[usize], chosen: &[usize]) -> bool {
+    let mut correct = correct.to_vec();
+    let mut chosen = chosen.to_vec();
+    correct.sort_unstable();
+    correct.dedup();
+    chosen.sort_unstable();
+    chosen.dedup();
+    correct == chosen
+}
+
+/// Number of blocks that have to be answered before a card is graded.
+pub fn quiz_count<'a>(blocks: impl IntoIterator<Item = &'a Block>) -> usize {
+    blocks
+        .into_iter()
+        .filter(|block| matches!(block, Block::MultipleChoice { .. } | Block::TypeAnswer { .. }))
+        .count()
+}
+
+/// The form typed answers are compared in: surrounding and repeated
+/// whitespace is dropped, accents are removed (`é` becomes `e`) and, unless
+/// `case_sensitive`, letters are lowercased.
+pub fn normalize_answer(text: &str, case_sensitive: bool) -> String {
+    let folded: String = text
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if case_sensitive { folded } else { folded.to_lowercase() }
+}
+
+/// Number of characters to insert, delete or replace to turn `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // distances from the part of `a` seen so far to every prefix of `b`
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// 1.0 for equal strings down to 0.0 for nothing in common.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let len = a.chars().count().max(b.chars().count());
+    if len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / len as f64
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AnswerGrade {
+    /// Equal to an accepted answer after normalizing.
+    Correct,
+    /// Off by a typo or two, see `CLOSE_SIMILARITY`.
+    Close,
+    Wrong,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AnswerCheck {
+    pub grade: AnswerGrade,
+    /// The accepted answer closest to what was typed.
+    pub closest: String,
+    pub similarity: f64,
+    /// Character by character from the typed answer to `closest`, both
+    /// normalized: removed characters were typed but are wrong, added ones
+    /// are missing.
+    pub diff: Vec<DiffLine>,
+}
+
+impl AnswerCheck {
+    /// The grade to propose; only correct answers are proposed as known.
+    pub fn proposed(&self) -> bool {
+        self.grade == AnswerGrade::Correct
+    }
+}
+
+/// Compares a typed answer with `expected` and the other accepted answers.
+pub fn check_answer(typed: &str, expected: &str, accept: &[String], case_sensitive: bool) -> AnswerCheck {
+    let normalized = normalize_answer(typed, case_sensitive);
+
+    let (closest, similarity) = std::iter::once(expected)
+        .chain(accept.iter().map(String::as_str).filter(|answer| !answer.trim().is_empty()))
+        .map(|answer| (answer, similarity(&normalized, &normalize_answer(answer, case_sensitive))))
+        // the first of equally close answers, i.e. `expected` if possible
+        .fold(None, |best: Option<(&str, f64)>, (answer, score)| match best {
+            Some((_, best_score)) if best_score >= score => best,
+            _ => Some((answer, score)),
+        })
+        .unwrap_or((expected, 0.0));
+
+    let grade = if similarity >= 1.0 {
+        AnswerGrade::Correct
+    } else if similarity >= CLOSE_SIMILARITY {
+        AnswerGrade::Close
+    } else {
+        AnswerGrade::Wrong
+    };
+
+    // in the compared form, so differences the grade ignores are not shown
+    let chars = |text: &str| {
+        normalize_answer(text, case_sensitive).chars().map(String::from).collect::<Vec<_>>()
+    };
+
+    AnswerCheck {
+        grade,
+        closest: closest.to_string(),
+        similarity,
+        diff: merge_diff(diff_lines(&chars(typed), &chars(closest))),
+    }
+}
+
+/// Joins neighbouring characters of the same kind into one run.
+fn merge_diff(diff: Vec<DiffLine>) -> Vec<DiffLine> {
+    let mut merged: Vec<DiffLine> = Vec::new();
+
+    for part in diff {
+        match (merged.last_mut(), part) {
+            (Some(DiffLine::Same(run)), DiffLine::Same(text))
+            | (Some(DiffLine::Added(run)), DiffLine::Added(text))
+            | (Some(DiffLine::Removed(run)), DiffLine::Removed(text)) => run.push_str(&text),
+            (_, part) => merged.push(part),
+        }
+    }
+
+    merged
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same(text: &str) -> DiffLine {
+        DiffLine::Same(text.to_string())
+    }
+
+    #[test]
+    fn normalizing_folds_case_accents_and_whitespace() {
+        assert_eq!(normalize_answer("  Crème   Brûlée ", false), "creme brulee");
+        assert_eq!(normalize_answer("Crème Brûlée", true), "Creme Brulee");
+        assert_eq!(normalize_answer("e\u{301}", false), "e");
+        assert_eq!(normalize_answer("", false), "");
+    }
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("paris", "pairs"), 2);
+        assert_eq!(levenshtein("äöü", "aöü"), 1);
+    }
+
+    #[test]
+    fn similarity_is_relative_to_the_longer_string() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("abc", "abc"), 1.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+        assert_eq!(similarity("abcd", "abce"), 0.75);
+    }
+
+    #[test]
+    fn case_and_accents_are_ignored_unless_case_sensitive() {
+        let check = check_answer("paris", "Paris", &[], false);
+        assert_eq!(check.grade, AnswerGrade::Correct);
+        assert_eq!(check.diff, vec![same("paris")]);
+
+        let check = check_answer("cafe", "café", &[], false);
+        assert_eq!(check.grade, AnswerGrade::Correct);
+        assert_eq!(check.diff, vec![same("cafe")]);
+
+        assert_eq!(check_answer("paris", "Paris", &[], true).grade, AnswerGrade::Close);
+    }
+
+    #[test]
+    fn accepted_alternatives_count_as_correct() {
+        let accept = vec!["USA".to_string(), " ".to_string()];
+        let check = check_answer("usa", "United States", &accept, false);
+
+        assert_eq!(check.grade, AnswerGrade::Correct);
+        assert_eq!(check.closest, "USA");
+        assert!(check.proposed());
+    }
+
+    #[test]
+    fn close_similarity_boundary() {
+        // 3 of 10 characters wrong: similarity 0.7 exactly
+        let check = check_answer("abcdefgxyz", "abcdefghij", &[], false);
+        assert_eq!(check.similarity, 0.7);
+        assert_eq!(check.grade, AnswerGrade::Close);
+        assert!(!check.proposed());
+
+        // 4 of 10 wrong
+        assert_eq!(check_answer("abcdefwxyz", "abcdefghij", &[], false).grade, AnswerGrade::Wrong);
+    }
+
+    #[test]
+    fn diff_shows_wrong_and_missing_characters() {
+        assert_eq!(
+            check_answer("Pari", "Paris", &[], false).diff,
+            vec![same("pari"), DiffLine::Added("s".to_string())]
+        );
+        assert_eq!(
+            check_answer("Parus", "Paris", &[], false).diff,
+            vec![
+                same("par"),
+                DiffLine::Removed("u".to_string()),
+                DiffLine::Added("i".to_string()),
+                same("s"),
+            ]
+        );
+    }
+
+    #[test]
+    fn choices_are_compared_as_sets() {
+        assert!(is_correct_choice(&[0, 2], &[2, 0]));
+        assert!(is_correct_choice(&[1], &[1, 1]));
+        assert!(!is_correct_choice(&[0, 2], &[0]));
+        assert!(!is_correct_choice(&[0], &[0, 1]));
+    }
+}
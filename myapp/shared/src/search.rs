@@ -0,0 +1,475 @@
+// Search query language, e.g.
+//
+//     deck:Math tag:exam -tag:done is:due progress:<50 type:math "eigen value"
+//
+// Terms separated by spaces must all match, `OR` between terms matches either
+// side, `-` negates a term and parentheses group. A bare word or a quoted
+// phrase searches card names and block contents. `field:value` terms:
+//
+//     deck:NAME        deck (full `A::B` path or leaf name) and its sub-decks
+//     did:ID           deck by id and its sub-decks
+//     tag:NAME         tag and its descendants
+//     is:due|new|learned|suspended|buried
+//     progress:<50     also <=, >, >=, = (plain number means =)
+//     type:TYPE        cards with a block of that type (`BLOCK_TYPES`)
+//     seen:N           reviewed in the last N days
+//     failed:N         answered wrong in the last N days
+//
+// The parser only builds the AST; the backend turns it into SQL.
+
+use crate::models::{ DueFilter, FilterQuery, BLOCK_TYPES };
+use crate::tags::normalize_tag;
+use serde::{ Serialize, Deserialize };
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Query {
+    /// All sub-queries match. Empty matches every card.
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    Term(Term),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum Term {
+    Text(String),
+    Deck(String),
+    DeckId(i64),
+    Tag(String),
+    Is(CardState),
+    Progress(Comparison, u8),
+    Type(String),
+    SeenWithin(u32),
+    FailedWithin(u32),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum CardState {
+    Due,
+    New,
+    Learned,
+    Suspended,
+    Buried,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    pub fn sql(self) -> &'static str {
+        match self {
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Eq => "=",
+            Comparison::Ge => ">=",
+            Comparison::Gt => ">",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ParseError {
+    /// Character offset into the query where the problem was found.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn error<T>(position: usize, message: impl Into<String>) -> Result<T, ParseError> {
+    Err(ParseError { position, message: message.into() })
+}
+
+
+// =======================
+// Tokenizer
+// =======================
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    Field(String, String),
+    Not,
+    Or,
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    let read_phrase = |i: &mut usize| -> Result<String, ParseError> {
+        let start = *i;
+        *i += 1;
+        let mut text = String::new();
+        while *i < chars.len() && chars[*i] != '"' {
+            text.push(chars[*i]);
+            *i += 1;
+        }
+        if *i == chars.len() {
+            return error(start, "unclosed quote");
+        }
+        *i += 1;
+        Ok(text)
+    };
+
+    while i < chars.len() {
+        let start = i;
+        match chars[i] {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push((start, Token::Open));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((start, Token::Close));
+                i += 1;
+            }
+            '-' => {
+                tokens.push((start, Token::Not));
+                i += 1;
+            }
+            '"' => tokens.push((start, Token::Phrase(read_phrase(&mut i)?))),
+            _ => {
+                let mut word = String::new();
+                while i < chars.len() && !chars[i].is_whitespace() && !"()\"".contains(chars[i]) {
+                    word.push(chars[i]);
+                    i += 1;
+                }
+
+                let token = match word.split_once(':') {
+                    Some((key, "")) if i < chars.len() && chars[i] == '"' => {
+                        Token::Field(key.to_lowercase(), read_phrase(&mut i)?)
+                    }
+                    Some((_, "")) => return error(i, format!("missing value after \"{word}\"")),
+                    Some((key, value)) => Token::Field(key.to_lowercase(), value.to_string()),
+                    None if word == "OR" => Token::Or,
+                    None => Token::Word(word),
+                };
+                tokens.push((start, token));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+
+// =======================
+// Parser
+// =======================
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map(|(p, _)| *p).unwrap_or(self.end)
+    }
+
+    fn or_expr(&mut self) -> Result<Query, ParseError> {
+        let mut alternatives = vec![self.and_expr()?];
+
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            alternatives.push(self.and_expr()?);
+        }
+
+        Ok(if alternatives.len() == 1 { alternatives.remove(0) } else { Query::Or(alternatives) })
+    }
+
+    fn and_expr(&mut self) -> Result<Query, ParseError> {
+        let mut terms = Vec::new();
+
+        while !matches!(self.peek(), None | Some(Token::Or) | Some(Token::Close)) {
+            terms.push(self.unary()?);
+        }
+
+        if terms.is_empty() {
+            return error(self.position(), "expected a search term");
+        }
+
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Query::And(terms) })
+    }
+
+    fn unary(&mut self) -> Result<Query, ParseError> {
+        let position = self.position();
+        let Some((_, token)) = self.tokens.get(self.pos).cloned() else {
+            return error(position, "expected a search term");
+        };
+        self.pos += 1;
+
+        match token {
+            Token::Not => Ok(Query::Not(Box::new(self.unary()?))),
+            Token::Open => {
+                let inner = self.or_expr()?;
+                if self.peek() != Some(&Token::Close) {
+                    return error(self.position(), "missing closing parenthesis");
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Token::Close => error(position, "unexpected closing parenthesis"),
+            Token::Or => error(position, "OR needs a term on both sides"),
+            Token::Word(text) | Token::Phrase(text) => Ok(Query::Term(Term::Text(text))),
+            Token::Field(key, value) => field_term(position, &key, &value).map(Query::Term),
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(position: usize, key: &str, value: &str) -> Result<T, ParseError> {
+    value
+        .parse()
+        .or_else(|_| error(position, format!("\"{key}:\" expects a number, got \"{value}\"")))
+}
+
+fn field_term(position: usize, key: &str, value: &str) -> Result<Term, ParseError> {
+    match key {
+        "deck" => Ok(Term::Deck(value.to_string())),
+        "did" => parse_number(position, key, value).map(Term::DeckId),
+        "tag" => match normalize_tag(value) {
+            Some(tag) => Ok(Term::Tag(tag)),
+            None => error(position, "\"tag:\" needs a tag name"),
+        },
+        "is" => match value.to_lowercase().as_str() {
+            "due" => Ok(Term::Is(CardState::Due)),
+            "new" => Ok(Term::Is(CardState::New)),
+            "learned" => Ok(Term::Is(CardState::Learned)),
+            "suspended" => Ok(Term::Is(CardState::Suspended)),
+            "buried" => Ok(Term::Is(CardState::Buried)),
+            _ => error(
+                position,
+                format!("unknown state \"{value}\", use due, new, learned, suspended or buried"),
+            ),
+        },
+        "progress" => {
+            let (comparison, number) = [
+                ("<=", Comparison::Le),
+                (">=", Comparison::Ge),
+                ("<", Comparison::Lt),
+                (">", Comparison::Gt),
+                ("=", Comparison::Eq),
+            ]
+            .into_iter()
+            .find_map(|(prefix, c)| value.strip_prefix(prefix).map(|rest| (c, rest)))
+            .unwrap_or((Comparison::Eq, value));
+
+            let percent: u8 = parse_number(position, key, number)?;
+            if percent > 100 {
+                return error(position, "progress is between 0 and 100");
+            }
+            Ok(Term::Progress(comparison, percent))
+        }
+        "type" => {
+            let block_type = value.to_lowercase();
+            if !BLOCK_TYPES.contains(&block_type.as_str()) {
+                return error(
+                    position,
+                    format!("unknown block type \"{value}\", use one of {}", BLOCK_TYPES.join(", ")),
+                );
+            }
+            Ok(Term::Type(block_type))
+        }
+        "seen" => parse_number(position, key, value).map(Term::SeenWithin),
+        "failed" => parse_number(position, key, value).map(Term::FailedWithin),
+        _ => error(position, format!("unknown field \"{key}:\"")),
+    }
+}
+
+/// Parses a search query. An empty (or blank) query matches every card.
+pub fn parse_query(input: &str) -> Result<Query, ParseError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(Query::And(Vec::new()));
+    }
+
+    let mut parser = Parser { tokens, pos: 0, end: input.chars().count() };
+    let query = parser.or_expr()?;
+
+    if parser.pos < parser.tokens.len() {
+        return error(parser.position(), "unexpected closing parenthesis");
+    }
+
+    Ok(query)
+}
+
+
+impl FilterQuery {
+    /// The query as a search AST, combining the form fields with the
+    /// free-form `search` text.
+    pub fn to_query(&self) -> Result<Query, ParseError> {
+        let mut all = Vec::new();
+
+        let tags: Vec<Query> = self
+            .tags
+            .iter()
+            .filter_map(|t| normalize_tag(t))
+            .map(|t| Query::Term(Term::Tag(t)))
+            .collect();
+        if !tags.is_empty() {
+            all.push(Query::Or(tags));
+        }
+
+        if !self.deck_ids.is_empty() {
+            all.push(Query::Or(
+                self.deck_ids.iter().map(|id| Query::Term(Term::DeckId(*id))).collect(),
+            ));
+        }
+
+        if let Some(min) = self.min_progress {
+            all.push(Query::Term(Term::Progress(Comparison::Ge, min)));
+        }
+        if let Some(max) = self.max_progress {
+            all.push(Query::Term(Term::Progress(Comparison::Le, max)));
+        }
+        if let Some(days) = self.seen_within_days {
+            all.push(Query::Term(Term::SeenWithin(days)));
+        }
+        if let Some(days) = self.failed_within_days {
+            all.push(Query::Term(Term::FailedWithin(days)));
+        }
+
+        match self.due {
+            DueFilter::Any => {}
+            DueFilter::Due => all.push(Query::Term(Term::Is(CardState::Due))),
+            DueFilter::New => all.push(Query::Term(Term::Is(CardState::New))),
+            DueFilter::Learned => all.push(Query::Term(Term::Is(CardState::Learned))),
+        }
+
+        all.push(parse_query(&self.search)?);
+
+        Ok(Query::And(all))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(term: Term) -> Query {
+        Query::Term(term)
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(parse_query("   "), Ok(Query::And(vec![])));
+    }
+
+    #[test]
+    fn terms_are_and_combined() {
+        assert_eq!(
+            parse_query("deck:Math tag:Exam eigen"),
+            Ok(Query::And(vec![
+                term(Term::Deck("Math".into())),
+                term(Term::Tag("exam".into())),
+                term(Term::Text("eigen".into())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn or_binds_weaker_than_and() {
+        assert_eq!(
+            parse_query("a b OR c"),
+            Ok(Query::Or(vec![
+                Query::And(vec![term(Term::Text("a".into())), term(Term::Text("b".into()))]),
+                term(Term::Text("c".into())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn negation_and_parentheses() {
+        assert_eq!(
+            parse_query("-(is:new OR is:suspended)"),
+            Ok(Query::Not(Box::new(Query::Or(vec![
+                term(Term::Is(CardState::New)),
+                term(Term::Is(CardState::Suspended)),
+            ]))))
+        );
+    }
+
+    #[test]
+    fn quoted_phrases_and_field_values() {
+        assert_eq!(
+            parse_query("\"eigen value\" deck:\"Linear Algebra\""),
+            Ok(Query::And(vec![
+                term(Term::Text("eigen value".into())),
+                term(Term::Deck("Linear Algebra".into())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn progress_comparisons() {
+        assert_eq!(parse_query("progress:<50"), Ok(term(Term::Progress(Comparison::Lt, 50))));
+        assert_eq!(parse_query("progress:>=75"), Ok(term(Term::Progress(Comparison::Ge, 75))));
+        assert_eq!(parse_query("progress:20"), Ok(term(Term::Progress(Comparison::Eq, 20))));
+        assert!(parse_query("progress:101").is_err());
+        assert!(parse_query("progress:abc").is_err());
+    }
+
+    #[test]
+    fn every_block_type_is_accepted() {
+        for block_type in BLOCK_TYPES {
+            assert_eq!(
+                parse_query(&format!("type:{block_type}")),
+                Ok(term(Term::Type(block_type.to_string())))
+            );
+        }
+        assert!(parse_query("type:pdf").is_err());
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        assert_eq!(parse_query("a \"open").unwrap_err().position, 2);
+        assert_eq!(parse_query("(a b").unwrap_err().position, 4);
+        assert_eq!(parse_query("a )").unwrap_err().position, 2);
+        assert_eq!(parse_query("a OR").unwrap_err().position, 4);
+        assert_eq!(parse_query("foo:bar").unwrap_err().position, 0);
+        assert_eq!(parse_query("is:").unwrap_err().position, 3);
+    }
+
+    #[test]
+    fn filter_query_combines_form_and_search() {
+        let filter = FilterQuery {
+            tags: vec!["ML".into()],
+            due: DueFilter::Due,
+            search: "knn".into(),
+            ..FilterQuery::default()
+        };
+
+        assert_eq!(
+            filter.to_query(),
+            Ok(Query::And(vec![
+                Query::Or(vec![term(Term::Tag("ml".into()))]),
+                term(Term::Is(CardState::Due)),
+                term(Term::Text("knn".into())),
+            ]))
+        );
+    }
+}
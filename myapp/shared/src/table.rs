@@ -0,0 +1,207 @@
+// Table blocks: CSV (or tab separated text copied from a spreadsheet) is
+// read when pasted, and tables are written out as CSV, Markdown or HTML,
+// the format of Anki fields. Cells may contain inline Markdown and `$math$`.
+
+use crate::markdown::render_inline_markdown;
+
+/// Number of columns: the widest of the header and all rows.
+pub fn column_count(headers: &[String], rows: &[Vec<String>]) -> usize {
+    rows.iter().map(Vec::len).chain([headers.len()]).max().unwrap_or(0)
+}
+
+/// The row padded with empty cells to `columns`.
+pub fn padded_row(row: &[String], columns: usize) -> Vec<String> {
+    let mut row = row.to_vec();
+    row.resize(columns, String::new());
+    row
+}
+
+/// Guesses the separator from the first line: tabs, then semicolons (CSV
+/// from spreadsheets in locales with a decimal comma), then commas.
+fn detect_delimiter(text: &str) -> char {
+    let first_line = text.lines().next().unwrap_or_default();
+    if first_line.contains('\t') {
+        '\t'
+    } else if first_line.matches(';').count() > first_line.matches(',').count() {
+        ';'
+    } else {
+        ','
+    }
+}
+
+/// Rows of CSV text. Quoted cells may contain separators, line breaks and
+/// `""` for a quote; empty lines are skipped.
+pub fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let delimiter = detect_delimiter(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.is_empty() => quoted = true,
+            c if quoted => cell.push(c),
+            c if c == delimiter => row.push(std::mem::take(&mut cell)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => cell.push(c),
+        }
+    }
+
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+    rows
+}
+
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Comma separated lines; an empty header row is left out, like in `to_html`.
+pub fn to_csv(headers: &[String], rows: &[Vec<String>]) -> String {
+    let columns = column_count(headers, rows);
+    let headers = Some(headers).filter(|headers| headers.iter().any(|cell| !cell.is_empty()));
+
+    headers
+        .into_iter()
+        .chain(rows.iter().map(Vec::as_slice))
+        .map(|row| {
+            padded_row(row, columns)
+                .iter()
+                .map(|cell| csv_cell(cell))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .map(|line| line + "\n")
+        .collect()
+}
+
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+pub fn to_markdown(headers: &[String], rows: &[Vec<String>]) -> String {
+    let columns = column_count(headers, rows);
+    let line = |row: &[String]| {
+        let cells: Vec<String> = padded_row(row, columns).iter().map(|cell| markdown_cell(cell)).collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+
+    let mut out = line(headers);
+    out.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+    for row in rows {
+        out.push_str(&line(row));
+    }
+    out
+}
+
+/// The table as HTML, with cells rendered like Markdown blocks.
+pub fn to_html(headers: &[String], rows: &[Vec<String>]) -> String {
+    let columns = column_count(headers, rows);
+    let cells = |row: &[String], tag: &str| {
+        padded_row(row, columns)
+            .iter()
+            .map(|cell| format!("<{tag}>{}</{tag}>", render_inline_markdown(cell)))
+            .collect::<String>()
+    };
+
+    let mut out = String::from("<table>");
+    if headers.iter().any(|cell| !cell.is_empty()) {
+        out.push_str(&format!("<thead><tr>{}</tr></thead>", cells(headers, "th")));
+    }
+    out.push_str("<tbody>");
+    for row in rows {
+        out.push_str(&format!("<tr>{}</tr>", cells(row, "td")));
+    }
+    out.push_str("</tbody></table>");
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn parses_comma_separated_lines() {
+        assert_eq!(parse_csv("a,b\r\nc,d\n"), rows(&[&["a", "b"], &["c", "d"]]));
+    }
+
+    #[test]
+    fn detects_tabs_and_semicolons() {
+        assert_eq!(parse_csv("1,5\t2\n3\t4"), rows(&[&["1,5", "2"], &["3", "4"]]));
+        assert_eq!(parse_csv("a;b;1,5\nc;d;2"), rows(&[&["a", "b", "1,5"], &["c", "d", "2"]]));
+    }
+
+    #[test]
+    fn quoted_cells() {
+        assert_eq!(
+            parse_csv("\"a, b\",\"say \"\"hi\"\"\"\n\"two\nlines\",x"),
+            rows(&[&["a, b", "say \"hi\""], &["two\nlines", "x"]])
+        );
+    }
+
+    #[test]
+    fn empty_lines_are_skipped_and_empty_cells_kept() {
+        assert_eq!(parse_csv("a,,c\n\n , \nd"), rows(&[&["a", "", "c"], &["d"]]));
+        assert_eq!(parse_csv(""), Vec::<Vec<String>>::new());
+    }
+
+    #[test]
+    fn short_rows_are_padded() {
+        let headers = vec!["h1".to_string()];
+        let body = rows(&[&["a", "b", "c"], &["d"]]);
+
+        assert_eq!(column_count(&headers, &body), 3);
+        assert_eq!(padded_row(&body[1], 3), vec!["d", "", ""]);
+        assert_eq!(
+            to_markdown(&headers, &body),
+            "| h1 |  |  |\n| --- | --- | --- |\n| a | b | c |\n| d |  |  |\n"
+        );
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        assert_eq!(to_markdown(&["a|b".to_string()], &[]), "| a\\|b |\n| --- |\n");
+    }
+
+    #[test]
+    fn csv_quotes_cells_and_reads_back() {
+        let headers = vec!["name".to_string(), "note".to_string()];
+        let body = rows(&[&["a, b", "say \"hi\""], &["two\nlines"]]);
+        let csv = to_csv(&headers, &body);
+
+        assert_eq!(csv, "name,note\n\"a, b\",\"say \"\"hi\"\"\"\n\"two\nlines\",\n");
+
+        let mut expected = vec![headers];
+        expected.extend(body.iter().map(|row| padded_row(row, 2)));
+        assert_eq!(parse_csv(&csv), expected);
+    }
+
+    #[test]
+    fn csv_leaves_out_an_empty_header() {
+        assert_eq!(to_csv(&["".to_string()], &rows(&[&["a", "b"]])), "a,b\n");
+    }
+}
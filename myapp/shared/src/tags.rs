@@ -0,0 +1,48 @@
+// Tags are lower case and may be nested with `::`, e.g. `ml::knn`.
+// A filter for a parent tag (`ml`) also matches all of its children.
+
+pub const TAG_SEPARATOR: &str = "::";
+
+/// Normalizes a single tag: trimmed, lower case, empty hierarchy levels removed.
+pub fn normalize_tag(raw: &str) -> Option<String> {
+    let parts: Vec<String> = raw
+        .split(TAG_SEPARATOR)
+        .map(|p| p.trim().to_lowercase())
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    (!parts.is_empty()).then(|| parts.join(TAG_SEPARATOR))
+}
+
+/// Parses a comma separated tag list, dropping empty entries and duplicates.
+pub fn parse_tags(raw: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for tag in raw.split(',').filter_map(normalize_tag) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+/// Comma joined form used by `Card::tags`, `None` if there are no tags.
+pub fn normalize_tags(raw: &str) -> Option<String> {
+    let tags = parse_tags(raw);
+
+    if tags.is_empty() {
+        None
+    } else {
+        Some(tags.join(","))
+    }
+}
+
+/// Whether `tag` is `filter` itself or one of its descendants.
+/// Unlike a substring match, `ml` does not match `html`.
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    tag == filter
+        || tag
+            .strip_prefix(filter)
+            .is_some_and(|rest| rest.starts_with(TAG_SEPARATOR))
+}
@@ -0,0 +1,335 @@
+// Checks the TeX of math blocks before MathJax sees it, so mistakes show up
+// in the editor instead of while studying. This is not a TeX parser: it
+// only checks that groups, delimiters and environments are closed in the
+// right order, which is what breaks rendering most of the time.
+
+use crate::models::Block;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TexError {
+    /// Offset in characters into the source.
+    pub position: usize,
+    /// 1-based, for showing to the user.
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// A problem in one math block of a card, reported when the card is saved.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct BlockTexError {
+    /// "front" or "back".
+    pub side: String,
+    /// Position of the block on its side, 0-based.
+    pub index: usize,
+    pub error: TexError,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Open {
+    Brace,
+    Left,
+    Environment(String),
+    Inline,
+    Display,
+    Dollar,
+    DoubleDollar,
+}
+
+impl Open {
+    fn describe(&self) -> String {
+        match self {
+            Open::Brace => "{".to_string(),
+            Open::Left => "\\left".to_string(),
+            Open::Environment(name) => format!("\\begin{{{name}}}"),
+            Open::Inline => "\\(".to_string(),
+            Open::Display => "\\[".to_string(),
+            Open::Dollar => "$".to_string(),
+            Open::DoubleDollar => "$$".to_string(),
+        }
+    }
+
+    fn closer(&self) -> String {
+        match self {
+            Open::Brace => "}".to_string(),
+            Open::Left => "\\right".to_string(),
+            Open::Environment(name) => format!("\\end{{{name}}}"),
+            Open::Inline => "\\)".to_string(),
+            Open::Display => "\\]".to_string(),
+            Open::Dollar => "$".to_string(),
+            Open::DoubleDollar => "$$".to_string(),
+        }
+    }
+}
+
+struct Checker {
+    chars: Vec<char>,
+    open: Vec<(Open, usize)>,
+    errors: Vec<TexError>,
+    delimiters: bool,
+}
+
+impl Checker {
+    fn error(&mut self, position: usize, message: String) {
+        let before = &self.chars[..position.min(self.chars.len())];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = position - before.iter().rposition(|&c| c == '\n').map_or(0, |i| i + 1) + 1;
+        self.errors.push(TexError { position, line, column, message });
+    }
+
+    /// `{name}` starting at `position`, for `\begin` and `\end`.
+    fn group_name(&self, position: usize) -> Option<(String, usize)> {
+        let mut i = position;
+        while self.chars.get(i).is_some_and(|c| c.is_whitespace()) {
+            i += 1;
+        }
+        if self.chars.get(i) != Some(&'{') {
+            return None;
+        }
+        let end = (i + 1..self.chars.len()).find(|&j| self.chars[j] == '}')?;
+        Some((self.chars[i + 1..end].iter().collect(), end + 1))
+    }
+
+    fn close(&mut self, expected: Open, position: usize, written: &str) {
+        match self.open.iter().rposition(|(open, _)| *open == expected) {
+            Some(index) => {
+                // everything opened after it is left open
+                for (open, at) in self.open.split_off(index + 1) {
+                    self.error(at, format!("{} is not closed before {written}", open.describe()));
+                }
+                self.open.pop();
+            }
+            None => {
+                let message = match self.open.last() {
+                    Some((open, _)) => format!("{written} does not match {}, expected {}", open.describe(), open.closer()),
+                    None => format!("{written} closes nothing"),
+                };
+                self.error(position, message);
+            }
+        }
+    }
+
+    /// `$` and `$$` open and close with the same token.
+    fn toggle(&mut self, token: Open, position: usize, written: &str) {
+        self.delimiters = true;
+        if self.open.iter().any(|(open, _)| *open == token) {
+            self.close(token, position, written);
+        } else {
+            self.open.push((token, position));
+        }
+    }
+
+    /// A `^` or `_` must be followed by something to raise or lower.
+    fn check_script(&mut self, position: usize) {
+        let next = self.chars[position + 1..].iter().find(|c| !c.is_whitespace());
+        if matches!(next, None | Some('}' | '^' | '_' | '$' | '&')) {
+            let script = self.chars[position];
+            self.error(position, format!("{script} is missing what to put there, e.g. {script}{{2}}"));
+        }
+    }
+
+    fn run(mut self) -> Vec<TexError> {
+        let mut i = 0;
+
+        while i < self.chars.len() {
+            let c = self.chars[i];
+            match c {
+                '%' => {
+                    // comment until the end of the line
+                    while i < self.chars.len() && self.chars[i] != '\n' {
+                        i += 1;
+                    }
+                    continue;
+                }
+                '{' => self.open.push((Open::Brace, i)),
+                '}' => self.close(Open::Brace, i, "}"),
+                '^' | '_' => self.check_script(i),
+                '$' if self.chars.get(i + 1) == Some(&'$') => {
+                    self.toggle(Open::DoubleDollar, i, "$$");
+                    i += 2;
+                    continue;
+                }
+                '$' => self.toggle(Open::Dollar, i, "$"),
+                '\\' => {
+                    let start = i;
+                    i += 1;
+                    let Some(&next) = self.chars.get(i) else {
+                        self.error(start, "\\ at the end, nothing to escape".to_string());
+                        break;
+                    };
+
+                    if !next.is_ascii_alphabetic() {
+                        match next {
+                            '(' => { self.delimiters = true; self.open.push((Open::Inline, start)) }
+                            ')' => self.close(Open::Inline, start, "\\)"),
+                            '[' => { self.delimiters = true; self.open.push((Open::Display, start)) }
+                            ']' => self.close(Open::Display, start, "\\]"),
+                            _ => {}
+                        }
+                        i += 1;
+                        continue;
+                    }
+
+                    while self.chars.get(i).is_some_and(|c| c.is_ascii_alphabetic()) {
+                        i += 1;
+                    }
+                    let word: String = self.chars[start + 1..i].iter().collect();
+
+                    match word.as_str() {
+                        "left" => self.open.push((Open::Left, start)),
+                        "right" => self.close(Open::Left, start, "\\right"),
+                        "begin" | "end" => match self.group_name(i) {
+                            Some((name, after)) => {
+                                if word == "begin" {
+                                    // MathJax also typesets environments outside of delimiters
+                                    self.delimiters = true;
+                                    self.open.push((Open::Environment(name), start));
+                                } else {
+                                    let written = format!("\\end{{{name}}}");
+                                    self.close(Open::Environment(name), start, &written);
+                                }
+                                i = after;
+                            }
+                            None => self.error(start, format!("\\{word} needs the name of an environment, e.g. \\{word}{{matrix}}")),
+                        },
+                        _ => {}
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        for (open, at) in std::mem::take(&mut self.open) {
+            self.error(at, format!("{} is never closed, expected {}", open.describe(), open.closer()));
+        }
+
+        if !self.delimiters && self.chars.iter().any(|c| !c.is_whitespace()) {
+            self.error(0, "No math delimiters, wrap the formula in $...$, $$...$$, \\(...\\) or \\[...\\]".to_string());
+        }
+
+        self.errors.sort_by_key(|error| error.position);
+        self.errors
+    }
+}
+
+/// Problems in the TeX of a math block, in the order they appear.
+pub fn validate_tex(source: &str) -> Vec<TexError> {
+    Checker {
+        chars: source.chars().collect(),
+        open: Vec::new(),
+        errors: Vec::new(),
+        delimiters: false,
+    }
+    .run()
+}
+
+/// Problems in the math blocks of one side of a card.
+pub fn math_block_errors<'a>(side: &str, blocks: impl IntoIterator<Item = &'a Block>) -> Vec<BlockTexError> {
+    blocks
+        .into_iter()
+        .enumerate()
+        .flat_map(|(index, block)| match block {
+            Block::Math { value } => validate_tex(value),
+            _ => Vec::new(),
+        }
+        .into_iter()
+        .map(move |error| BlockTexError { side: side.to_string(), index, error }))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(source: &str) -> Vec<(usize, String)> {
+        validate_tex(source).into_iter().map(|e| (e.position, e.message)).collect()
+    }
+
+    #[test]
+    fn valid_formulas() {
+        for source in [
+            "$x^2$",
+            "$$\\frac{a}{b}$$",
+            "\\(\\left( x \\right)\\)",
+            "\\[ a_{ij} \\]",
+            "\\begin{matrix} 1 & 2 \\\\ 3 & 4 \\end{matrix}",
+            "$a$ and $b$ % a comment with { and $",
+            "$\\{ x \\}$",
+            "",
+        ] {
+            assert_eq!(validate_tex(source), vec![], "{source}");
+        }
+    }
+
+    #[test]
+    fn unclosed_brace() {
+        assert_eq!(
+            messages("$\\frac{a}{b$"),
+            vec![(9, "{ is not closed before $".to_string())]
+        );
+    }
+
+    #[test]
+    fn closing_without_opening() {
+        assert_eq!(messages("$a}$"), vec![(2, "} does not match $, expected $".to_string())]);
+        assert_eq!(messages("$a$ \\)"), vec![(4, "\\) closes nothing".to_string())]);
+    }
+
+    #[test]
+    fn environments_must_match() {
+        assert_eq!(
+            messages("\\begin{matrix} 1 \\end{pmatrix}"),
+            vec![
+                (0, "\\begin{matrix} is never closed, expected \\end{matrix}".to_string()),
+                (17, "\\end{pmatrix} does not match \\begin{matrix}, expected \\end{matrix}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn left_needs_right() {
+        assert_eq!(
+            messages("$\\left( x$"),
+            vec![(1, "\\left is not closed before $".to_string())]
+        );
+    }
+
+    #[test]
+    fn scripts_need_an_argument() {
+        assert_eq!(messages("$x^$"), vec![(2, "^ is missing what to put there, e.g. ^{2}".to_string())]);
+        assert_eq!(messages("$x_ $"), vec![(2, "_ is missing what to put there, e.g. _{2}".to_string())]);
+    }
+
+    #[test]
+    fn trailing_backslash_and_missing_delimiters() {
+        assert_eq!(messages("$x$ \\"), vec![(4, "\\ at the end, nothing to escape".to_string())]);
+        assert_eq!(
+            messages("x^2"),
+            vec![(0, "No math delimiters, wrap the formula in $...$, $$...$$, \\(...\\) or \\[...\\]".to_string())]
+        );
+    }
+
+    #[test]
+    fn positions_have_lines_and_columns() {
+        let errors = validate_tex("$$\na + {b\n$$");
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (2, 5));
+    }
+
+    #[test]
+    fn errors_of_math_blocks_only() {
+        let blocks = [
+            Block::Text { value: "{".into() },
+            Block::Math { value: "$ok$".into() },
+            Block::Math { value: "${$".into() },
+        ];
+
+        let errors = math_block_errors("back", &blocks);
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].side.as_str(), errors[0].index), ("back", 2));
+    }
+}
@@ -0,0 +1,199 @@
+// Decks as text, for other apps: CSV in the format Anki imports (the fields
+// are HTML) and a Markdown document. Media files are not part of the text;
+// blocks using them are written with the file name only.
+
+use crate::markdown::{escape_html, render_markdown};
+use crate::models::{Block, Card};
+use crate::table;
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// The block as HTML of an Anki field.
+pub fn block_html(block: &Block) -> String {
+    match block {
+        Block::Text { value } => escape_html(value).replace('\n', "<br>"),
+        Block::Math { value } => format!("\\[{}\\]", escape_html(value)),
+        Block::Image { src } | Block::Occlusion { src, .. } => {
+            format!("<img src=\"{}\">", escape_html(file_name(src)))
+        }
+        Block::File { path } => escape_html(file_name(path)),
+        Block::Audio { src, .. } | Block::Video { src } => format!("[sound:{}]", file_name(src)),
+        // Anki uses the same `{{c1::...}}` syntax
+        Block::Cloze { value } => escape_html(value).replace('\n', "<br>"),
+        Block::Markdown { value } => render_markdown(value),
+        Block::Code { source, html, .. } if html.is_empty() => {
+            format!("<pre>{}</pre>", escape_html(source))
+        }
+        Block::Code { html, .. } => html.clone(),
+        Block::MultipleChoice { question, options, correct, .. } => {
+            let options: String = options
+                .iter()
+                .enumerate()
+                .map(|(i, option)| {
+                    let option = escape_html(option);
+                    if correct.contains(&i) {
+                        format!("<li><b>{option}</b></li>")
+                    } else {
+                        format!("<li>{option}</li>")
+                    }
+                })
+                .collect();
+            format!("{}<ul>{options}</ul>", escape_html(question))
+        }
+        Block::TypeAnswer { expected, .. } => escape_html(expected),
+        Block::Table { headers, rows } => table::to_html(headers, rows),
+    }
+}
+
+/// The block as Markdown.
+pub fn block_markdown(block: &Block) -> String {
+    match block {
+        Block::Text { value } | Block::Cloze { value } | Block::Markdown { value } => value.clone(),
+        Block::Math { value } => format!("$${value}$$"),
+        Block::Image { src } | Block::Occlusion { src, .. } => format!("![]({})", file_name(src)),
+        Block::File { path } => format!("[{0}]({0})", file_name(path)),
+        Block::Audio { src, .. } | Block::Video { src } => format!("[{0}]({0})", file_name(src)),
+        Block::Code { language, source, .. } => format!("```{language}\n{}\n```", source.trim_end()),
+        Block::MultipleChoice { question, options, correct, .. } => {
+            let mut lines = vec![question.clone(), String::new()];
+            lines.extend(options.iter().enumerate().map(|(i, option)| {
+                let mark = if correct.contains(&i) { "x" } else { " " };
+                format!("- [{mark}] {option}")
+            }));
+            lines.join("\n")
+        }
+        Block::TypeAnswer { expected, .. } => expected.clone(),
+        Block::Table { headers, rows } => table::to_markdown(headers, rows).trim_end().to_string(),
+    }
+}
+
+/// One line per card: front, back, tags and the `Parent::Child` deck, with
+/// the header lines Anki reads to import it without asking.
+pub fn to_anki_csv(cards: &[(String, Card)]) -> String {
+    let rows: Vec<Vec<String>> = cards
+        .iter()
+        .map(|(deck, card)| {
+            let field = |blocks: &[Block]| blocks.iter().map(block_html).collect::<Vec<_>>().join("<br>");
+            vec![
+                field(&card.front_blocks),
+                field(&card.back_blocks),
+                card.tag_list().join(" "),
+                deck.clone(),
+            ]
+        })
+        .collect();
+
+    let mut out = String::from("#separator:comma\n#html:true\n#tags column:3\n#deck column:4\n");
+    out.push_str(&table::to_csv(&[], &rows));
+    out
+}
+
+/// A heading per deck and per card, front and back separated by a rule.
+pub fn to_markdown_document(cards: &[(String, Card)]) -> String {
+    let mut out = String::new();
+    let mut current_deck = None;
+
+    for (deck, card) in cards {
+        if current_deck != Some(deck) {
+            out.push_str(&format!("# {deck}\n\n"));
+            current_deck = Some(deck);
+        }
+
+        out.push_str(&format!("## {}\n\n", card.name));
+        let tags = card.tag_list();
+        if !tags.is_empty() {
+            out.push_str(&format!("Tags: {}\n\n", tags.join(", ")));
+        }
+
+        for block in &card.front_blocks {
+            out.push_str(&format!("{}\n\n", block_markdown(block)));
+        }
+        out.push_str("---\n\n");
+        for block in &card.back_blocks {
+            out.push_str(&format!("{}\n\n", block_markdown(block)));
+        }
+    }
+
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(front: Vec<Block>, back: Vec<Block>, tags: &str) -> Card {
+        Card {
+            name: "Sorting".to_string(),
+            front_blocks: front,
+            back_blocks: back,
+            tags: Some(tags.to_string()),
+            ..Card::new_empty(1)
+        }
+    }
+
+    fn complexity_table() -> Block {
+        Block::Table {
+            headers: vec!["Algorithm".to_string(), "Time".to_string()],
+            rows: vec![vec!["Merge sort".to_string(), "$O(n \\log n)$".to_string()]],
+        }
+    }
+
+    #[test]
+    fn tables_are_html_in_anki_fields() {
+        let html = block_html(&complexity_table());
+        assert!(html.starts_with("<table><thead><tr><th>Algorithm</th><th>Time</th></tr></thead>"));
+        assert!(html.contains("<td>\\(O(n \\log n)\\)</td>"));
+    }
+
+    #[test]
+    fn tables_are_markdown_in_documents() {
+        assert_eq!(
+            block_markdown(&complexity_table()),
+            "| Algorithm | Time |\n| --- | --- |\n| Merge sort | $O(n \\log n)$ |"
+        );
+    }
+
+    #[test]
+    fn anki_csv_has_one_line_per_card() {
+        let cards = vec![(
+            "CS::Algorithms".to_string(),
+            card(
+                vec![Block::Text { value: "Complexity, \"big O\"".to_string() }],
+                vec![complexity_table()],
+                "cs,sorting",
+            ),
+        )];
+        let csv = to_anki_csv(&cards);
+
+        assert!(csv.starts_with("#separator:comma\n"));
+        assert_eq!(csv.lines().filter(|l| !l.starts_with('#')).count(), 1);
+        assert!(csv.contains("\"Complexity, &quot;big O&quot;\",<table>"));
+        assert!(csv.ends_with(",cs sorting,CS::Algorithms\n"));
+    }
+
+    #[test]
+    fn markdown_document_separates_front_and_back() {
+        let cards = vec![(
+            "CS".to_string(),
+            card(vec![Block::Math { value: "n^2".to_string() }], vec![complexity_table()], ""),
+        )];
+
+        assert_eq!(
+            to_markdown_document(&cards),
+            "# CS\n\n## Sorting\n\n$$n^2$$\n\n---\n\n\
+             | Algorithm | Time |\n| --- | --- |\n| Merge sort | $O(n \\log n)$ |\n\n"
+        );
+    }
+
+    #[test]
+    fn media_keeps_only_the_file_name() {
+        assert_eq!(block_html(&Block::Image { src: "files/a.png".to_string() }), "<img src=\"a.png\">");
+        assert_eq!(
+            block_html(&Block::Audio { src: "files/b.mp3".to_string(), autoplay: false }),
+            "[sound:b.mp3]"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::db::load_card;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use shared::models::Card;
+
+// Every deletion of a cloze card is scheduled on its own in `cloze_schedule`.
+// The card row keeps the totals of all its deletions and is due as soon as
+// one of them is, so deck counts, filters and sorting work as for any card.
+
+/// Scores and scheduling of one cloze deletion.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ClozeSchedule {
+    pub ordinal: u32,
+    pub times_seen: u32,
+    pub times_correct: u32,
+    pub due_at: Option<i64>,
+    pub interval_days: u32,
+}
+
+pub fn load_cloze_schedule(conn: &Connection, card_id: i64, ordinal: u32) -> Result<ClozeSchedule, String> {
+    let schedule = conn
+        .query_row(
+            "
+            SELECT times_seen, times_correct, due_at, interval_days FROM cloze_schedule
+            WHERE card_id = ?1 AND ordinal = ?2
+            ",
+            params![card_id, ordinal],
+            |row| {
+                Ok(ClozeSchedule {
+                    ordinal,
+                    times_seen: row.get(0)?,
+                    times_correct: row.get(1)?,
+                    due_at: row.get(2)?,
+                    interval_days: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    // never studied
+    Ok(schedule.unwrap_or(ClozeSchedule { ordinal, ..Default::default() }))
+}
+
+pub fn save_cloze_schedule_tx(conn: &Connection, card_id: i64, schedule: &ClozeSchedule) -> Result<(), String> {
+    conn.execute(
+        "
+        INSERT INTO cloze_schedule (card_id, ordinal, times_seen, times_correct, due_at, interval_days)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ON CONFLICT(card_id, ordinal) DO UPDATE SET
+            times_seen = excluded.times_seen,
+            times_correct = excluded.times_correct,
+            due_at = excluded.due_at,
+            interval_days = excluded.interval_days
+        ",
+        params![
+            card_id,
+            schedule.ordinal,
+            schedule.times_seen,
+            schedule.times_correct,
+            schedule.due_at,
+            schedule.interval_days
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// The card as the review item of one of its deletions.
+pub fn cloze_item(card: &Card, schedule: &ClozeSchedule) -> Card {
+    Card {
+        cloze: Some(schedule.ordinal),
+        times_seen: schedule.times_seen,
+        times_correct: schedule.times_correct,
+        due_at: schedule.due_at,
+        interval_days: schedule.interval_days,
+        ..card.clone()
+    }
+}
+
+/// Replaces every cloze card by one review item per deletion. Other cards
+/// are kept as they are.
+pub fn expand_cloze_cards(conn: &Connection, cards: Vec<Card>) -> Result<Vec<Card>, String> {
+    let mut items = Vec::with_capacity(cards.len());
+
+    for card in cards {
+        let ordinals = card.cloze_ordinals();
+        if ordinals.is_empty() {
+            items.push(card);
+            continue;
+        }
+
+        for ordinal in ordinals {
+            let schedule = load_cloze_schedule(conn, card.id, ordinal)?;
+            items.push(cloze_item(&card, &schedule));
+        }
+    }
+
+    Ok(items)
+}
+
+/// Drops the schedules of deletions that no longer exist and makes the card
+/// due when its earliest deletion is. Called whenever blocks are written.
+pub fn sync_card_schedule_tx(conn: &Connection, card_id: i64) -> Result<(), String> {
+    let card = load_card(conn, card_id)?;
+    let ordinals = card.cloze_ordinals();
+
+    let mut stmt = conn
+        .prepare("SELECT ordinal FROM cloze_schedule WHERE card_id = ?1")
+        .map_err(|e| e.to_string())?;
+    let stored: Vec<u32> = stmt
+        .query_map([card_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    for ordinal in stored.into_iter().filter(|o| !ordinals.contains(o)) {
+        conn.execute(
+            "DELETE FROM cloze_schedule WHERE card_id = ?1 AND ordinal = ?2",
+            params![card_id, ordinal],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    // a card that was never studied stays new until one deletion is
+    if ordinals.is_empty() || card.due_at.is_none() {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let mut due_at = i64::MAX;
+    let mut interval_days = u32::MAX;
+
+    for ordinal in ordinals {
+        let schedule = load_cloze_schedule(conn, card_id, ordinal)?;
+        // deletions never studied (e.g. added later) are due right away
+        due_at = due_at.min(schedule.due_at.unwrap_or(now));
+        interval_days = interval_days.min(schedule.interval_days);
+    }
+
+    conn.execute(
+        "UPDATE card SET due_at = ?1, interval_days = ?2 WHERE id = ?3",
+        params![due_at, interval_days, card_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub fn delete_cloze_schedules_tx(conn: &Connection, card_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM cloze_schedule WHERE card_id = ?1", [card_id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
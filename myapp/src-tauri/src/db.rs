@@ -1,20 +1,31 @@
 use tauri::path::BaseDirectory;
 use tauri::Manager;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::functions::FunctionFlags;
 use shared::models::*;
 use serde_json;
 use std::fs;
 use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
 use tauri_plugin_dialog::{ DialogExt, FileDialogBuilder, FilePath };
 use futures::channel::oneshot;
+use crate::trash::{trash_cards_tx, trash_deck_tx};
+use crate::undo::{record_undo, UndoAction};
+use crate::history::{record_revision_tx, last_saved_card};
+use crate::drafts::delete_card_drafts_tx;
+use crate::render::{card_math_errors, render_block_html};
+use crate::notes::prune_unused_notes;
+use crate::cloze::{
+    cloze_item, delete_cloze_schedules_tx, expand_cloze_cards, load_cloze_schedule,
+    save_cloze_schedule_tx, sync_card_schedule_tx, ClozeSchedule,
+};
 
 
-// TODO: Transaction support
-// Import/export currently calls add_deck / add_card / save_card_blocks,
-// each of which opens its own DB connection. This is correct but prevents
-// atomic imports. Future refactor: move SQL logic into *_tx helpers that
-// accept &Connection / &Transaction so multi-step operations can be
-// committed or rolled back as a single unit.
+// Transaction support
+// Multi-step operations (imports, merges) use the *_tx helpers below, which
+// accept &Connection / &Transaction so the whole operation can be committed
+// or rolled back as a single unit. The tauri commands open their own
+// connection and delegate to the same helpers where one exists.
 
 
 
@@ -30,7 +41,18 @@ pub fn open_db(app: &tauri::AppHandle) -> Result<Connection, String> {
 
     let db_path = app_data_dir.join("cards.db");
 
-    Connection::open(db_path).map_err(|e| e.to_string())
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    // progress(times_seen, times_correct) for search queries like `progress:<50`
+    conn.create_scalar_function(
+        "progress",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| Ok(progress_percent(ctx.get(0)?, ctx.get(1)?)),
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(conn)
 }
 
 
@@ -40,20 +62,9 @@ pub fn get_deck(app: tauri::AppHandle, deck_id: i64) -> Result<Deck, String> {
 
     let deck = conn
         .query_row(
-            "
-            SELECT id, name, created_at, card_count
-            FROM deck
-            WHERE id = ?
-            ",
+            &format!("SELECT {DECK_COLUMNS} FROM deck WHERE id = ?"),
             [deck_id],
-            |row| {
-                Ok(Deck {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    created_at: row.get(2)?,
-                    card_count: row.get(3)?,
-                })
-            },
+            deck_from_row,
         )
         .map_err(|e| e.to_string())?;
 
@@ -61,9 +72,75 @@ pub fn get_deck(app: tauri::AppHandle, deck_id: i64) -> Result<Deck, String> {
 }
 
 
+pub const DECK_COLUMNS: &str = "id, name, created_at, card_count, parent_id";
+
+pub fn deck_from_row(row: &rusqlite::Row) -> rusqlite::Result<Deck> {
+    Ok(Deck {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created_at: row.get(2)?,
+        card_count: row.get(3)?,
+        parent_id: row.get(4)?,
+        new_count: 0,
+        due_count: 0,
+        learned_count: 0,
+        inactive_count: 0,
+    })
+}
+
+pub fn child_deck_ids(conn: &Connection, deck_id: i64) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM deck WHERE parent_id = ?1 AND deleted_at IS NULL ORDER BY id")
+        .map_err(|e| e.to_string())?;
+
+    let ids = stmt
+        .query_map([deck_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(ids)
+}
+
+/// The deck itself followed by all of its descendants that are not in the trash.
+pub fn deck_subtree_ids(conn: &Connection, deck_id: i64) -> Result<Vec<i64>, String> {
+    subtree_ids(conn, deck_id, false)
+}
+
+/// Like `deck_subtree_ids`, but also walks into trashed sub-decks. Trash,
+/// restore and purge need these.
+pub fn deck_subtree_ids_with_trashed(conn: &Connection, deck_id: i64) -> Result<Vec<i64>, String> {
+    subtree_ids(conn, deck_id, true)
+}
+
+fn subtree_ids(conn: &Connection, deck_id: i64, with_trashed: bool) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare(
+            "
+            WITH RECURSIVE subtree(id) AS (
+                SELECT ?1
+                UNION
+                SELECT deck.id FROM deck JOIN subtree ON deck.parent_id = subtree.id
+                WHERE ?2 OR deck.deleted_at IS NULL
+            )
+            SELECT id FROM subtree
+            ",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let ids = stmt
+        .query_map(params![deck_id, with_trashed], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(ids)
+}
+
+
 #[tauri::command]
 pub fn init_db(app: tauri::AppHandle) -> Result<(), String> {
-    let conn = open_db(&app)?;
+    let mut conn = open_db(&app)?;
 
     conn.execute_batch(
         "
@@ -97,48 +174,177 @@ pub fn init_db(app: tauri::AppHandle) -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
-    Ok(())
+    crate::migrations::run(&mut conn)?;
+
+    crate::trash::purge_expired(&app)
 }
 
 #[tauri::command]
 pub fn add_deck(app: tauri::AppHandle, name: String) -> Result<i64, String> {
     let conn = open_db(&app)?;
+
+    // `Math::Linear Algebra` creates (or reuses) `Math` and adds the child
+    let mut path = split_deck_path(&name);
+    let Some(leaf) = path.pop() else {
+        return Err("Deck name must not be empty".into());
+    };
+
+    let parent_id = find_or_create_deck_path(&conn, None, &path)?;
+    insert_deck_tx(&conn, leaf, parent_id)
+}
+
+pub fn insert_deck_tx(
+    conn: &Connection,
+    name: &str,
+    parent_id: Option<i64>,
+) -> Result<i64, String> {
     let now = chrono::Utc::now().timestamp();
 
     conn.execute(
-        "INSERT INTO deck (name, created_at, card_count) VALUES (?1, ?2, ?3)",
-        params![name, now, 0],
+        "INSERT INTO deck (name, created_at, card_count, parent_id) VALUES (?1, ?2, ?3, ?4)",
+        params![name, now, 0, parent_id],
     )
     .map_err(|e| e.to_string())?;
 
     Ok(conn.last_insert_rowid())
 }
 
+pub fn find_child_deck(
+    conn: &Connection,
+    parent_id: Option<i64>,
+    name: &str,
+) -> Result<Option<i64>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id FROM deck
+             WHERE parent_id IS ?1 AND name = ?2 AND deleted_at IS NULL
+             ORDER BY id LIMIT 1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut rows = stmt
+        .query_map(params![parent_id, name], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    rows.next().transpose().map_err(|e| e.to_string())
+}
+
+/// Fails unless the deck exists and is not in the trash.
+pub fn ensure_deck_exists(conn: &Connection, deck_id: i64) -> Result<(), String> {
+    conn.query_row(
+        "SELECT id FROM deck WHERE id = ?1 AND deleted_at IS NULL",
+        [deck_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .map_err(|_| format!("No deck found with id {}", deck_id))?;
+
+    Ok(())
+}
+
+/// Walks a deck path down from `parent_id` (the top level for `None`),
+/// creating missing decks on the way. Returns the id of the last deck, or
+/// `parent_id` for an empty path.
+pub fn find_or_create_deck_path(
+    conn: &Connection,
+    parent_id: Option<i64>,
+    path: &[&str],
+) -> Result<Option<i64>, String> {
+    let mut parent_id = parent_id;
+
+    for segment in path {
+        let id = match find_child_deck(conn, parent_id, segment)? {
+            Some(id) => id,
+            None => insert_deck_tx(conn, segment, parent_id)?,
+        };
+        parent_id = Some(id);
+    }
+
+    Ok(parent_id)
+}
+
 #[tauri::command]
 pub fn get_decks(app: tauri::AppHandle) -> Result<Vec<Deck>, String> {
     let conn = open_db(&app)?;
+    let now = chrono::Utc::now().timestamp();
 
     let mut stmt = conn
-        .prepare("SELECT id, name, created_at, card_count FROM deck ORDER BY id DESC")
+        .prepare(&format!("SELECT {DECK_COLUMNS} FROM deck WHERE deleted_at IS NULL ORDER BY id DESC"))
         .map_err(|e| e.to_string())?;
 
-    let decks = stmt
-        .query_map([], |row| {
-            Ok(Deck {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                created_at: row.get(2)?,
-                card_count: row.get(3)?
-            })
+    let mut decks: Vec<Deck> = stmt
+        .query_map([], deck_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // card_count is kept up to date by triggers; the other counts depend on
+    // the current time and are computed here for the cards directly in each
+    // deck ...
+    let mut stmt = conn
+        .prepare(
+            "
+            SELECT deck_id,
+                   SUM(CASE WHEN active AND due_at IS NULL THEN 1 ELSE 0 END),
+                   SUM(CASE WHEN active AND due_at <= ?1 THEN 1 ELSE 0 END),
+                   SUM(CASE WHEN active AND due_at > ?1 THEN 1 ELSE 0 END),
+                   SUM(CASE WHEN active THEN 0 ELSE 1 END)
+            FROM (
+                SELECT deck_id, due_at,
+                       NOT suspended AND (buried_until IS NULL OR buried_until <= ?1) AS active
+                FROM card
+                WHERE deleted_at IS NULL
+            )
+            GROUP BY deck_id
+            ",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let direct: HashMap<i64, (u32, u32, u32, u32)> = stmt
+        .query_map([now], |row| {
+            Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))
         })
         .map_err(|e| e.to_string())?
         .collect::<Result<_, _>>()
         .map_err(|e| e.to_string())?;
 
+    // ... and added to the deck and every one of its ancestors
+    let parents: HashMap<i64, Option<i64>> = decks.iter().map(|d| (d.id, d.parent_id)).collect();
+    let index: HashMap<i64, usize> = decks.iter().enumerate().map(|(i, d)| (d.id, i)).collect();
+    let own: Vec<(i64, u32)> = decks.iter().map(|d| (d.id, d.card_count)).collect();
+
+    for deck in decks.iter_mut() {
+        deck.card_count = 0;
+    }
+
+    for (deck_id, card_count) in own {
+        let (new, due, learned, inactive) = direct.get(&deck_id).copied().unwrap_or_default();
+        let mut current = Some(deck_id);
+        let mut hops = 0;
+
+        while let Some(id) = current {
+            if let Some(&i) = index.get(&id) {
+                let deck = &mut decks[i];
+                deck.card_count += card_count;
+                deck.new_count += new;
+                deck.due_count += due;
+                deck.learned_count += learned;
+                deck.inactive_count += inactive;
+            }
+            current = parents.get(&id).copied().flatten();
+
+            hops += 1;
+            if hops > parents.len() {
+                break; // defensive: never loop on a broken parent chain
+            }
+        }
+    }
+
     Ok(decks)
 }
 
 
+/// Renames a deck. A name containing `::` moves the deck as well:
+/// `Math::Algebra` puts it below `Math`, `::Algebra` moves it to the top level.
 #[tauri::command]
 pub fn rename_deck(
     app: tauri::AppHandle,
@@ -147,11 +353,36 @@ pub fn rename_deck(
 ) -> Result<(), String> {
     let conn = open_db(&app).map_err(|e| e.to_string())?;
 
+    if !name.contains(DECK_PATH_SEPARATOR) {
+        conn.execute(
+            "UPDATE deck
+             SET name = ?1
+             WHERE id = ?2",
+            rusqlite::params![name, deck_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        return Ok(());
+    }
+
+    let mut path = split_deck_path(&name);
+    let Some(leaf) = path.pop() else {
+        return Err("Deck name must not be empty".into());
+    };
+
+    let parent_id = find_or_create_deck_path(&conn, None, &path)?;
+
+    if let Some(parent_id) = parent_id {
+        if deck_subtree_ids(&conn, deck_id)?.contains(&parent_id) {
+            return Err("A deck cannot be moved into its own sub-deck".into());
+        }
+    }
+
     conn.execute(
         "UPDATE deck
-         SET name = ?1
-         WHERE id = ?2",
-        rusqlite::params![name, deck_id],
+         SET name = ?1, parent_id = ?2
+         WHERE id = ?3",
+        rusqlite::params![leaf, parent_id, deck_id],
     )
     .map_err(|e| e.to_string())?;
 
@@ -168,86 +399,119 @@ pub fn add_card(app: tauri::AppHandle, deck_id: i64, name: String) -> Result<i64
     let now = chrono::Utc::now().timestamp();
 
     conn.execute(
-        "INSERT INTO card (deck_id, name, created_at)
-         VALUES (?1, ?2, ?3)",
-        params![deck_id, name, now],
+        "INSERT INTO card (deck_id, uid, name, created_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![deck_id, new_card_uid(), name, now],
     )
     .map_err(|e| e.to_string())?;
 
-    // TODO: 
-    // increment the deck card count by 1
+    // deck.card_count is incremented by the card_count_insert trigger
 
     Ok(conn.last_insert_rowid())
 }
 
 
 
-// for the import function
-pub fn restore_card_metadata(
-    app: &tauri::AppHandle,
-    card_id: i64,
-    created_at: i64,
-    times_seen: u32,
-    times_correct: u32,
-    tags: Option<String>,
-) -> Result<(), String> {
-    let conn = open_db(app)?;
+pub fn new_card_uid() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
 
+
+// Card rows are read in several places, always with these columns in this order.
+// Tags come from the tag tables, joined into the comma separated form.
+pub const CARD_COLUMNS: &str = "
+    card.id, card.deck_id, card.uid, card.name, card.created_at,
+    card.times_seen, card.times_correct,
+    (SELECT group_concat(name, ',') FROM (
+        SELECT tag.name FROM card_tag JOIN tag ON tag.id = card_tag.tag_id
+        WHERE card_tag.card_id = card.id
+        ORDER BY tag.name
+    )),
+    card.due_at, card.interval_days, card.suspended, card.buried_until, card.version,
+    card.note_id";
+
+pub fn card_from_row(row: &rusqlite::Row) -> rusqlite::Result<Card> {
+    Ok(Card {
+        id: row.get(0)?,
+        deck_id: row.get(1)?,
+        uid: row.get(2)?,
+        name: row.get(3)?,
+        created_at: row.get(4)?,
+        times_seen: row.get(5)?,
+        times_correct: row.get(6)?,
+        tags: row.get(7)?,
+        due_at: row.get(8)?,
+        interval_days: row.get(9)?,
+        suspended: row.get(10)?,
+        buried_until: row.get(11)?,
+        version: row.get(12)?,
+        note_id: row.get(13)?,
+        cloze: None,
+        front_blocks: vec![],
+        back_blocks: vec![],
+    })
+}
+
+
+/// Inserts a complete card (metadata + blocks) into `deck_id`.
+/// Used by the import, which must not reuse ids from the archive.
+pub fn insert_card_tx(
+    conn: &Connection,
+    deck_id: i64,
+    card: &Card,
+) -> Result<i64, String> {
     // defensive fix: avoid underflow later
-    let seen = times_seen.max(times_correct);
+    let seen = card.times_seen.max(card.times_correct);
+
+    let uid = if card.uid.is_empty() { new_card_uid() } else { card.uid.clone() };
 
     conn.execute(
-        r#"
-        UPDATE card
-        SET
-            created_at = ?1,
-            times_seen = ?2,
-            times_correct = ?3,
-            tags = ?4
-        WHERE id = ?5
-        "#,
+        "
+        INSERT INTO card (
+            deck_id, uid, name, created_at, times_seen, times_correct,
+            due_at, interval_days, suspended, buried_until
+        )
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        ",
         params![
-            created_at,
+            deck_id,
+            uid,
+            card.name,
+            card.created_at,
             seen,
-            times_correct,
-            tags,
-            card_id
+            card.times_correct,
+            card.due_at,
+            card.interval_days,
+            card.suspended,
+            card.buried_until
         ],
     )
     .map_err(|e| e.to_string())?;
 
-    Ok(())
-}
+    let card_id = conn.last_insert_rowid();
+    set_card_tags_tx(conn, card_id, &card.tag_list())?;
+    write_blocks_tx(conn, card_id, &card.front_blocks, &card.back_blocks)?;
+    record_revision_tx(conn, card_id)?;
 
+    Ok(card_id)
+}
 
 
 #[tauri::command]
 pub fn get_cards(app: tauri::AppHandle, deck_id: i64) -> Result<Vec<Card>, String> {
     let conn = open_db(&app)?;
 
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare(&format!(
         "
-        SELECT id, deck_id, name, created_at, times_seen, times_correct, tags
+        SELECT {CARD_COLUMNS}
         FROM card
-        WHERE deck_id = ?
+        WHERE deck_id = ? AND deleted_at IS NULL
         ORDER BY created_at DESC
         "
-    ).map_err(|e| e.to_string())?;
+    )).map_err(|e| e.to_string())?;
 
-    let rows = stmt.query_map([deck_id], |row| {
-        Ok(Card {
-            id: row.get(0)?,
-            deck_id: row.get(1)?,
-            name: row.get(2)?,
-            created_at: row.get(3)?,
-            times_seen: row.get(4)?,
-            times_correct: row.get(5)?,
-            tags: row.get(6)?,
-            front_blocks: vec![],
-            back_blocks: vec![],
-        })
-    })
-    .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([deck_id], card_from_row)
+        .map_err(|e| e.to_string())?;
 
     let mut cards = Vec::new();
     for card in rows {
@@ -260,113 +524,210 @@ pub fn get_cards(app: tauri::AppHandle, deck_id: i64) -> Result<Vec<Card>, Strin
 #[tauri::command]
 pub fn get_card(app: tauri::AppHandle, id: i64) -> Result<Card, String> {
     let conn = open_db(&app)?;
+    load_card(&conn, id)
+}
 
+pub fn load_card(conn: &Connection, id: i64) -> Result<Card, String> {
     // load card
     let card = conn.query_row(
-        "
-        SELECT id, deck_id, name, created_at, times_seen, times_correct, tags
-        FROM card
-        WHERE id = ?
-        ",
+        &format!("SELECT {CARD_COLUMNS} FROM card WHERE id = ?"),
         [id],
-        |row| {
-            Ok(Card {
-                id: row.get(0)?,
-                deck_id: row.get(1)?,
-                name: row.get(2)?,
-                created_at: row.get(3)?,
-                times_seen: row.get(4)?,
-                times_correct: row.get(5)?,
-                tags: row.get(6)?,
-                front_blocks: vec![],
-                back_blocks: vec![],
-            })
-        },
+        card_from_row,
     )
     .map_err(|e| e.to_string())?;
 
-    let mut front = Vec::<Block>::new();
-    let mut back = Vec::<Block>::new();
+    let blocks = load_card_blocks(conn, id)?;
 
+    Ok(Card {
+        front_blocks: blocks.front.into_iter().map(|b| b.block).collect(),
+        back_blocks: blocks.back.into_iter().map(|b| b.block).collect(),
+        ..card
+    })
+}
+
+/// The blocks of a card in order, with their row ids.
+pub fn load_card_blocks(conn: &Connection, card_id: i64) -> Result<CardBlocks, String> {
     let mut stmt = conn.prepare(
         "
-        SELECT side, block_type, content
+        SELECT id, side, content
         FROM block
         WHERE card_id = ?
         ORDER BY position ASC
         "
     ).map_err(|e| e.to_string())?;
 
-    let rows = stmt.query_map([id], |row| {
-        let side: String = row.get(0)?;
+    let rows = stmt.query_map([card_id], |row| {
+        let id: i64 = row.get(0)?;
+        let side: String = row.get(1)?;
         let content: String = row.get(2)?; //retrieves exactly this string, byte-for-byte: {"type":"Text","value":"Transform each sentence...\n\nYou finish..."}
 
         let block: Block = serde_json::from_str(&content).unwrap(); // When deserialized, it becomes: Block::Text { value: "Hello" } and this is based on the tag. So the Tag decides what block tyoe the string gets serialized into :=)
-        Ok((side, block))
+        Ok((side, EditorBlock { id: Some(id), block }))
     }).map_err(|e| e.to_string())?;
 
+    let mut blocks = CardBlocks::default();
     for row in rows {
         let (side, block) = row.map_err(|e| e.to_string())?;
         if side == "front" {
-            front.push(block);
+            blocks.front.push(block);
         } else {
-            back.push(block);
+            blocks.back.push(block);
         }
     }
 
-    Ok(Card {
-        front_blocks: front,
-        back_blocks: back,
-        ..card
-    })
+    Ok(blocks)
+}
+
+#[tauri::command]
+pub fn get_card_blocks(app: tauri::AppHandle, card_id: i64) -> Result<CardBlocks, String> {
+    let conn = open_db(&app)?;
+    load_card_blocks(&conn, card_id)
 }
 
+/// Saves the blocks from the editor. Math blocks with broken TeX are saved
+/// as well, their problems are reported back.
 #[tauri::command]
 pub fn save_card_blocks(
     app: tauri::AppHandle,
     card_id: i64,
-    front: Vec<Block>,
-    back: Vec<Block>,
-) -> Result<(), String> {
+    front: Vec<EditorBlock>,
+    back: Vec<EditorBlock>,
+) -> Result<SaveReport, String> {
 
-    let conn = open_db(&app)?;
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    conn.execute("DELETE FROM block WHERE card_id = ?", [card_id])
-        .map_err(|e| e.to_string())?;
+    apply_blocks_tx(&tx, card_id, &front, &back)?;
+    record_revision_tx(&tx, card_id)?;
 
-    for (i, block) in front.iter().enumerate() {
-        conn.execute(
-            "
-            INSERT INTO block (card_id, side, position, block_type, content)
-            VALUES (?1, 'front', ?2, ?3, ?4)
-            ",
-            params![
-                card_id,
-                i as i64,
-                block.block_type(),
-                serde_json::to_string(block).unwrap()
-            ],
-        )
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(SaveReport { conflict: None, math_errors: card_math_errors(&front, &back) })
+}
+
+/// Autosave of a single block while the card is being edited. Does not
+/// record a revision; that happens when the card is saved.
+#[tauri::command]
+pub fn save_block(app: tauri::AppHandle, card_id: i64, id: i64, block: Block) -> Result<(), String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let owner: Option<i64> = tx
+        .query_row("SELECT card_id FROM block WHERE id = ?1", [id], |row| row.get(0))
+        .optional()
         .map_err(|e| e.to_string())?;
+
+    if owner != Some(card_id) {
+        return Err(format!("No block found with id {} on card {}", id, card_id));
     }
 
-    for (i, block) in back.iter().enumerate() {
-        conn.execute(
-            "
-            INSERT INTO block (card_id, side, position, block_type, content)
-            VALUES (?1, 'back', ?2, ?3, ?4)
-            ",
-            params![
-                card_id,
-                i as i64,
-                block.block_type(),
-                serde_json::to_string(block).unwrap() // Becomes json because the block enum has serialze
-            ],
-        )
+    let block = render_block_html(&block);
+    tx.execute(
+        "UPDATE block SET block_type = ?1, content = ?2 WHERE id = ?3",
+        params![block.block_type(), serde_json::to_string(&block).map_err(|e| e.to_string())?, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // the block may have gained or lost cloze deletions
+    sync_card_schedule_tx(&tx, card_id)?;
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Brings the card's block rows in line with `front` and `back`: rows missing
+/// from the lists are deleted, known ids are updated (content and position)
+/// only if something changed, blocks without an id are inserted.
+pub fn apply_blocks_tx(
+    conn: &Connection,
+    card_id: i64,
+    front: &[EditorBlock],
+    back: &[EditorBlock],
+) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare("SELECT id, side, position, content FROM block WHERE card_id = ?1")
         .map_err(|e| e.to_string())?;
+
+    let existing: HashMap<i64, (String, i64, String)> = stmt
+        .query_map([card_id], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?))))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut kept = HashSet::new();
+
+    for (side, blocks) in [("front", front), ("back", back)] {
+        for (i, editor_block) in blocks.iter().enumerate() {
+            let block = &render_block_html(&editor_block.block);
+            let content = serde_json::to_string(block).map_err(|e| e.to_string())?; // Becomes json because the block enum has serialze
+            let row = (side.to_string(), i as i64, content);
+
+            // an id of another card (or one listed twice) is treated as a new block
+            let known = editor_block
+                .id
+                .filter(|id| existing.contains_key(id) && kept.insert(*id));
+
+            match known {
+                Some(id) if existing[&id] == row => {}
+                Some(id) => {
+                    conn.execute(
+                        "
+                        UPDATE block SET side = ?1, position = ?2, block_type = ?3, content = ?4
+                        WHERE id = ?5
+                        ",
+                        params![row.0, row.1, block.block_type(), row.2, id],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+                None => {
+                    conn.execute(
+                        "
+                        INSERT INTO block (card_id, side, position, block_type, content)
+                        VALUES (?1, ?2, ?3, ?4, ?5)
+                        ",
+                        params![card_id, row.0, row.1, block.block_type(), row.2],
+                    )
+                    .map_err(|e| e.to_string())?;
+                }
+            }
+        }
     }
 
-    Ok(())
+    for id in existing.keys().filter(|id| !kept.contains(id)) {
+        conn.execute("DELETE FROM block WHERE id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    // cloze deletions may have been added or removed
+    sync_card_schedule_tx(conn, card_id)
+}
+
+/// Replaces the blocks of a card with plain blocks (import, undo, restoring
+/// a revision). Existing rows are reused by position.
+pub fn write_blocks_tx(
+    conn: &Connection,
+    card_id: i64,
+    front: &[Block],
+    back: &[Block],
+) -> Result<(), String> {
+    let existing = load_card_blocks(conn, card_id)?;
+
+    let with_ids = |blocks: &[Block], rows: &[EditorBlock]| -> Vec<EditorBlock> {
+        blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| EditorBlock {
+                id: rows.get(i).and_then(|r| r.id),
+                block: block.clone(),
+            })
+            .collect()
+    };
+
+    apply_blocks_tx(
+        conn,
+        card_id,
+        &with_ids(front, &existing.front),
+        &with_ids(back, &existing.back),
+    )
 }
 
 
@@ -378,41 +739,91 @@ pub fn update_card_metadata(
     name: String,
     tags: Option<String>,
 ) -> Result<(), String> {
-    let conn = open_db(&app)?;
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    conn.execute(
+    tx.execute(
         "UPDATE card
-         SET name = ?1,
-             tags = ?2
-         WHERE id = ?3",
-        rusqlite::params![name, tags, id],
+         SET name = ?1
+         WHERE id = ?2",
+        rusqlite::params![name, id],
     )
     .map_err(|e| e.to_string())?;
 
-    Ok(())
-}
+    set_card_tags_tx(&tx, id, &shared::tags::parse_tags(tags.as_deref().unwrap_or_default()))?;
 
+    tx.commit().map_err(|e| e.to_string())
+}
 
+/// Saves the whole card from the editor in one step, so the edit can be
+/// undone as a unit.
+///
+/// If `base_version` is given and the card has changed since (in another
+/// window, by an import, ...), nothing is saved and the current card is
+/// returned as the conflict so the user can decide. `None` saves
+/// unconditionally. Problems in the TeX of math blocks do not stop the save,
+/// they are reported back.
 #[tauri::command]
-pub async fn delete_block_from_app_data(
+pub fn save_card(
     app: tauri::AppHandle,
-    virtual_path: String,
-) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
+    id: i64,
+    name: String,
+    tags: Option<String>,
+    front: Vec<EditorBlock>,
+    back: Vec<EditorBlock>,
+    base_version: Option<i64>,
+) -> Result<SaveReport, String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let current = load_card(&tx, id)?;
+    if base_version.is_some_and(|v| v != current.version) {
+        return Ok(SaveReport { conflict: Some(current), math_errors: Vec::new() });
+    }
+
+    // Blocks may have been autosaved already; undo goes back to the last save
+    let before = last_saved_card(&tx, id)?;
+
+    tx.execute("UPDATE card SET name = ?1 WHERE id = ?2", params![name, id])
         .map_err(|e| e.to_string())?;
 
-    let full_path = app_data_dir.join(&virtual_path);
+    set_card_tags_tx(&tx, id, &shared::tags::parse_tags(tags.as_deref().unwrap_or_default()))?;
+    apply_blocks_tx(&tx, id, &front, &back)?;
+    record_revision_tx(&tx, id)?;
 
-    if !full_path.exists() {
-        return Ok(());
+    record_undo(&tx, &format!("Edit \"{}\"", before.name), &UndoAction::EditCard { card: before })?;
+    delete_card_drafts_tx(&tx, id)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(SaveReport { conflict: None, math_errors: card_math_errors(&front, &back) })
+}
+
+/// Replaces the tags of a card. Tags no card uses anymore are removed.
+pub fn set_card_tags_tx(conn: &Connection, card_id: i64, tags: &[String]) -> Result<(), String> {
+    conn.execute("DELETE FROM card_tag WHERE card_id = ?1", [card_id])
+        .map_err(|e| e.to_string())?;
+
+    for tag in tags {
+        conn.execute("INSERT OR IGNORE INTO tag (name) VALUES (?1)", [tag])
+            .map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT OR IGNORE INTO card_tag (card_id, tag_id)
+             SELECT ?1, id FROM tag WHERE name = ?2",
+            params![card_id, tag],
+        )
+        .map_err(|e| e.to_string())?;
     }
 
-    println!("Deleting the file {:#?}", full_path);
+    prune_unused_tags(conn)
+}
 
-    std::fs::remove_file(&full_path)
-        .map_err(|e| format!("Failed to delete {:?}: {}", full_path, e))?;
+pub fn prune_unused_tags(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM tag WHERE id NOT IN (SELECT tag_id FROM card_tag)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -420,7 +831,7 @@ pub async fn delete_block_from_app_data(
 
 // Delete operations
 
-fn delete_file_from_app_data(
+pub fn delete_file_from_app_data(
     app: &tauri::AppHandle,
     virtual_path: &str,
 ) -> Result<(), String> {
@@ -436,69 +847,61 @@ fn delete_file_from_app_data(
         return Ok(());
     }
 
-    println!("Deleting the file {:#?}", full_path);
-
     std::fs::remove_file(&full_path)
         .map_err(|e| format!("Failed to delete {:?}: {}", full_path, e))?;
 
     Ok(())
 }
 
+/// Moves a deck, its sub-decks and all of their cards to the trash.
 #[tauri::command]
 pub async fn delete_deck(
     app: tauri::AppHandle,
     deck_id: i64,
 ) -> Result<(), String> {
-    let conn = open_db(&app)?;
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    conn.execute("PRAGMA foreign_keys = ON;", [])
-        .map_err(|e| e.to_string())?;
-
-    // 1. Fetch all card ids in this deck
-    let mut stmt = conn
-        .prepare("SELECT id FROM card WHERE deck_id = ?1")
-        .map_err(|e| e.to_string())?;
-
-    let card_ids: Vec<i64> = stmt
-        .query_map([deck_id], |row| row.get(0))
-        .map_err(|e| e.to_string())?
-        .filter_map(Result::ok)
-        .collect();
-
-    // 2. Delete cards one-by-one (this deletes files!)
-    for card_id in card_ids {
-        delete_card(app.clone(), card_id)?;
-    }
-
-    // 3. Delete the deck itself
-    let affected = conn.execute(
-        "DELETE FROM deck WHERE id = ?1",
-        [deck_id],
-    )
-    .map_err(|e| e.to_string())?;
+    let deck = tx
+        .query_row(
+            &format!("SELECT {DECK_COLUMNS} FROM deck WHERE id = ?1 AND deleted_at IS NULL"),
+            [deck_id],
+            deck_from_row,
+        )
+        .map_err(|_| format!("No deck found with id {}", deck_id))?;
 
-    if affected == 0 {
-        return Err(format!("No deck found with id {}", deck_id));
-    }
+    trash_deck_tx(&tx, deck_id, chrono::Utc::now().timestamp())?;
+    record_undo(&tx, &format!("Delete deck \"{}\"", deck.name), &UndoAction::DeleteDeck { deck_id })?;
 
-    Ok(())
+    tx.commit().map_err(|e| e.to_string())
 }
 
 
+/// Moves a card to the trash. Its media files stay until the trash is purged.
 #[tauri::command]
 pub fn delete_card(app: tauri::AppHandle, id: i64) -> Result<(), String> {
-    let conn = open_db(&app)?;
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    conn.execute("PRAGMA foreign_keys = ON;", [])
-        .map_err(|e| e.to_string())?;
+    let name: String = tx
+        .query_row("SELECT name FROM card WHERE id = ?1 AND deleted_at IS NULL", [id], |row| row.get(0))
+        .map_err(|_| format!("No card found with id {}", id))?;
+
+    // (deck.card_count is decremented by the card_count_trash trigger)
+    trash_cards_tx(&tx, &[id], chrono::Utc::now().timestamp())?;
+    record_undo(&tx, &format!("Delete \"{}\"", name), &UndoAction::DeleteCards { card_ids: vec![id] })?;
 
-    // Collect file paths
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Virtual paths of all media files referenced by a card's blocks.
+pub fn card_file_paths(conn: &Connection, card_id: i64) -> Result<Vec<String>, String> {
     let mut stmt = conn
         .prepare("SELECT content FROM block WHERE card_id = ?1")
         .map_err(|e| e.to_string())?;
 
     let blocks: Vec<Block> = stmt
-        .query_map(rusqlite::params![id], |row| {
+        .query_map(rusqlite::params![card_id], |row| {
             let content: String = row.get(0)?;
             let block: Block = serde_json::from_str(&content)
                 .map_err(|e| rusqlite::Error::FromSqlConversionFailure(
@@ -512,21 +915,27 @@ pub fn delete_card(app: tauri::AppHandle, id: i64) -> Result<(), String> {
         .filter_map(Result::ok)
         .collect();
 
-    for block in &blocks {
-        match block {
-            Block::Image { src } => {
-                delete_file_from_app_data(&app, src)?;
-            }
-            Block::File { path } => {
-                delete_file_from_app_data(&app, path)?;
-            }
-            _ => {
-                // Text / Math → nothing to delete
-            }
-        }
-    }
+    // Text / Math → nothing to delete
+    Ok(blocks
+        .iter()
+        .filter_map(|b| b.file_path().map(str::to_string))
+        .collect())
+}
+
+/// Deletes the card row; blocks cascade. Media files are left to the caller,
+/// which should only remove them once the surrounding transaction committed.
+pub fn delete_card_tx(conn: &Connection, id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM block WHERE card_id = ?1", rusqlite::params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM card_tag WHERE card_id = ?1", rusqlite::params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM review_log WHERE card_id = ?1", rusqlite::params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM card_revision WHERE card_id = ?1", rusqlite::params![id])
+        .map_err(|e| e.to_string())?;
+    delete_card_drafts_tx(conn, id)?;
+    delete_cloze_schedules_tx(conn, id)?;
 
-    // Delete card (blocks cascade)
     let affected = conn.execute(
         "DELETE FROM card WHERE id = ?1",
         rusqlite::params![id],
@@ -537,10 +946,8 @@ pub fn delete_card(app: tauri::AppHandle, id: i64) -> Result<(), String> {
         return Err(format!("No card found with id {}", id));
     }
 
-    // TODO: 
-    // decrement the deck card count by 1
-
-    Ok(())
+    prune_unused_notes(conn)?;
+    prune_unused_tags(conn)
 }
 
 
@@ -580,28 +987,131 @@ pub async fn download_file(
 }
 
 
+/// Records an answer. For a cloze card, `cloze` is the deletion that was
+/// asked; it is scheduled on its own, the card only keeps the totals.
 #[tauri::command]
 pub async fn update_score(
     app: tauri::AppHandle,
     card_id: i64,
     correct: bool,
+    cloze: Option<u32>,
 ) -> Result<Card, String> {
-    let conn = open_db(&app)?;
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
     let correct_inc = if correct { 1 } else { 0 };
 
-    conn.execute(
+    // Cards answered wrong come back after a few minutes, the others after
+    // their (doubled) interval.
+    let card = load_card(&tx, card_id)?;
+    let cloze_before = cloze
+        .map(|ordinal| load_cloze_schedule(&tx, card_id, ordinal))
+        .transpose()?;
+    let item = match &cloze_before {
+        Some(schedule) => cloze_item(&card, schedule),
+        None => card.clone(),
+    };
+
+    let interval = item.next_interval(correct);
+    let now = chrono::Utc::now().timestamp();
+    let due_at = if interval == 0 {
+        now + RELEARN_DELAY_SECS
+    } else {
+        now + interval as i64 * 86_400
+    };
+
+    tx.execute(
         "
         UPDATE card
         SET
             times_seen = times_seen + 1,
-            times_correct = times_correct + ?
+            times_correct = times_correct + ?,
+            interval_days = ?,
+            due_at = ?
         WHERE id = ?;
         ",
-        (correct_inc, card_id),
+        (correct_inc, interval, due_at, card_id),
     )
     .map_err(|e| e.to_string())?;
 
+    if let Some(schedule) = &cloze_before {
+        let after = ClozeSchedule {
+            ordinal: schedule.ordinal,
+            times_seen: schedule.times_seen + 1,
+            times_correct: schedule.times_correct + correct_inc,
+            due_at: Some(due_at),
+            interval_days: interval,
+        };
+        save_cloze_schedule_tx(&tx, card_id, &after)?;
+        // the card is due again with its earliest deletion
+        sync_card_schedule_tx(&tx, card_id)?;
+    }
+
+    tx.execute(
+        "INSERT INTO review_log (card_id, reviewed_at, correct) VALUES (?1, ?2, ?3)",
+        params![card_id, now, correct],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let undo = UndoAction::Grade {
+        card_id,
+        times_seen: card.times_seen,
+        times_correct: card.times_correct,
+        due_at: card.due_at,
+        interval_days: card.interval_days,
+        review_id: tx.last_insert_rowid(),
+        cloze: cloze_before,
+    };
+    record_undo(&tx, &format!("Answer \"{}\"", card.name), &undo)?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
     // Return the updated card
     get_card(app, card_id)
 }
+
+
+const RELEARN_DELAY_SECS: i64 = 10 * 60;
+
+/// Due cards of a deck and all of its sub-decks: overdue cards first
+/// (oldest due date first), then cards that were never studied.
+/// Suspended, buried and trashed cards are left out. Cloze cards are listed
+/// once per due deletion.
+#[tauri::command]
+pub fn get_study_queue(app: tauri::AppHandle, deck_id: i64) -> Result<Vec<Card>, String> {
+    let conn = open_db(&app)?;
+    let now = chrono::Utc::now().timestamp();
+
+    let mut stmt = conn
+        .prepare(
+            "
+            SELECT id FROM card
+            WHERE deck_id = ?1 AND deleted_at IS NULL AND (due_at IS NULL OR due_at <= ?2)
+              AND NOT suspended AND (buried_until IS NULL OR buried_until <= ?2)
+            ",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut cards = Vec::new();
+    for id in deck_subtree_ids(&conn, deck_id)? {
+        let ids: Vec<i64> = stmt
+            .query_map(params![id, now], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+
+        for card_id in ids {
+            cards.push(load_card(&conn, card_id)?);
+        }
+    }
+
+    let mut cards = expand_cloze_cards(&conn, cards)?;
+    cards.retain(|c| c.is_due(now));
+    sort_study_queue(&mut cards);
+    Ok(cards)
+}
+
+/// Overdue cards first (oldest due date first), then never studied cards.
+pub fn sort_study_queue(cards: &mut [Card]) {
+    cards.sort_by_key(|c| (c.due_at.is_none(), c.due_at, c.created_at));
+}
@@ -0,0 +1,177 @@
+use crate::db::open_db;
+use rusqlite::{params, Connection, OptionalExtension};
+use shared::models::{CardBlocks, Draft};
+
+// The card editor autosaves its state here while typing, so nothing is lost
+// if the app is closed before the card is saved. A card has at most one
+// draft; drafts of new cards (no card_id yet) are addressed by their id.
+
+const DRAFT_COLUMNS: &str = "id, card_id, deck_id, base_version, name, tags, blocks, updated_at";
+
+fn draft_from_row(row: &rusqlite::Row) -> rusqlite::Result<Draft> {
+    let blocks: String = row.get(6)?;
+    let blocks: CardBlocks = serde_json::from_str(&blocks).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+
+    Ok(Draft {
+        id: row.get(0)?,
+        card_id: row.get(1)?,
+        deck_id: row.get(2)?,
+        base_version: row.get(3)?,
+        name: row.get(4)?,
+        tags: row.get(5)?,
+        blocks,
+        updated_at: row.get(7)?,
+    })
+}
+
+pub fn delete_card_drafts_tx(conn: &Connection, card_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM draft WHERE card_id = ?1", [card_id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Virtual paths of the media files used by drafts, so purging the trash
+/// does not delete files a draft still needs.
+pub fn draft_file_paths(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {DRAFT_COLUMNS} FROM draft"))
+        .map_err(|e| e.to_string())?;
+
+    let paths = stmt
+        .query_map([], draft_from_row)
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .flat_map(|d| d.blocks.front.into_iter().chain(d.blocks.back))
+        .filter_map(|b| b.block.file_path().map(str::to_string))
+        .collect();
+
+    Ok(paths)
+}
+
+
+// =======================
+// Tauri commands
+// =======================
+
+/// Stores the draft and returns its id.
+#[tauri::command]
+pub fn save_draft(app: tauri::AppHandle, draft: Draft) -> Result<i64, String> {
+    let conn = open_db(&app)?;
+    let blocks = serde_json::to_string(&draft.blocks).map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now().timestamp();
+
+    if draft.id != 0 {
+        let affected = conn
+            .execute(
+                "
+                UPDATE draft
+                SET card_id = ?1, deck_id = ?2, base_version = ?3, name = ?4, tags = ?5,
+                    blocks = ?6, updated_at = ?7
+                WHERE id = ?8
+                ",
+                params![
+                    draft.card_id,
+                    draft.deck_id,
+                    draft.base_version,
+                    draft.name,
+                    draft.tags,
+                    blocks,
+                    now,
+                    draft.id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+
+        if affected > 0 {
+            return Ok(draft.id);
+        }
+    }
+
+    conn.execute(
+        "
+        INSERT INTO draft (card_id, deck_id, base_version, name, tags, blocks, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+        ON CONFLICT(card_id) DO UPDATE SET
+            deck_id = excluded.deck_id,
+            base_version = excluded.base_version,
+            name = excluded.name,
+            tags = excluded.tags,
+            blocks = excluded.blocks,
+            updated_at = excluded.updated_at
+        ",
+        params![draft.card_id, draft.deck_id, draft.base_version, draft.name, draft.tags, blocks, now],
+    )
+    .map_err(|e| e.to_string())?;
+
+    match draft.card_id {
+        Some(card_id) => conn
+            .query_row("SELECT id FROM draft WHERE card_id = ?1", [card_id], |row| row.get(0))
+            .map_err(|e| e.to_string()),
+        None => Ok(conn.last_insert_rowid()),
+    }
+}
+
+/// Drafts left over from earlier sessions, newest first. Drafts of trashed
+/// cards or decks are left out.
+#[tauri::command]
+pub fn list_drafts(app: tauri::AppHandle) -> Result<Vec<Draft>, String> {
+    let conn = open_db(&app)?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "
+            SELECT {DRAFT_COLUMNS} FROM draft
+            WHERE (card_id IS NULL OR card_id IN (SELECT id FROM card WHERE deleted_at IS NULL))
+              AND deck_id IN (SELECT id FROM deck WHERE deleted_at IS NULL)
+            ORDER BY updated_at DESC
+            "
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let drafts = stmt
+        .query_map([], draft_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(drafts)
+}
+
+#[tauri::command]
+pub fn get_draft(app: tauri::AppHandle, id: i64) -> Result<Draft, String> {
+    let conn = open_db(&app)?;
+
+    conn.query_row(
+        &format!("SELECT {DRAFT_COLUMNS} FROM draft WHERE id = ?1"),
+        [id],
+        draft_from_row,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Unsaved changes to an existing card, if any.
+#[tauri::command]
+pub fn get_card_draft(app: tauri::AppHandle, card_id: i64) -> Result<Option<Draft>, String> {
+    let conn = open_db(&app)?;
+
+    conn.query_row(
+        &format!("SELECT {DRAFT_COLUMNS} FROM draft WHERE card_id = ?1"),
+        [card_id],
+        draft_from_row,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_draft(app: tauri::AppHandle, id: i64) -> Result<(), String> {
+    let conn = open_db(&app)?;
+
+    conn.execute("DELETE FROM draft WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -1,7 +1,12 @@
-use crate::db::{get_card, get_deck, open_db};
+use crate::db::{
+    get_deck, open_db, load_card, child_deck_ids, deck_subtree_ids, ensure_deck_exists, deck_from_row,
+    DECK_COLUMNS,
+};
+use rusqlite::Connection;
 use futures::channel::oneshot;
 use serde::{Deserialize, Serialize};
-use shared::models::{derive_export_path, Block, Card, Deck};
+use shared::models::{derive_export_path, deck_full_name, Block, Card, Deck, TextExportFormat};
+use shared::text_export::{to_anki_csv, to_markdown_document};
 use std::io::Write;
 use tauri::Manager;
 use tauri_plugin_dialog::{DialogExt, FileDialogBuilder, FilePath};
@@ -13,11 +18,24 @@ use zip::{write::FileOptions, ZipWriter};
 // Models
 // =======================
 
+/// Version 3 added `subdecks`; version 2 archives are still importable.
+pub const EXPORT_VERSION: u32 = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeckExport {
     pub export_version: u32,
     pub deck: Deck,
     pub cards: Vec<Card>,
+
+    #[serde(default)]
+    pub subdecks: Vec<DeckExport>,
+}
+
+impl DeckExport {
+    /// Number of cards including all sub-decks.
+    pub fn total_cards(&self) -> usize {
+        self.cards.len() + self.subdecks.iter().map(DeckExport::total_cards).sum::<usize>()
+    }
 }
 
 #[derive(Debug)]
@@ -32,11 +50,9 @@ pub struct ExportFile {
 // DB helpers
 // =======================
 
-pub fn get_card_ids(app: &tauri::AppHandle, deck_id: i64) -> Result<Vec<i64>, String> {
-    let conn = open_db(app)?;
-
+pub fn get_card_ids(conn: &Connection, deck_id: i64) -> Result<Vec<i64>, String> {
     let mut stmt = conn
-        .prepare("SELECT id FROM card WHERE deck_id = ? ORDER BY created_at DESC")
+        .prepare("SELECT id FROM card WHERE deck_id = ? AND deleted_at IS NULL ORDER BY created_at DESC")
         .map_err(|e| e.to_string())?;
 
     let rows = stmt
@@ -51,27 +67,56 @@ pub fn get_card_ids(app: &tauri::AppHandle, deck_id: i64) -> Result<Vec<i64>, St
     Ok(ids)
 }
 
-pub fn export_deck_cards(app: &tauri::AppHandle, deck_id: i64) -> Result<Vec<Card>, String> {
-    let card_ids = get_card_ids(app, deck_id)?;
+pub fn export_deck_cards(conn: &Connection, deck_id: i64) -> Result<Vec<Card>, String> {
+    let card_ids = get_card_ids(conn, deck_id)?;
 
     let mut cards = Vec::new();
     for id in card_ids {
-        cards.push(get_card(app.clone(), id)?);
+        cards.push(load_card(conn, id)?);
     }
 
     Ok(cards)
 }
 
 
+/// The cards of the deck and its sub-decks, each with the full
+/// `Parent::Child` name of its deck.
+pub fn named_deck_cards(conn: &Connection, deck_id: i64) -> Result<Vec<(String, Card)>, String> {
+    ensure_deck_exists(conn, deck_id)?;
+
+    let mut stmt = conn
+        .prepare(&format!("SELECT {DECK_COLUMNS} FROM deck"))
+        .map_err(|e| e.to_string())?;
+
+    let decks: Vec<Deck> = stmt
+        .query_map([], deck_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut cards = Vec::new();
+    for id in deck_subtree_ids(conn, deck_id)? {
+        let name = deck_full_name(&decks, id);
+        for card in export_deck_cards(conn, id)? {
+            cards.push((name.clone(), card));
+        }
+    }
+
+    Ok(cards)
+}
+
 
 // =======================
 // Export helpers
 // =======================
 
-pub fn collect_export_files(cards: &[Card]) -> Vec<ExportFile> {
+/// `first_index` keeps the zip paths unique when several decks go into one archive.
+pub fn collect_export_files(cards: &[Card], first_index: usize) -> Vec<ExportFile> {
     let mut files = Vec::new();
 
     for (card_index, card) in cards.iter().enumerate() {
+        let card_index = first_index + card_index;
+
         for (block_index, block) in card.front_blocks.iter().enumerate() {
             if let Some(src) = block.file_path() {
                 files.push(ExportFile {
@@ -150,28 +195,51 @@ pub fn rewrite_cards_for_export(
 // ZIP builder
 // =======================
 
-pub fn build_deck_zip(
+/// Builds the export model for a deck and, recursively, its sub-decks.
+/// Media of all decks is collected into `export_files`; `card_index` counts
+/// the cards exported so far.
+pub fn build_deck_export(
     app: &tauri::AppHandle,
+    conn: &Connection,
     deck_id: i64,
-) -> Result<Vec<u8>, String> {
+    export_files: &mut Vec<ExportFile>,
+    card_index: &mut usize,
+) -> Result<DeckExport, String> {
     let deck = get_deck(app.clone(), deck_id)?;
-    let cards = export_deck_cards(app, deck_id)?;
+    let cards = export_deck_cards(conn, deck_id)?;
 
     // 1️⃣ Collect files
-    let export_files = collect_export_files(&cards);
+    let files = collect_export_files(&cards, *card_index);
+    *card_index += cards.len();
 
     // 2️⃣ Build path map
-    let path_map = build_export_path_map(&export_files);
+    let path_map = build_export_path_map(&files);
+    export_files.extend(files);
 
     // 3️⃣ Rewrite cards for export.json
     let exported_cards = rewrite_cards_for_export(&cards, &path_map);
 
+    let mut subdecks = Vec::new();
+    for child_id in child_deck_ids(conn, deck_id)? {
+        subdecks.push(build_deck_export(app, conn, child_id, export_files, card_index)?);
+    }
+
     // 4️⃣ Build export model
-    let export = DeckExport {
-        export_version: 2,
+    Ok(DeckExport {
+        export_version: EXPORT_VERSION,
         deck,
         cards: exported_cards,
-    };
+        subdecks,
+    })
+}
+
+pub fn build_deck_zip(
+    app: &tauri::AppHandle,
+    deck_id: i64,
+) -> Result<Vec<u8>, String> {
+    let mut export_files = Vec::new();
+    let conn = open_db(app)?;
+    let export = build_deck_export(app, &conn, deck_id, &mut export_files, &mut 0)?;
 
 
     let mut buffer = Vec::new();
@@ -242,4 +310,53 @@ pub async fn export_deck(
     .await.map_err(|e| e.to_string())??;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Saves the deck as text for other apps (see `shared::text_export`). Media
+/// files are not included.
+#[tauri::command]
+pub async fn export_deck_text(
+    app: tauri::AppHandle,
+    deck_id: i64,
+    format: TextExportFormat,
+) -> Result<(), String> {
+    let text = tauri::async_runtime::spawn_blocking({
+        let app = app.clone();
+        move || -> Result<String, String> {
+            let conn = open_db(&app)?;
+            let cards = named_deck_cards(&conn, deck_id)?;
+            Ok(match format {
+                TextExportFormat::AnkiCsv => to_anki_csv(&cards),
+                TextExportFormat::Markdown => to_markdown_document(&cards),
+            })
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let file_name = match format {
+        TextExportFormat::AnkiCsv => "deck-export.csv",
+        TextExportFormat::Markdown => "deck-export.md",
+    };
+
+    let (tx, rx) = oneshot::channel();
+
+    FileDialogBuilder::new(app.dialog().clone())
+        .set_file_name(file_name)
+        .save_file(move |file| {
+            let _ = tx.send(file);
+        });
+
+    let dest = match rx.await {
+        Ok(Some(FilePath::Path(path))) => path,
+        Ok(_) => return Ok(()), // user cancelled or non-path
+        Err(_) => return Ok(()), // dialog canceled
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        std::fs::write(dest, text).map_err(|e| e.to_string())
+    })
+    .await.map_err(|e| e.to_string())??;
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use crate::db::{open_db, load_card, set_card_tags_tx, write_blocks_tx};
+use crate::undo::{record_undo, UndoAction};
+use rusqlite::{params, Connection, OptionalExtension};
+use shared::models::{Block, Card, CardRevision};
+
+/// Number of revisions kept per card; older ones are dropped.
+const REVISION_LIMIT: i64 = 50;
+
+fn revision_from_row(row: &rusqlite::Row) -> rusqlite::Result<CardRevision> {
+    let blocks = |i: usize| -> rusqlite::Result<Vec<Block>> {
+        let json: String = row.get(i)?;
+        serde_json::from_str(&json).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(i, rusqlite::types::Type::Text, Box::new(e))
+        })
+    };
+
+    Ok(CardRevision {
+        id: row.get(0)?,
+        card_id: row.get(1)?,
+        created_at: row.get(2)?,
+        name: row.get(3)?,
+        tags: row.get(4)?,
+        front_blocks: blocks(5)?,
+        back_blocks: blocks(6)?,
+    })
+}
+
+const REVISION_COLUMNS: &str = "id, card_id, created_at, name, tags, front, back";
+
+/// Records the current content of a card as a new revision, unless it is
+/// unchanged since the last one.
+pub fn record_revision_tx(conn: &Connection, card_id: i64) -> Result<(), String> {
+    let card = load_card(conn, card_id)?;
+    let front = serde_json::to_string(&card.front_blocks).map_err(|e| e.to_string())?;
+    let back = serde_json::to_string(&card.back_blocks).map_err(|e| e.to_string())?;
+
+    let unchanged: bool = conn
+        .query_row(
+            "
+            SELECT EXISTS (
+                SELECT 1 FROM (
+                    SELECT name, tags, front, back FROM card_revision
+                    WHERE card_id = ?1 ORDER BY id DESC LIMIT 1
+                )
+                WHERE name = ?2 AND tags IS ?3 AND front = ?4 AND back = ?5
+            )
+            ",
+            params![card_id, card.name, card.tags, front, back],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    if unchanged {
+        return Ok(());
+    }
+
+    conn.execute(
+        "
+        INSERT INTO card_revision (card_id, created_at, name, tags, front, back)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ",
+        params![card_id, chrono::Utc::now().timestamp(), card.name, card.tags, front, back],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "
+        DELETE FROM card_revision
+        WHERE card_id = ?1 AND id NOT IN (
+            SELECT id FROM card_revision WHERE card_id = ?1 ORDER BY id DESC LIMIT ?2
+        )
+        ",
+        params![card_id, REVISION_LIMIT],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// The card as of its latest revision, i.e. without blocks that were only
+/// autosaved since.
+pub fn last_saved_card(conn: &Connection, card_id: i64) -> Result<Card, String> {
+    let card = load_card(conn, card_id)?;
+
+    let latest = conn
+        .query_row(
+            &format!(
+                "SELECT {REVISION_COLUMNS} FROM card_revision WHERE card_id = ?1 ORDER BY id DESC LIMIT 1"
+            ),
+            [card_id],
+            revision_from_row,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    Ok(match latest {
+        Some(revision) => Card {
+            name: revision.name,
+            tags: revision.tags,
+            front_blocks: revision.front_blocks,
+            back_blocks: revision.back_blocks,
+            ..card
+        },
+        None => card,
+    })
+}
+
+/// Virtual paths of the media files used by any revision. They are kept
+/// when the trash is purged so that old revisions can still be restored.
+pub fn revision_file_paths(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {REVISION_COLUMNS} FROM card_revision"))
+        .map_err(|e| e.to_string())?;
+
+    let paths = stmt
+        .query_map([], revision_from_row)
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .flat_map(|r| r.front_blocks.into_iter().chain(r.back_blocks))
+        .filter_map(|b| b.file_path().map(str::to_string))
+        .collect();
+
+    Ok(paths)
+}
+
+
+// =======================
+// Tauri commands
+// =======================
+
+/// All revisions of a card, newest first.
+#[tauri::command]
+pub fn get_card_history(app: tauri::AppHandle, card_id: i64) -> Result<Vec<CardRevision>, String> {
+    let conn = open_db(&app)?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {REVISION_COLUMNS} FROM card_revision WHERE card_id = ?1 ORDER BY id DESC"
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let revisions = stmt
+        .query_map([card_id], revision_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(revisions)
+}
+
+/// Puts the name, tags and blocks of an old revision back on the card. This
+/// is recorded as a new revision and can be undone like any edit.
+#[tauri::command]
+pub fn restore_revision(app: tauri::AppHandle, revision_id: i64) -> Result<Card, String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let revision = tx
+        .query_row(
+            &format!("SELECT {REVISION_COLUMNS} FROM card_revision WHERE id = ?1"),
+            [revision_id],
+            revision_from_row,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let before = last_saved_card(&tx, revision.card_id)?;
+
+    tx.execute(
+        "UPDATE card SET name = ?1 WHERE id = ?2",
+        params![revision.name, revision.card_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    set_card_tags_tx(
+        &tx,
+        revision.card_id,
+        &shared::tags::parse_tags(revision.tags.as_deref().unwrap_or_default()),
+    )?;
+    write_blocks_tx(&tx, revision.card_id, &revision.front_blocks, &revision.back_blocks)?;
+
+    record_undo(
+        &tx,
+        &format!("Restore an old version of \"{}\"", before.name),
+        &UndoAction::EditCard { card: before },
+    )?;
+    record_revision_tx(&tx, revision.card_id)?;
+
+    let card = load_card(&tx, revision.card_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(card)
+}
@@ -1,21 +1,25 @@
-use tauri_plugin_dialog::{ FileDialogBuilder, FilePath, DialogExt };
-use crate::db::{ open_db, add_deck, add_card , save_card_blocks, restore_card_metadata };
-use futures::channel::oneshot;
+use crate::db::{
+    open_db, insert_deck_tx, insert_card_tx, write_blocks_tx, delete_file_from_app_data,
+    find_child_deck, ensure_deck_exists,
+};
+use crate::trash::trash_cards_tx;
 use tauri::Manager;
-use crate::export::DeckExport;
+use crate::export::{DeckExport, EXPORT_VERSION, export_deck_cards};
+use crate::history::record_revision_tx;
 use tauri_plugin_bliet::BlietExt;
+use shared::models::{
+    Card, ImportMode, ImportPreview, ImportSummary, RemovedCards, DECK_PATH_SEPARATOR,
+};
+use rusqlite::{params, Connection};
 use std::collections::HashMap;
 use std::io::{Cursor, Read};
+use std::sync::Mutex;
 use uuid::Uuid;
 use zip::ZipArchive;
 
-/* 
+/*
 TODO:
 
-🔹 Transactions
-
-Wrap import in a DB transaction so partial imports roll back on failure.
-
 🔹 Progress feedback
 
 Emit progress events for large decks.
@@ -33,13 +37,33 @@ Support export_version = 1 JSON-only imports.
 For very large decks, stream extraction instead of buffering. */
 
 
-pub fn import_deck_zip(
-    app: &tauri::AppHandle,
-    zip_bytes: &[u8],
-) -> Result<DeckExport, String> {
-    let reader = Cursor::new(zip_bytes);
-    let mut zip = ZipArchive::new(reader).map_err(|e| e.to_string())?;
+type Archive<'a> = ZipArchive<Cursor<&'a [u8]>>;
+
+/// The archive picked by `stage_import`, kept until the user chose how to
+/// apply it (preview first, then `import_deck`).
+#[derive(Default)]
+pub struct PendingImport(pub Mutex<Option<Vec<u8>>>);
+
+impl PendingImport {
+    fn bytes(&self) -> Result<Vec<u8>, String> {
+        self.0
+            .lock()
+            .map_err(|e| e.to_string())?
+            .clone()
+            .ok_or_else(|| "No archive staged for import".to_string())
+    }
+}
+
 
+// =======================
+// Archive helpers
+// =======================
+
+pub fn open_archive(zip_bytes: &[u8]) -> Result<Archive<'_>, String> {
+    ZipArchive::new(Cursor::new(zip_bytes)).map_err(|e| e.to_string())
+}
+
+pub fn read_export(zip: &mut Archive) -> Result<DeckExport, String> {
     // 1️⃣ Read export.json
     let mut json = String::new();
     zip.by_name("export.json")
@@ -47,16 +71,34 @@ pub fn import_deck_zip(
         .read_to_string(&mut json)
         .map_err(|e| e.to_string())?;
 
-    let mut export: DeckExport = serde_json::from_str(&json)
+    let export: DeckExport = serde_json::from_str(&json)
         .map_err(|e| e.to_string())?;
 
-    if export.export_version != 2 {
+    if !(2..=EXPORT_VERSION).contains(&export.export_version) {
         return Err(format!(
             "Unsupported export version: {}",
             export.export_version
         ));
     }
 
+    Ok(export)
+}
+
+fn read_zip_file(zip: &mut Archive, zip_path: &str) -> Option<Vec<u8>> {
+    let mut file = zip.by_name(zip_path).ok()?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+/// Copies the media referenced by `cards` into the app files dir and rewrites
+/// the block paths to the new virtual paths. Returns the new virtual paths so
+/// they can be cleaned up again if the import fails afterwards.
+pub fn extract_card_files<'c>(
+    app: &tauri::AppHandle,
+    zip: &mut Archive,
+    cards: impl IntoIterator<Item = &'c mut Card>,
+) -> Result<Vec<String>, String> {
     // 2️⃣ Prepare app files dir
     let app_data_dir = app.path().app_data_dir()
         .map_err(|e| e.to_string())?;
@@ -67,105 +109,423 @@ pub fn import_deck_zip(
     // 3️⃣ Extract files + build path map
     let mut path_map: HashMap<String, String> = HashMap::new();
 
-    for i in 0..zip.len() {
-        let mut file = zip.by_index(i).map_err(|e| e.to_string())?;
-        let zip_path = file.name().to_string();
+    for card in cards {
+        for block in card.all_blocks_mut() {
+            let Some(path) = block.file_path_mut() else { continue };
 
-        if !zip_path.starts_with("files/") {
-            continue;
+            if let Some(new_path) = path_map.get(path.as_str()) {
+                *path = new_path.clone();
+                continue;
+            }
+
+            if !path.starts_with("files/") {
+                continue;
+            }
+
+            let mut file = zip
+                .by_name(path)
+                .map_err(|_| format!("{} not found in archive", path))?;
+
+            let ext = std::path::Path::new(path.as_str())
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("bin");
+
+            let new_name = format!("{}.{}", Uuid::new_v4(), ext);
+            let dest_path = files_dir.join(&new_name);
+
+            let mut out = std::fs::File::create(&dest_path)
+                .map_err(|e| e.to_string())?;
+
+            std::io::copy(&mut file, &mut out)
+                .map_err(|e| e.to_string())?;
+
+            // 4️⃣ Rewrite paths inside blocks
+            let new_path = format!("files/{}", new_name);
+            path_map.insert(path.clone(), new_path.clone());
+            *path = new_path;
+        }
+    }
+
+    Ok(path_map.into_values().collect())
+}
+
+
+// =======================
+// Merge planning
+// =======================
+
+pub struct MergePlan {
+    pub added: Vec<Card>,
+    /// (card currently in the deck, revised card from the archive)
+    pub updated: Vec<(Card, Card)>,
+    pub removed: Vec<Card>,
+    pub unchanged: u32,
+}
+
+/// Plan for one deck of the archive and, recursively, its sub-decks.
+pub struct DeckPlan {
+    pub name: String,
+    /// Existing deck the cards go into; `None` creates a new deck.
+    pub target: Option<i64>,
+    pub cards: MergePlan,
+    pub subdecks: Vec<DeckPlan>,
+}
+
+impl DeckPlan {
+    pub fn preview(&self) -> ImportPreview {
+        let mut preview = ImportPreview::default();
+        self.collect_preview("", &mut preview);
+        preview
+    }
+
+    // Cards of sub-decks are listed as `Sub::Card name`
+    fn collect_preview(&self, prefix: &str, preview: &mut ImportPreview) {
+        let name = |card: &Card| format!("{}{}", prefix, card.name);
+
+        preview.added.extend(self.cards.added.iter().map(name));
+        preview.updated.extend(self.cards.updated.iter().map(|(_, c)| name(c)));
+        preview.removed.extend(self.cards.removed.iter().map(name));
+        preview.unchanged += self.cards.unchanged;
+
+        for sub in &self.subdecks {
+            sub.collect_preview(&format!("{}{}{}", prefix, sub.name, DECK_PATH_SEPARATOR), preview);
         }
+    }
 
-        let ext = std::path::Path::new(&zip_path)
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("bin");
+    /// Cards whose content will be written, i.e. whose media must be extracted.
+    fn incoming_cards_mut(&mut self) -> Vec<&mut Card> {
+        let mut cards: Vec<&mut Card> = self.cards.added.iter_mut().collect();
+        cards.extend(self.cards.updated.iter_mut().map(|(_, new)| new));
 
-        let new_name = format!("{}.{}", Uuid::new_v4(), ext);
-        let dest_path = files_dir.join(&new_name);
+        for sub in self.subdecks.iter_mut() {
+            cards.extend(sub.incoming_cards_mut());
+        }
 
-        let mut out = std::fs::File::create(&dest_path)
-            .map_err(|e| e.to_string())?;
+        cards
+    }
+}
 
-        std::io::copy(&mut file, &mut out)
-            .map_err(|e| e.to_string())?;
+pub fn plan_import(
+    app: &tauri::AppHandle,
+    conn: &Connection,
+    zip: &mut Archive,
+    export: DeckExport,
+    mode: &ImportMode,
+) -> Result<DeckPlan, String> {
+    let target = match *mode {
+        ImportMode::NewDeck => None,
+        ImportMode::Merge { deck_id, .. } | ImportMode::Replace { deck_id } => Some(deck_id),
+    };
 
-        path_map.insert(
-            zip_path,
-            format!("files/{}", new_name),
-        );
+    if let Some(deck_id) = target {
+        ensure_deck_exists(conn, deck_id)?;
     }
 
-    // 4️⃣ Rewrite paths inside blocks
-    for card in export.cards.iter_mut() {
-        for block in card.all_blocks_mut() {
-            if let Some(path) = block.file_path_mut() {
-                if let Some(new_path) = path_map.get(path) {
-                    *path = new_path.clone();
+    plan_deck(app, conn, zip, export, target, mode)
+}
+
+// Sub-decks are matched by name below the target deck; those without a
+// counterpart are created.
+fn plan_deck(
+    app: &tauri::AppHandle,
+    conn: &Connection,
+    zip: &mut Archive,
+    export: DeckExport,
+    target: Option<i64>,
+    mode: &ImportMode,
+) -> Result<DeckPlan, String> {
+    let cards = match (target, mode) {
+        (Some(deck_id), ImportMode::Merge { .. }) => {
+            let existing = export_deck_cards(conn, deck_id)?;
+            plan_merge(app, zip, export.cards, existing)?
+        }
+        (Some(deck_id), _) => MergePlan {
+            added: export.cards,
+            updated: vec![],
+            removed: export_deck_cards(conn, deck_id)?,
+            unchanged: 0,
+        },
+        (None, _) => MergePlan {
+            added: export.cards,
+            updated: vec![],
+            removed: vec![],
+            unchanged: 0,
+        },
+    };
+
+    let mut subdecks = Vec::new();
+    for sub in export.subdecks {
+        let sub_target = match target {
+            Some(deck_id) => find_child_deck(conn, Some(deck_id), &sub.deck.name)?,
+            None => None,
+        };
+        subdecks.push(plan_deck(app, conn, zip, sub, sub_target, mode)?);
+    }
+
+    Ok(DeckPlan {
+        name: export.deck.name,
+        target,
+        cards,
+        subdecks,
+    })
+}
+
+/// Pairs archive cards with deck cards: first by uid, then by name for
+/// cards exported before uids existed.
+pub fn plan_merge(
+    app: &tauri::AppHandle,
+    zip: &mut Archive,
+    incoming: Vec<Card>,
+    existing: Vec<Card>,
+) -> Result<MergePlan, String> {
+    let mut remaining: Vec<Option<Card>> = existing.into_iter().map(Some).collect();
+    let mut plan = MergePlan {
+        added: vec![],
+        updated: vec![],
+        removed: vec![],
+        unchanged: 0,
+    };
+
+    for card in incoming {
+        let by_uid = remaining.iter().position(|c| {
+            c.as_ref().is_some_and(|c| !card.uid.is_empty() && c.uid == card.uid)
+        });
+        let matched = by_uid.or_else(|| {
+            remaining
+                .iter()
+                .position(|c| c.as_ref().is_some_and(|c| c.name == card.name))
+        });
+
+        let Some(old) = matched.and_then(|i| remaining[i].take()) else {
+            plan.added.push(card);
+            continue;
+        };
+
+        if card_content_matches(app, zip, &old, &card)? {
+            plan.unchanged += 1;
+        } else {
+            plan.updated.push((old, card));
+        }
+    }
+
+    plan.removed = remaining.into_iter().flatten().collect();
+    Ok(plan)
+}
+
+/// Compares name and blocks. Media blocks point to different paths in the
+/// archive and on disk, so for those the file contents are compared.
+fn card_content_matches(
+    app: &tauri::AppHandle,
+    zip: &mut Archive,
+    old: &Card,
+    new: &Card,
+) -> Result<bool, String> {
+    if old.name != new.name
+        || old.front_blocks.len() != new.front_blocks.len()
+        || old.back_blocks.len() != new.back_blocks.len()
+    {
+        return Ok(false);
+    }
+
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+
+    for (a, b) in old.all_blocks().zip(new.all_blocks()) {
+        match (a.file_path(), b.file_path()) {
+            (Some(disk_path), Some(zip_path)) => {
+                if a.block_type() != b.block_type() {
+                    return Ok(false);
+                }
+                let on_disk = std::fs::read(app_data_dir.join(disk_path)).ok();
+                if on_disk.is_none() || on_disk != read_zip_file(zip, zip_path) {
+                    return Ok(false);
+                }
+            }
+            _ => {
+                if a != b {
+                    return Ok(false);
                 }
             }
         }
     }
 
-    Ok(export)
+    Ok(true)
 }
 
 
-
+// =======================
+// Apply
+// =======================
 
 pub fn import_deck_export(
     app: &tauri::AppHandle,
+    zip: &mut Archive,
     export: DeckExport,
+    mode: ImportMode,
 ) -> Result<i64, String> {
     // ⚠️ IMPORTANT:
     // Do NOT reuse export.deck.id or card.id
 
-    // 1️⃣ Create deck
-    let new_deck_id = add_deck(
-        app.clone(),
-        export.deck.name.clone(),
-    )?;
+    let mut conn = open_db(app)?;
+    let mut plan = plan_import(app, &conn, zip, export, &mode)?;
+
+    // Only media of cards that will actually be written is extracted
+    let extracted = extract_card_files(app, zip, plan.incoming_cards_mut())?;
+
+    let removed = match mode {
+        ImportMode::NewDeck => RemovedCards::Keep,
+        ImportMode::Merge { removed, .. } => removed,
+        ImportMode::Replace { .. } => RemovedCards::Delete,
+    };
 
-    // 2️⃣ Create cards
-    for card in export.cards {
-        let new_card_id = add_card(
-            app.clone(),
-            new_deck_id,
-            card.name.clone(),
-        )?;
+    let now = chrono::Utc::now().timestamp();
 
-        save_card_blocks(
-            app.clone(),
-            new_card_id,
-            card.front_blocks,
-            card.back_blocks,
-        )?;
+    let result = (|| -> Result<i64, String> {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let deck_id = apply_deck_tx(&tx, &plan, None, removed, now)?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(deck_id)
+    })();
 
-        restore_card_metadata(
-            app,
-            new_card_id,
-            card.created_at,
-            card.times_seen,
-            card.times_correct,
-            card.tags,
-        )?;
+    if result.is_err() {
+        for path in &extracted {
+            let _ = delete_file_from_app_data(app, path);
+        }
     }
 
+    result
+}
 
-    Ok(new_deck_id)
+fn apply_deck_tx(
+    tx: &rusqlite::Connection,
+    plan: &DeckPlan,
+    parent_id: Option<i64>,
+    removed: RemovedCards,
+    now: i64,
+) -> Result<i64, String> {
+    // the deck may have been deleted since the plan was made
+    let deck_id = match plan.target {
+        Some(deck_id) => {
+            ensure_deck_exists(tx, deck_id)?;
+            deck_id
+        }
+        None => insert_deck_tx(tx, &plan.name, parent_id)?,
+    };
+
+    for card in &plan.cards.added {
+        insert_card_tx(tx, deck_id, card)?;
+    }
+
+    // Revised content, but the learner's scores and tags stay
+    // (the old media files stay for the card's history; the trash purge
+    // removes them once no revision uses them anymore)
+    for (old, new) in &plan.cards.updated {
+        let uid = if new.uid.is_empty() { &old.uid } else { &new.uid };
+        tx.execute(
+            "UPDATE card SET name = ?1, uid = ?2 WHERE id = ?3",
+            params![new.name, uid, old.id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        write_blocks_tx(tx, old.id, &new.front_blocks, &new.back_blocks)?;
+        record_revision_tx(tx, old.id)?;
+    }
+
+    match removed {
+        RemovedCards::Keep => {}
+        RemovedCards::Archive if !plan.cards.removed.is_empty() => {
+            // next to the deck, and the same one on every import
+            let (name, parent_id): (String, Option<i64>) = tx
+                .query_row(
+                    "SELECT name, parent_id FROM deck WHERE id = ?1",
+                    [deck_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .map_err(|e| e.to_string())?;
+            let archive_name = format!("{} (archived)", name);
+            let archive_id = match find_child_deck(tx, parent_id, &archive_name)? {
+                Some(id) => id,
+                None => insert_deck_tx(tx, &archive_name, parent_id)?,
+            };
+            for card in &plan.cards.removed {
+                tx.execute(
+                    "UPDATE card SET deck_id = ?1 WHERE id = ?2",
+                    params![archive_id, card.id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+        RemovedCards::Archive => {}
+        // into the trash, so they can still be restored
+        RemovedCards::Delete => {
+            let card_ids: Vec<i64> = plan.cards.removed.iter().map(|c| c.id).collect();
+            trash_cards_tx(tx, &card_ids, now)?;
+        }
+    }
+
+    for sub in &plan.subdecks {
+        apply_deck_tx(tx, sub, Some(deck_id), removed, now)?;
+    }
+
+    Ok(deck_id)
 }
 
 
+// =======================
+// Tauri commands
+// =======================
+
+/// Lets the user pick an archive and keeps it until it is imported.
 #[tauri::command]
-pub async fn import_deck(app: tauri::AppHandle) -> Result<i64, String> {
+pub async fn stage_import(
+    app: tauri::AppHandle,
+    pending: tauri::State<'_, PendingImport>,
+) -> Result<Option<ImportSummary>, String> {
     let Some(bytes) = app
         .bliet()
         .pick_import_file()
         .await
         .map_err(|e| e.to_string())?
     else {
-        return Ok(0);
+        return Ok(None);
     };
 
-    let export = import_deck_zip(&app, &bytes)?;
-    import_deck_export(&app, export)
+    let export = read_export(&mut open_archive(&bytes)?)?;
+
+    *pending.0.lock().map_err(|e| e.to_string())? = Some(bytes);
+
+    Ok(Some(ImportSummary {
+        deck_name: export.deck.name,
+        card_count: export.total_cards() as u32,
+    }))
+}
+
+#[tauri::command]
+pub fn preview_import(
+    app: tauri::AppHandle,
+    pending: tauri::State<'_, PendingImport>,
+    mode: ImportMode,
+) -> Result<ImportPreview, String> {
+    let bytes = pending.bytes()?;
+    let mut zip = open_archive(&bytes)?;
+    let export = read_export(&mut zip)?;
+
+    let conn = open_db(&app)?;
+    Ok(plan_import(&app, &conn, &mut zip, export, &mode)?.preview())
+}
+
+#[tauri::command]
+pub fn import_deck(
+    app: tauri::AppHandle,
+    pending: tauri::State<'_, PendingImport>,
+    mode: ImportMode,
+) -> Result<i64, String> {
+    let bytes = pending.bytes()?;
+    let mut zip = open_archive(&bytes)?;
+    let export = read_export(&mut zip)?;
+
+    let deck_id = import_deck_export(&app, &mut zip, export, mode)?;
+
+    *pending.0.lock().map_err(|e| e.to_string())? = None;
+    Ok(deck_id)
 }
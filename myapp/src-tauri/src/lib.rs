@@ -1,19 +1,50 @@
+mod cloze;
 mod db;
+mod drafts;
 mod export;
+mod history;
 mod import;
-use tauri::http;
+mod migrations;
+mod notes;
+mod organize;
+mod protocol;
+mod query;
+mod render;
+mod tags;
+mod trash;
+mod undo;
 use tauri::{Manager, AppHandle};
-use mime_guess;
 use urlencoding;
-use crate::export::export_deck;
-use crate::import::import_deck;
+use crate::export::{export_deck, export_deck_text};
+use crate::organize::{
+    move_cards, copy_cards, merge_decks, split_deck, bulk_update_cards,
+    reset_progress, suspend_cards, bury_cards,
+};
+use crate::render::validate_math;
+use crate::notes::{ list_note_types, add_note, get_note, update_note };
+use crate::tags::{ list_tags, rename_tag, merge_tags, delete_tag, get_cards_with_tag };
+use crate::query::{
+    list_filtered_decks, get_filtered_deck, save_filtered_deck, delete_filtered_deck,
+    count_filter_matches, get_filtered_study_queue, search_cards, list_cards,
+};
+use crate::trash::{
+    list_trash, restore_card, restore_deck, purge_card, purge_deck, empty_trash,
+    set_trash_retention_days,
+};
+use crate::undo::{ last_undo, undo_last };
+use crate::history::{ get_card_history, restore_revision };
+use crate::drafts::{ save_draft, list_drafts, get_draft, get_card_draft, delete_draft };
+use crate::import::{ import_deck, stage_import, preview_import, PendingImport };
 use crate::db::{
     init_db,
     add_deck,
     get_decks,
     add_card,
     save_card_blocks,
+    save_block,
+    get_card_blocks,
     update_card_metadata,
+    save_card,
     get_card,
     get_cards,
     get_deck,
@@ -21,8 +52,8 @@ use crate::db::{
     delete_card,
     delete_deck,
     update_score,
-    delete_block_from_app_data,
     rename_deck,
+    get_study_queue,
 };
 
 
@@ -46,25 +77,8 @@ pub fn run() {
         let app_data_dir = _ctx.app_handle().path().app_data_dir().unwrap();
         let full_path = app_data_dir.join(&virtual_path);
 
-        match std::fs::read(&full_path) {
-            Ok(bytes) => {
-                let mime = mime_guess::from_path(&full_path).first_or_octet_stream().to_string();
-
-                http::Response
-                    ::builder()
-                    .header("Content-Type", mime)
-                    .status(200)
-                    .body(bytes)
-                    .unwrap()
-            }
-            Err(e) => {
-                http::Response
-                    ::builder()
-                    .status(404)
-                    .body(format!("missing file: {}", e).into_bytes())
-                    .unwrap()
-            }
-        }
+        let range = request.headers().get("range").and_then(|v| v.to_str().ok());
+        protocol::file_response(&full_path, range)
     })
     .setup(|app| {
         #[cfg(debug_assertions)] // only include this code on debug builds
@@ -75,6 +89,7 @@ pub fn run() {
         }
         Ok(())
     })
+    .manage(PendingImport::default())
     .plugin(tauri_plugin_bliet::init()).plugin(tauri_plugin_dialog::init())
     .invoke_handler(
             tauri::generate_handler![
@@ -85,15 +100,63 @@ pub fn run() {
                 get_cards,
                 get_card,
                 save_card_blocks,
+                save_block,
+                get_card_blocks,
                 update_card_metadata,
+                save_card,
                 download_file,
                 delete_card,
                 export_deck,
+                export_deck_text,
                 import_deck,
+                stage_import,
+                preview_import,
                 delete_deck,
                 update_score,
-                delete_block_from_app_data,
                 rename_deck,
+                get_study_queue,
+                move_cards,
+                copy_cards,
+                merge_decks,
+                split_deck,
+                list_tags,
+                rename_tag,
+                merge_tags,
+                delete_tag,
+                get_cards_with_tag,
+                list_filtered_decks,
+                get_filtered_deck,
+                save_filtered_deck,
+                delete_filtered_deck,
+                count_filter_matches,
+                get_filtered_study_queue,
+                search_cards,
+                list_cards,
+                bulk_update_cards,
+                reset_progress,
+                suspend_cards,
+                bury_cards,
+                list_trash,
+                restore_card,
+                restore_deck,
+                purge_card,
+                purge_deck,
+                empty_trash,
+                set_trash_retention_days,
+                last_undo,
+                undo_last,
+                get_card_history,
+                restore_revision,
+                save_draft,
+                list_drafts,
+                get_draft,
+                get_card_draft,
+                delete_draft,
+                list_note_types,
+                add_note,
+                get_note,
+                update_note,
+                validate_math,
             ]
         )
         .run(tauri::generate_context!())
@@ -0,0 +1,419 @@
+use rusqlite::{params, Connection};
+
+// =======================
+// Schema migrations
+// =======================
+//
+// `init_db` creates the original tables with CREATE TABLE IF NOT EXISTS.
+// Everything added afterwards goes in here as a numbered step. SQLite's
+// `user_version` pragma remembers how many steps a database has already
+// seen, so every step runs exactly once per database.
+
+type Migration = fn(&Connection) -> rusqlite::Result<()>;
+
+const MIGRATIONS: &[Migration] = &[
+    add_card_uid,
+    add_deck_parent,
+    add_card_scheduling,
+    maintain_deck_card_count,
+    normalize_tags,
+    add_review_log_and_filtered_decks,
+    add_card_suspension,
+    add_trash_and_undo,
+    add_card_revisions,
+    add_drafts_and_card_version,
+    add_cloze_schedule,
+    add_notes,
+];
+
+pub fn run(conn: &mut Connection) -> Result<(), String> {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+        migration(&tx).map_err(|e| format!("migration {} failed: {}", i + 1, e))?;
+
+        tx.pragma_update(None, "user_version", (i + 1) as i64)
+            .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+
+// 1: stable card identity for merge imports
+fn add_card_uid(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE card ADD COLUMN uid TEXT NOT NULL DEFAULT '';
+        UPDATE card SET uid = lower(hex(randomblob(16))) WHERE uid = '';
+        CREATE INDEX IF NOT EXISTS idx_card_uid ON card(uid);
+        "
+    )
+}
+
+
+// 2: sub-decks
+fn add_deck_parent(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE deck ADD COLUMN parent_id INTEGER REFERENCES deck(id);
+        CREATE INDEX IF NOT EXISTS idx_deck_parent ON deck(parent_id);
+        CREATE INDEX IF NOT EXISTS idx_card_deck ON card(deck_id);
+        "
+    )
+}
+
+
+// 3: per-card scheduling; a card without due_at was never studied
+fn add_card_scheduling(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE card ADD COLUMN due_at INTEGER;
+        ALTER TABLE card ADD COLUMN interval_days INTEGER NOT NULL DEFAULT 0;
+        "
+    )
+}
+
+
+// 4: keep deck.card_count in sync with the card table and repair the
+// counts of databases created before (they were never updated)
+fn maintain_deck_card_count(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TRIGGER IF NOT EXISTS card_count_insert AFTER INSERT ON card
+        BEGIN
+            UPDATE deck SET card_count = card_count + 1 WHERE id = NEW.deck_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS card_count_delete AFTER DELETE ON card
+        BEGIN
+            UPDATE deck SET card_count = card_count - 1 WHERE id = OLD.deck_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS card_count_move AFTER UPDATE OF deck_id ON card
+        WHEN OLD.deck_id <> NEW.deck_id
+        BEGIN
+            UPDATE deck SET card_count = card_count - 1 WHERE id = OLD.deck_id;
+            UPDATE deck SET card_count = card_count + 1 WHERE id = NEW.deck_id;
+        END;
+
+        UPDATE deck SET card_count = (SELECT COUNT(*) FROM card WHERE card.deck_id = deck.id);
+        "
+    )
+}
+
+
+// 5: tags move from the comma separated card.tags column into their own
+// tables. card.tags is no longer read or written afterwards.
+fn normalize_tags(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS tag (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        );
+
+        CREATE TABLE IF NOT EXISTS card_tag (
+            card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tag(id) ON DELETE CASCADE,
+            PRIMARY KEY (card_id, tag_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_card_tag_tag ON card_tag(tag_id);
+        "
+    )?;
+
+    let mut stmt = conn.prepare("SELECT id, tags FROM card WHERE tags IS NOT NULL")?;
+    let cards: Vec<(i64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    for (card_id, raw) in cards {
+        for tag in shared::tags::parse_tags(&raw) {
+            conn.execute("INSERT OR IGNORE INTO tag (name) VALUES (?1)", [&tag])?;
+            conn.execute(
+                "INSERT OR IGNORE INTO card_tag (card_id, tag_id)
+                 SELECT ?1, id FROM tag WHERE name = ?2",
+                params![card_id, tag],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+
+// 6: every answer is logged (filtered decks select e.g. "failed in the last
+// 3 days") and filtered decks are stored as JSON encoded FilterQuery
+fn add_review_log_and_filtered_decks(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS review_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+            reviewed_at INTEGER NOT NULL,
+            correct INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_review_log_card ON review_log(card_id, reviewed_at);
+
+        CREATE TABLE IF NOT EXISTS filtered_deck (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            query TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+        "
+    )
+}
+
+
+// 7: cards can be suspended or buried until a date
+fn add_card_suspension(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE card ADD COLUMN suspended INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE card ADD COLUMN buried_until INTEGER;
+        "
+    )
+}
+
+
+// 8: soft deletion into a trash, settings and the undo log. Trashed cards
+// no longer count towards deck.card_count.
+fn add_trash_and_undo(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE card ADD COLUMN deleted_at INTEGER;
+        ALTER TABLE deck ADD COLUMN deleted_at INTEGER;
+
+        CREATE TABLE IF NOT EXISTS setting (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS undo_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            created_at INTEGER NOT NULL,
+            label TEXT NOT NULL,
+            action TEXT NOT NULL
+        );
+
+        DROP TRIGGER IF EXISTS card_count_insert;
+        DROP TRIGGER IF EXISTS card_count_delete;
+        DROP TRIGGER IF EXISTS card_count_move;
+
+        CREATE TRIGGER card_count_insert AFTER INSERT ON card
+        WHEN NEW.deleted_at IS NULL
+        BEGIN
+            UPDATE deck SET card_count = card_count + 1 WHERE id = NEW.deck_id;
+        END;
+
+        CREATE TRIGGER card_count_delete AFTER DELETE ON card
+        WHEN OLD.deleted_at IS NULL
+        BEGIN
+            UPDATE deck SET card_count = card_count - 1 WHERE id = OLD.deck_id;
+        END;
+
+        CREATE TRIGGER card_count_move AFTER UPDATE OF deck_id ON card
+        WHEN OLD.deck_id <> NEW.deck_id AND NEW.deleted_at IS NULL
+        BEGIN
+            UPDATE deck SET card_count = card_count - 1 WHERE id = OLD.deck_id;
+            UPDATE deck SET card_count = card_count + 1 WHERE id = NEW.deck_id;
+        END;
+
+        CREATE TRIGGER card_count_trash AFTER UPDATE OF deleted_at ON card
+        WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL
+        BEGIN
+            UPDATE deck SET card_count = card_count - 1 WHERE id = NEW.deck_id;
+        END;
+
+        CREATE TRIGGER card_count_restore AFTER UPDATE OF deleted_at ON card
+        WHEN OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL
+        BEGIN
+            UPDATE deck SET card_count = card_count + 1 WHERE id = NEW.deck_id;
+        END;
+
+        UPDATE deck SET card_count = (
+            SELECT COUNT(*) FROM card WHERE card.deck_id = deck.id AND card.deleted_at IS NULL
+        );
+        "
+    )
+}
+
+
+// 9: edit history. Every existing card starts with its current content as
+// the first revision.
+fn add_card_revisions(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS card_revision (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+            created_at INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            tags TEXT,
+            front TEXT NOT NULL,
+            back TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS card_revision_card ON card_revision(card_id);
+
+        INSERT INTO card_revision (card_id, created_at, name, tags, front, back)
+        SELECT card.id, card.created_at, card.name,
+               (SELECT group_concat(name, ',') FROM (
+                   SELECT tag.name FROM card_tag JOIN tag ON tag.id = card_tag.tag_id
+                   WHERE card_tag.card_id = card.id
+                   ORDER BY tag.name
+               )),
+               (SELECT json_group_array(json(content)) FROM (
+                   SELECT content FROM block WHERE card_id = card.id AND side = 'front' ORDER BY position
+               )),
+               (SELECT json_group_array(json(content)) FROM (
+                   SELECT content FROM block WHERE card_id = card.id AND side = 'back' ORDER BY position
+               ))
+        FROM card;
+        "
+    )
+}
+
+
+// 10: editor drafts, and a version number on cards that every change of
+// their content bumps, to notice edits made elsewhere while editing
+fn add_drafts_and_card_version(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        ALTER TABLE card ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+
+        CREATE TRIGGER IF NOT EXISTS card_version_name AFTER UPDATE OF name ON card
+        BEGIN
+            UPDATE card SET version = version + 1 WHERE id = NEW.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS card_version_block_insert AFTER INSERT ON block
+        BEGIN
+            UPDATE card SET version = version + 1 WHERE id = NEW.card_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS card_version_block_update AFTER UPDATE ON block
+        BEGIN
+            UPDATE card SET version = version + 1 WHERE id = NEW.card_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS card_version_block_delete AFTER DELETE ON block
+        BEGIN
+            UPDATE card SET version = version + 1 WHERE id = OLD.card_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS card_version_tag_insert AFTER INSERT ON card_tag
+        BEGIN
+            UPDATE card SET version = version + 1 WHERE id = NEW.card_id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS card_version_tag_delete AFTER DELETE ON card_tag
+        BEGIN
+            UPDATE card SET version = version + 1 WHERE id = OLD.card_id;
+        END;
+
+        CREATE TABLE IF NOT EXISTS draft (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id INTEGER UNIQUE,
+            deck_id INTEGER NOT NULL,
+            base_version INTEGER,
+            name TEXT NOT NULL,
+            tags TEXT NOT NULL,
+            blocks TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        "
+    )
+}
+
+
+// 11: each deletion of a cloze card is scheduled on its own; a missing row
+// means the deletion was never studied
+fn add_cloze_schedule(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS cloze_schedule (
+            card_id INTEGER NOT NULL REFERENCES card(id) ON DELETE CASCADE,
+            ordinal INTEGER NOT NULL,
+            times_seen INTEGER NOT NULL DEFAULT 0,
+            times_correct INTEGER NOT NULL DEFAULT 0,
+            due_at INTEGER,
+            interval_days INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (card_id, ordinal)
+        );
+        "
+    )
+}
+
+
+// 12: note types, which generate several cards from the fields of one note
+fn add_notes(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS note_type (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            fields TEXT NOT NULL,
+            templates TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS note (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            note_type_id INTEGER NOT NULL REFERENCES note_type(id),
+            deck_id INTEGER NOT NULL REFERENCES deck(id),
+            fields TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+
+        ALTER TABLE card ADD COLUMN note_id INTEGER REFERENCES note(id);
+        ALTER TABLE card ADD COLUMN template INTEGER;
+        CREATE INDEX IF NOT EXISTS idx_card_note ON card(note_id);
+        "
+    )?;
+
+    let template = |name: &str, front: &str, back: &str| {
+        serde_json::json!({ "name": name, "front": front, "back": back })
+    };
+
+    let builtin = [
+        (
+            "Basic",
+            serde_json::json!(["Front", "Back"]),
+            serde_json::json!([template("Card 1", "{{Front}}", "{{Back}}")]),
+        ),
+        (
+            "Basic + Reverse",
+            serde_json::json!(["Front", "Back"]),
+            serde_json::json!([
+                template("Card 1", "{{Front}}", "{{Back}}"),
+                template("Card 2", "{{Back}}", "{{Front}}"),
+            ]),
+        ),
+        (
+            "Word ↔ Definition ↔ Example",
+            serde_json::json!(["Word", "Definition", "Example"]),
+            serde_json::json!([
+                template("Word → Definition", "{{Word}}", "{{Definition}}\n\n{{Example}}"),
+                template("Definition → Word", "{{Definition}}", "{{Word}}\n\n{{Example}}"),
+                template("Example → Word", "{{Example}}", "{{Word}}\n\n{{Definition}}"),
+            ]),
+        ),
+    ];
+
+    for (name, fields, templates) in builtin {
+        conn.execute(
+            "INSERT INTO note_type (name, fields, templates) VALUES (?1, ?2, ?3)",
+            params![name, fields.to_string(), templates.to_string()],
+        )?;
+    }
+
+    Ok(())
+}
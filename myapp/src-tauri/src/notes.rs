@@ -0,0 +1,230 @@
+use crate::db::{insert_card_tx, open_db, write_blocks_tx};
+use crate::history::record_revision_tx;
+use rusqlite::{params, Connection, OptionalExtension};
+use shared::models::{Card, Note, NoteType};
+use shared::notes::{card_name, generate_card};
+
+// A note holds the field values, its note type the templates that turn them
+// into cards (see `shared::notes`). Generated cards are ordinary cards with
+// `note_id` and `template` set; saving the note rewrites their blocks, so
+// scores and scheduling stay with the card.
+
+fn json_column<T: serde::de::DeserializeOwned>(row: &rusqlite::Row, index: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(index)?;
+    serde_json::from_str(&text).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn note_type_from_row(row: &rusqlite::Row) -> rusqlite::Result<NoteType> {
+    Ok(NoteType {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        fields: json_column(row, 2)?,
+        templates: json_column(row, 3)?,
+    })
+}
+
+pub fn load_note_type(conn: &Connection, id: i64) -> Result<NoteType, String> {
+    conn.query_row(
+        "SELECT id, name, fields, templates FROM note_type WHERE id = ?1",
+        [id],
+        note_type_from_row,
+    )
+    .map_err(|_| format!("No note type found with id {}", id))
+}
+
+pub fn load_note(conn: &Connection, id: i64) -> Result<Note, String> {
+    conn.query_row(
+        "SELECT id, note_type_id, deck_id, fields, created_at FROM note WHERE id = ?1",
+        [id],
+        |row| {
+            Ok(Note {
+                id: row.get(0)?,
+                note_type_id: row.get(1)?,
+                deck_id: row.get(2)?,
+                fields: json_column(row, 3)?,
+                created_at: row.get(4)?,
+            })
+        },
+    )
+    .map_err(|_| format!("No note found with id {}", id))
+}
+
+/// Writes the cards of a note: cards already generated get the new content,
+/// templates that make a card for the first time add one next to its other
+/// cards, which may have been moved out of the note's deck since.
+/// Cards whose front came out empty are kept as they are, with their progress.
+/// Returns the ids of the note's current cards.
+fn sync_note_cards_tx(conn: &Connection, note: &Note) -> Result<Vec<i64>, String> {
+    let note_type = load_note_type(conn, note.note_type_id)?;
+    let mut card_ids = Vec::new();
+
+    let sibling_deck_id: Option<i64> = conn
+        .query_row(
+            "
+            SELECT card.deck_id FROM card JOIN deck ON deck.id = card.deck_id
+            WHERE card.note_id = ?1 AND card.deleted_at IS NULL AND deck.deleted_at IS NULL
+            ORDER BY card.template LIMIT 1
+            ",
+            [note.id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let deck_id = sibling_deck_id.unwrap_or(note.deck_id);
+
+    for (index, template) in note_type.templates.iter().enumerate() {
+        let Some((front, back)) = generate_card(&note_type, template, &note.fields) else {
+            continue;
+        };
+        let name = card_name(&note_type, template, &note.fields);
+
+        // trashed cards are updated too, so restoring one brings back the current note
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM card WHERE note_id = ?1 AND template = ?2",
+                params![note.id, index],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+
+        let card_id = match existing {
+            Some(card_id) => {
+                conn.execute(
+                    "UPDATE card SET name = ?1 WHERE id = ?2 AND name != ?1",
+                    params![name, card_id],
+                )
+                .map_err(|e| e.to_string())?;
+                write_blocks_tx(conn, card_id, &front, &back)?;
+                record_revision_tx(conn, card_id)?;
+                card_id
+            }
+            None => {
+                let card = Card {
+                    name,
+                    front_blocks: front,
+                    back_blocks: back,
+                    ..Card::new_empty(deck_id)
+                };
+                let card_id = insert_card_tx(conn, deck_id, &card)?;
+                conn.execute(
+                    "UPDATE card SET note_id = ?1, template = ?2 WHERE id = ?3",
+                    params![note.id, index, card_id],
+                )
+                .map_err(|e| e.to_string())?;
+                card_id
+            }
+        };
+
+        card_ids.push(card_id);
+    }
+
+    Ok(card_ids)
+}
+
+/// Removes notes none of whose cards are left, e.g. after purging the trash.
+pub fn prune_unused_notes(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM note WHERE id NOT IN (SELECT note_id FROM card WHERE note_id IS NOT NULL)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// One value per field of the type; missing values are empty, extra ones dropped.
+fn fit_fields(note_type: &NoteType, mut fields: Vec<String>) -> Vec<String> {
+    fields.resize(note_type.fields.len(), String::new());
+    fields
+}
+
+
+// =======================
+// Tauri commands
+// =======================
+
+#[tauri::command]
+pub fn list_note_types(app: tauri::AppHandle) -> Result<Vec<NoteType>, String> {
+    let conn = open_db(&app)?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, name, fields, templates FROM note_type ORDER BY id")
+        .map_err(|e| e.to_string())?;
+
+    let note_types = stmt
+        .query_map([], note_type_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(note_types)
+}
+
+/// Creates a note and its cards in `deck_id`, returning the ids of the cards.
+#[tauri::command]
+pub fn add_note(
+    app: tauri::AppHandle,
+    deck_id: i64,
+    note_type_id: i64,
+    fields: Vec<String>,
+) -> Result<Vec<i64>, String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let note_type = load_note_type(&tx, note_type_id)?;
+    let fields = fit_fields(&note_type, fields);
+    let now = chrono::Utc::now().timestamp();
+
+    tx.execute(
+        "INSERT INTO note (note_type_id, deck_id, fields, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            note_type_id,
+            deck_id,
+            serde_json::to_string(&fields).map_err(|e| e.to_string())?,
+            now
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let note = load_note(&tx, tx.last_insert_rowid())?;
+    let card_ids = sync_note_cards_tx(&tx, &note)?;
+
+    if card_ids.is_empty() {
+        return Err("No card would be generated, fill in the fields the cards ask for".to_string());
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(card_ids)
+}
+
+#[tauri::command]
+pub fn get_note(app: tauri::AppHandle, id: i64) -> Result<Note, String> {
+    let conn = open_db(&app)?;
+    load_note(&conn, id)
+}
+
+/// Saves the fields of a note and updates all cards generated from it.
+#[tauri::command]
+pub fn update_note(app: tauri::AppHandle, id: i64, fields: Vec<String>) -> Result<Vec<i64>, String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let note = load_note(&tx, id)?;
+    let note_type = load_note_type(&tx, note.note_type_id)?;
+    let note = Note { fields: fit_fields(&note_type, fields), ..note };
+
+    tx.execute(
+        "UPDATE note SET fields = ?1 WHERE id = ?2",
+        params![serde_json::to_string(&note.fields).map_err(|e| e.to_string())?, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let card_ids = sync_note_cards_tx(&tx, &note)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(card_ids)
+}
@@ -0,0 +1,429 @@
+use crate::db::{
+    open_db, load_card, insert_card_tx, new_card_uid, find_child_deck, find_or_create_deck_path,
+    deck_subtree_ids, delete_file_from_app_data,
+    set_card_tags_tx, ensure_deck_exists,
+};
+use crate::trash::{trash_cards_tx, trash_deck_tx};
+use crate::cloze::delete_cloze_schedules_tx;
+use crate::undo::{record_undo, UndoAction};
+use rusqlite::params;
+use shared::models::{BulkAction, Card, split_deck_path};
+use shared::tags::{normalize_tag, tag_matches};
+use tauri::Manager;
+use uuid::Uuid;
+
+
+// =======================
+// Helpers
+// =======================
+
+/// Gives every media file of the card its own copy in the files dir so the
+/// copy and the original can be edited and deleted independently.
+/// Returns the virtual paths of the new files.
+pub fn duplicate_media(
+    app: &tauri::AppHandle,
+    card: &mut Card,
+) -> Result<Vec<String>, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let mut created = Vec::new();
+
+    for block in card.all_blocks_mut() {
+        let Some(path) = block.file_path_mut() else { continue };
+
+        let ext = std::path::Path::new(path.as_str())
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("bin");
+
+        let new_path = format!("files/{}.{}", Uuid::new_v4(), ext);
+
+        std::fs::copy(app_data_dir.join(path.as_str()), app_data_dir.join(&new_path))
+            .map_err(|e| format!("Failed to copy {}: {}", path, e))?;
+
+        created.push(new_path.clone());
+        *path = new_path;
+    }
+
+    Ok(created)
+}
+
+pub fn deck_card_ids(conn: &rusqlite::Connection, deck_id: i64) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM card WHERE deck_id = ?1 AND deleted_at IS NULL ORDER BY created_at")
+        .map_err(|e| e.to_string())?;
+
+    let ids = stmt
+        .query_map([deck_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(ids)
+}
+
+/// Two cards are duplicates if they share a uid (e.g. the same deck imported
+/// twice) or have the same name and content.
+fn is_duplicate(a: &Card, b: &Card) -> bool {
+    (!a.uid.is_empty() && a.uid == b.uid)
+        || (a.name == b.name && a.front_blocks == b.front_blocks && a.back_blocks == b.back_blocks)
+}
+
+/// Zeroes the scores and scheduling of a stored card and forgets its review
+/// log, so `seen:N` and `failed:N` no longer match it; content, tags and
+/// suspension are kept.
+pub fn reset_progress_tx(conn: &rusqlite::Connection, card_id: i64) -> Result<(), String> {
+    conn.execute(
+        "
+        UPDATE card
+        SET times_seen = 0, times_correct = 0, due_at = NULL, interval_days = 0
+        WHERE id = ?1
+        ",
+        [card_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute("DELETE FROM review_log WHERE card_id = ?1", [card_id])
+        .map_err(|e| e.to_string())?;
+
+    delete_cloze_schedules_tx(conn, card_id)
+}
+
+pub fn reset_card_progress(card: &mut Card) {
+    card.times_seen = 0;
+    card.times_correct = 0;
+    card.due_at = None;
+    card.interval_days = 0;
+}
+
+
+// =======================
+// Tauri commands
+// =======================
+
+#[tauri::command]
+pub fn move_cards(
+    app: tauri::AppHandle,
+    card_ids: Vec<i64>,
+    target_deck_id: i64,
+) -> Result<(), String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    ensure_deck_exists(&tx, target_deck_id)?;
+
+    for id in card_ids {
+        // deck.card_count of both decks is adjusted by the card_count_move trigger
+        tx.execute(
+            "UPDATE card SET deck_id = ?1 WHERE id = ?2",
+            params![target_deck_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Copies cards (blocks and media files) into another deck. The copies get
+/// their own uid, so a later merge import does not mistake them for the originals.
+#[tauri::command]
+pub fn copy_cards(
+    app: tauri::AppHandle,
+    card_ids: Vec<i64>,
+    target_deck_id: i64,
+    reset_progress: bool,
+) -> Result<Vec<i64>, String> {
+    let mut conn = open_db(&app)?;
+    let mut created_files = Vec::new();
+
+    let result = (|| -> Result<Vec<i64>, String> {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        ensure_deck_exists(&tx, target_deck_id)?;
+        let mut new_ids = Vec::new();
+
+        for id in card_ids {
+            let mut card = load_card(&tx, id)?;
+            card.uid = new_card_uid();
+            if reset_progress {
+                reset_card_progress(&mut card);
+            }
+
+            created_files.extend(duplicate_media(&app, &mut card)?);
+            new_ids.push(insert_card_tx(&tx, target_deck_id, &card)?);
+        }
+
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(new_ids)
+    })();
+
+    if result.is_err() {
+        for path in &created_files {
+            let _ = delete_file_from_app_data(&app, path);
+        }
+    }
+
+    result
+}
+
+
+/// Moves the cards, notes and drafts of `source_id` into `target_id` and
+/// puts the emptied source deck in the trash. Sub-decks with a namesake below the target are merged into
+/// it the same way, the others move below the target. Of two duplicate cards
+/// the one with more reviews is kept, the other goes to the trash.
+/// Returns the number of duplicates trashed.
+fn merge_deck_tx(
+    conn: &rusqlite::Connection,
+    source_id: i64,
+    target_id: i64,
+    now: i64,
+) -> Result<u32, String> {
+    let mut target_cards = Vec::new();
+    for id in deck_card_ids(conn, target_id)? {
+        target_cards.push(load_card(conn, id)?);
+    }
+
+    let mut duplicates = 0;
+
+    for card_id in deck_card_ids(conn, source_id)? {
+        let card = load_card(conn, card_id)?;
+
+        let duplicate = target_cards.iter().position(|t| is_duplicate(t, &card));
+
+        let drop_id = match duplicate {
+            Some(i) if target_cards[i].times_seen >= card.times_seen => Some(card.id),
+            Some(i) => Some(std::mem::replace(&mut target_cards[i], card.clone()).id),
+            None => {
+                target_cards.push(card.clone());
+                None
+            }
+        };
+
+        if let Some(drop_id) = drop_id {
+            trash_cards_tx(conn, &[drop_id], now)?;
+            duplicates += 1;
+        }
+    }
+
+    // All cards of the source deck, trashed ones included, now belong to the
+    // target; restoring them from the trash later puts them there.
+    conn.execute(
+        "UPDATE card SET deck_id = ?1 WHERE deck_id = ?2",
+        params![target_id, source_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // new cards of a note and saved drafts would otherwise land in the trash
+    for table in ["note", "draft"] {
+        conn.execute(
+            &format!("UPDATE {table} SET deck_id = ?1 WHERE deck_id = ?2"),
+            params![target_id, source_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    let children: Vec<(i64, String, bool)> = {
+        let mut stmt = conn
+            .prepare("SELECT id, name, deleted_at IS NOT NULL FROM deck WHERE parent_id = ?1 ORDER BY id")
+            .map_err(|e| e.to_string())?;
+
+        stmt.query_map([source_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    for (child_id, name, trashed) in children {
+        let namesake = if trashed { None } else { find_child_deck(conn, Some(target_id), &name)? };
+
+        match namesake {
+            Some(namesake_id) => duplicates += merge_deck_tx(conn, child_id, namesake_id, now)?,
+            None => {
+                conn.execute(
+                    "UPDATE deck SET parent_id = ?1 WHERE id = ?2",
+                    params![target_id, child_id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    // trashed rather than deleted, so saved `did:` queries don't dangle and
+    // the deck can be restored
+    trash_deck_tx(conn, source_id, now)?;
+
+    Ok(duplicates)
+}
+
+/// Merges the source decks with their sub-decks into `target_id` (see
+/// `merge_deck_tx`). Returns the number of duplicates moved to the trash.
+#[tauri::command]
+pub fn merge_decks(
+    app: tauri::AppHandle,
+    source_ids: Vec<i64>,
+    target_id: i64,
+) -> Result<u32, String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    ensure_deck_exists(&tx, target_id)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let mut duplicates = 0;
+
+    for source_id in source_ids.into_iter().filter(|id| *id != target_id) {
+        ensure_deck_exists(&tx, source_id)?;
+
+        if deck_subtree_ids(&tx, source_id)?.contains(&target_id) {
+            return Err("A deck cannot be merged into its own sub-deck".into());
+        }
+
+        duplicates += merge_deck_tx(&tx, source_id, target_id, now)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(duplicates)
+}
+
+/// Moves the cards of a deck into sub-decks named after their tags.
+/// With `by_tag` only cards carrying that tag are moved into one sub-deck;
+/// without it every tagged card goes into the sub-deck of its first tag.
+/// Untagged cards stay where they are. Returns the ids of the sub-decks used.
+#[tauri::command]
+pub fn split_deck(
+    app: tauri::AppHandle,
+    deck_id: i64,
+    by_tag: Option<String>,
+) -> Result<Vec<i64>, String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let by_tag = by_tag.as_deref().and_then(normalize_tag);
+    let mut sub_decks: Vec<(String, i64)> = Vec::new();
+
+    for card_id in deck_card_ids(&tx, deck_id)? {
+        let card = load_card(&tx, card_id)?;
+        let tags = card.tag_list();
+
+        let tag = match &by_tag {
+            Some(wanted) => tags.into_iter().find(|t| t == wanted),
+            None => tags.into_iter().next(),
+        };
+        let Some(tag) = tag else { continue };
+
+        let sub_id = match sub_decks.iter().find(|(name, _)| *name == tag) {
+            Some((_, id)) => *id,
+            None => {
+                // `lang::verbs` goes into `verbs` below `lang`
+                let id = find_or_create_deck_path(&tx, Some(deck_id), &split_deck_path(&tag))?
+                    .unwrap_or(deck_id);
+                sub_decks.push((tag, id));
+                id
+            }
+        };
+
+        tx.execute(
+            "UPDATE card SET deck_id = ?1 WHERE id = ?2",
+            params![sub_id, card_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(sub_decks.into_iter().map(|(_, id)| id).collect())
+}
+
+/// Applies one action to all selected cards of the card browser in a single
+/// transaction.
+#[tauri::command]
+pub fn bulk_update_cards(
+    app: tauri::AppHandle,
+    card_ids: Vec<i64>,
+    action: BulkAction,
+) -> Result<(), String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    match action {
+        BulkAction::Delete => {
+            trash_cards_tx(&tx, &card_ids, chrono::Utc::now().timestamp())?;
+
+            let label = format!("Delete {} cards", card_ids.len());
+            record_undo(&tx, &label, &UndoAction::DeleteCards { card_ids })?;
+        }
+        BulkAction::AddTag { tag } => {
+            let tag = normalize_tag(&tag).ok_or("Tag name must not be empty")?;
+            for id in card_ids {
+                let mut tags = load_card(&tx, id)?.tag_list();
+                if !tags.contains(&tag) {
+                    tags.push(tag.clone());
+                    set_card_tags_tx(&tx, id, &tags)?;
+                }
+            }
+        }
+        BulkAction::RemoveTag { tag } => {
+            let tag = normalize_tag(&tag).ok_or("Tag name must not be empty")?;
+            for id in card_ids {
+                let mut tags = load_card(&tx, id)?.tag_list();
+                tags.retain(|t| !tag_matches(t, &tag));
+                set_card_tags_tx(&tx, id, &tags)?;
+            }
+        }
+        BulkAction::ResetProgress => {
+            for id in card_ids {
+                reset_progress_tx(&tx, id)?;
+            }
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Starts learning the cards from scratch.
+#[tauri::command]
+pub fn reset_progress(app: tauri::AppHandle, card_ids: Vec<i64>) -> Result<(), String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for id in card_ids {
+        reset_progress_tx(&tx, id)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Takes cards out of (or back into) rotation until they are unsuspended.
+#[tauri::command]
+pub fn suspend_cards(app: tauri::AppHandle, card_ids: Vec<i64>, suspended: bool) -> Result<(), String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for id in card_ids {
+        tx.execute(
+            "UPDATE card SET suspended = ?1 WHERE id = ?2",
+            params![suspended, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Hides cards from studying for `days` days; `None` unburies them.
+#[tauri::command]
+pub fn bury_cards(app: tauri::AppHandle, card_ids: Vec<i64>, days: Option<u32>) -> Result<(), String> {
+    let until = days.map(|d| chrono::Utc::now().timestamp() + d as i64 * 86_400);
+
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for id in card_ids {
+        tx.execute(
+            "UPDATE card SET buried_until = ?1 WHERE id = ?2",
+            params![until, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
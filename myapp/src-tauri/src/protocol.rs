@@ -0,0 +1,134 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use tauri::http;
+
+// Serves media files for the `appimg` protocol. Audio and video players ask
+// for byte ranges while playing and seeking, so only the requested part of a
+// file is read instead of the whole file.
+
+/// Largest part sent for any range (and for audio and video requested
+/// without one); the player asks for the rest when it needs it.
+const MAX_RANGE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// MIME type for a file served by the `appimg` protocol. Audio is mapped
+/// explicitly since web views refuse to play e.g. `.m4a` with a guessed type.
+fn content_type(path: &Path) -> String {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    match extension.as_str() {
+        "mp3" => "audio/mpeg".to_string(),
+        "ogg" => "audio/ogg".to_string(),
+        "wav" => "audio/wav".to_string(),
+        "m4a" => "audio/mp4".to_string(),
+        _ => mime_guess::from_path(path).first_or_octet_stream().to_string(),
+    }
+}
+
+/// First and last byte (inclusive) of a `Range: bytes=...` header for a file
+/// of `len` bytes, at most `MAX_RANGE_BYTES` long. `Err` if the range cannot
+/// be satisfied; multiple ranges are not supported and answered with the
+/// first one.
+fn parse_range(header: &str, len: u64) -> Result<(u64, u64), ()> {
+    if len == 0 {
+        return Err(());
+    }
+
+    let spec = header.trim().strip_prefix("bytes=").ok_or(())?;
+    let first = spec.split(',').next().unwrap_or_default().trim();
+    let (start, end) = first.split_once('-').ok_or(())?;
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // the last N bytes
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().map_err(|_| ())?;
+            if suffix == 0 {
+                return Err(());
+            }
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (start, "") => (start.parse().map_err(|_| ())?, len - 1),
+        (start, end) => (start.parse().map_err(|_| ())?, end.parse().map_err(|_| ())?),
+    };
+
+    if start > end || start >= len {
+        return Err(());
+    }
+
+    let end = end.min(len - 1).min(start.saturating_add(MAX_RANGE_BYTES - 1));
+    Ok((start, end))
+}
+
+fn error_response(status: u16, message: String) -> http::Response<Vec<u8>> {
+    http::Response::builder()
+        .status(status)
+        .body(message.into_bytes())
+        .unwrap()
+}
+
+/// The file at `path`, or the part of it asked for by a `Range` header.
+pub fn file_response(path: &Path, range: Option<&str>) -> http::Response<Vec<u8>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => return error_response(404, format!("missing file: {}", e)),
+    };
+
+    let len = match file.metadata() {
+        Ok(metadata) => metadata.len(),
+        Err(e) => return error_response(500, e.to_string()),
+    };
+
+    let mime = content_type(path);
+
+    // Players cope with partial answers, so audio and video are never read
+    // whole; images and other files are.
+    let is_media = mime.starts_with("audio/") || mime.starts_with("video/");
+    let range = match range {
+        None if is_media && len > 0 => Some("bytes=0-"),
+        range => range,
+    };
+
+    let Some(range) = range else {
+        let mut bytes = Vec::with_capacity(len as usize);
+        if let Err(e) = file.read_to_end(&mut bytes) {
+            return error_response(500, e.to_string());
+        }
+
+        return http::Response::builder()
+            .status(200)
+            .header("Content-Type", mime)
+            .header("Content-Length", bytes.len())
+            .header("Accept-Ranges", "bytes")
+            .body(bytes)
+            .unwrap();
+    };
+
+    let Ok((start, end)) = parse_range(range, len) else {
+        return http::Response::builder()
+            .status(416)
+            .header("Content-Range", format!("bytes */{}", len))
+            .body(Vec::new())
+            .unwrap();
+    };
+
+    let mut bytes = vec![0; (end - start + 1) as usize];
+    let read = file
+        .seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_exact(&mut bytes));
+    if let Err(e) = read {
+        return error_response(500, e.to_string());
+    }
+
+    http::Response::builder()
+        .status(206)
+        .header("Content-Type", mime)
+        .header("Content-Length", bytes.len())
+        .header("Content-Range", format!("bytes {}-{}/{}", start, end, len))
+        .header("Accept-Ranges", "bytes")
+        .body(bytes)
+        .unwrap()
+}
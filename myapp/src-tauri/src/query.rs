@@ -0,0 +1,381 @@
+use crate::db::{open_db, load_card, deck_subtree_ids, sort_study_queue, deck_from_row, DECK_COLUMNS};
+use crate::tags::card_has_tag_sql;
+use crate::cloze::expand_cloze_cards;
+use rusqlite::types::Value;
+use rusqlite::{params, Connection};
+use shared::models::{Card, CardPage, CardSort, Deck, FilterQuery, FilteredDeck, deck_full_name, CARD_PAGE_SIZE};
+use shared::search::{parse_query, CardState, Query, Term};
+
+
+// =======================
+// Query evaluation
+// =======================
+
+/// Translates a search AST into a condition on `card`, binding literals as
+/// positional parameters in `values`.
+pub fn query_sql(
+    conn: &Connection,
+    query: &Query,
+    now: i64,
+    values: &mut Vec<Value>,
+) -> Result<String, String> {
+    let joined = |parts: &[Query], op: &str, empty: &str, values: &mut Vec<Value>| -> Result<String, String> {
+        if parts.is_empty() {
+            return Ok(empty.to_string());
+        }
+        let sql = parts
+            .iter()
+            .map(|q| query_sql(conn, q, now, values))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(format!("({})", sql.join(op)))
+    };
+
+    match query {
+        Query::And(parts) => joined(parts, " AND ", "1", values),
+        Query::Or(parts) => joined(parts, " OR ", "0", values),
+        // conditions on NULL columns (e.g. due_at of new cards) are NULL, not false
+        Query::Not(inner) => Ok(format!("NOT coalesce({}, 0)", query_sql(conn, inner, now, values)?)),
+        Query::Term(term) => term_sql(conn, term, now, values),
+    }
+}
+
+fn term_sql(conn: &Connection, term: &Term, now: i64, values: &mut Vec<Value>) -> Result<String, String> {
+    let days_ago = |days: u32| Value::Integer(now - days as i64 * 86_400);
+
+    let sql = match term {
+        Term::Text(text) => {
+            let pattern = bind(values, Value::Text(format!("%{}%", escape_like(text))));
+            format!(
+                "(card.name LIKE {pattern} ESCAPE '\\' OR card.id IN (
+                    SELECT card_id FROM block
+                    WHERE json_extract(content, '$.value') LIKE {pattern} ESCAPE '\\'
+                       OR json_extract(content, '$.source') LIKE {pattern} ESCAPE '\\'
+                       OR json_extract(content, '$.question') LIKE {pattern} ESCAPE '\\'
+                       OR json_extract(content, '$.options') LIKE {pattern} ESCAPE '\\'
+                       OR json_extract(content, '$.expected') LIKE {pattern} ESCAPE '\\'
+                       OR json_extract(content, '$.headers') LIKE {pattern} ESCAPE '\\'
+                       OR json_extract(content, '$.rows') LIKE {pattern} ESCAPE '\\'
+                ))"
+            )
+        }
+        Term::Deck(name) => deck_ids_sql(&decks_named(conn, name)?, conn, values)?,
+        Term::DeckId(id) => deck_ids_sql(&[*id], conn, values)?,
+        Term::Tag(tag) => card_has_tag_sql(&bind(values, Value::Text(tag.clone()))),
+        Term::Is(CardState::New) => "card.due_at IS NULL".to_string(),
+        Term::Is(CardState::Due) => {
+            format!("(card.due_at IS NULL OR card.due_at <= {})", bind(values, Value::Integer(now)))
+        }
+        Term::Is(CardState::Learned) => {
+            format!("card.due_at > {}", bind(values, Value::Integer(now)))
+        }
+        Term::Is(CardState::Suspended) => "card.suspended".to_string(),
+        Term::Is(CardState::Buried) => {
+            format!("card.buried_until > {}", bind(values, Value::Integer(now)))
+        }
+        Term::Progress(comparison, percent) => format!(
+            "progress(card.times_seen, card.times_correct) {} {}",
+            comparison.sql(),
+            bind(values, Value::Integer(*percent as i64)),
+        ),
+        Term::Type(block_type) => format!(
+            "card.id IN (SELECT card_id FROM block WHERE block_type = {})",
+            bind(values, Value::Text(block_type.clone())),
+        ),
+        Term::SeenWithin(days) => format!(
+            "card.id IN (SELECT card_id FROM review_log WHERE reviewed_at >= {})",
+            bind(values, days_ago(*days)),
+        ),
+        Term::FailedWithin(days) => format!(
+            "card.id IN (SELECT card_id FROM review_log WHERE reviewed_at >= {} AND correct = 0)",
+            bind(values, days_ago(*days)),
+        ),
+    };
+
+    Ok(sql)
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Decks whose full path or leaf name equals `name` (case insensitive).
+fn decks_named(conn: &Connection, name: &str) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {DECK_COLUMNS} FROM deck WHERE deleted_at IS NULL"))
+        .map_err(|e| e.to_string())?;
+
+    let decks: Vec<Deck> = stmt
+        .query_map([], deck_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let name = name.trim().to_lowercase();
+    Ok(decks
+        .iter()
+        .filter(|d| d.name.to_lowercase() == name || deck_full_name(&decks, d.id).to_lowercase() == name)
+        .map(|d| d.id)
+        .collect())
+}
+
+/// Cards in any of the decks or their sub-decks.
+fn deck_ids_sql(deck_ids: &[i64], conn: &Connection, values: &mut Vec<Value>) -> Result<String, String> {
+    let mut ids = Vec::new();
+    for id in deck_ids {
+        ids.extend(deck_subtree_ids(conn, *id)?);
+    }
+
+    let placeholders = ids
+        .into_iter()
+        .map(|id| bind(values, Value::Integer(id)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Ok(format!("card.deck_id IN ({placeholders})"))
+}
+
+/// Ids of the cards matching the query, in no particular order.
+pub fn matching_card_ids(conn: &Connection, query: &Query, now: i64) -> Result<Vec<i64>, String> {
+    let mut values = Vec::new();
+    let condition = query_sql(conn, query, now, &mut values)?;
+
+    let mut stmt = conn
+        .prepare(&format!("SELECT card.id FROM card WHERE card.deleted_at IS NULL AND {condition}"))
+        .map_err(|e| e.to_string())?;
+
+    let ids = stmt
+        .query_map(rusqlite::params_from_iter(values), |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(ids)
+}
+
+/// Cards matching a filtered deck query, in no particular order.
+pub fn matching_cards(conn: &Connection, query: &FilterQuery, now: i64) -> Result<Vec<Card>, String> {
+    let query = query.to_query().map_err(|e| e.to_string())?;
+
+    matching_card_ids(conn, &query, now)?
+        .into_iter()
+        .map(|id| load_card(conn, id))
+        .collect()
+}
+
+/// Number of cards matching a filtered deck query, counted without loading them.
+pub fn count_matching_cards(conn: &Connection, query: &FilterQuery, now: i64) -> Result<u32, String> {
+    let query = query.to_query().map_err(|e| e.to_string())?;
+
+    let mut values = Vec::new();
+    let condition = query_sql(conn, &query, now, &mut values)?;
+
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM card WHERE card.deleted_at IS NULL AND {condition}"),
+        rusqlite::params_from_iter(values),
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Adds a positional parameter and returns its placeholder (`?N`).
+fn bind(values: &mut Vec<Value>, value: Value) -> String {
+    values.push(value);
+    format!("?{}", values.len())
+}
+
+fn load_filtered_deck(conn: &Connection, id: i64) -> Result<FilteredDeck, String> {
+    let (name, query, created_at): (String, String, i64) = conn
+        .query_row(
+            "SELECT name, query, created_at FROM filtered_deck WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(FilteredDeck {
+        id,
+        name,
+        query: serde_json::from_str(&query).map_err(|e| e.to_string())?,
+        created_at,
+        card_count: 0,
+    })
+}
+
+
+// =======================
+// Tauri commands
+// =======================
+
+#[tauri::command]
+pub fn list_filtered_decks(app: tauri::AppHandle) -> Result<Vec<FilteredDeck>, String> {
+    let conn = open_db(&app)?;
+    let now = chrono::Utc::now().timestamp();
+
+    let mut stmt = conn
+        .prepare("SELECT id FROM filtered_deck ORDER BY name")
+        .map_err(|e| e.to_string())?;
+
+    let ids: Vec<i64> = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut decks = Vec::new();
+    for id in ids {
+        let mut deck = load_filtered_deck(&conn, id)?;
+        deck.card_count = count_matching_cards(&conn, &deck.query, now)?;
+        decks.push(deck);
+    }
+
+    Ok(decks)
+}
+
+#[tauri::command]
+pub fn get_filtered_deck(app: tauri::AppHandle, id: i64) -> Result<FilteredDeck, String> {
+    let conn = open_db(&app)?;
+    load_filtered_deck(&conn, id)
+}
+
+/// Creates a filtered deck (`id` = None) or updates an existing one.
+/// Returns its id.
+#[tauri::command]
+pub fn save_filtered_deck(
+    app: tauri::AppHandle,
+    id: Option<i64>,
+    name: String,
+    query: FilterQuery,
+) -> Result<i64, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Filtered deck name must not be empty".into());
+    }
+
+    let conn = open_db(&app)?;
+    let query = serde_json::to_string(&query).map_err(|e| e.to_string())?;
+
+    match id {
+        Some(id) => {
+            conn.execute(
+                "UPDATE filtered_deck SET name = ?1, query = ?2 WHERE id = ?3",
+                params![name, query, id],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(id)
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO filtered_deck (name, query, created_at) VALUES (?1, ?2, ?3)",
+                params![name, query, chrono::Utc::now().timestamp()],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+/// Deletes only the saved query; the cards are untouched.
+#[tauri::command]
+pub fn delete_filtered_deck(app: tauri::AppHandle, id: i64) -> Result<(), String> {
+    let conn = open_db(&app)?;
+    conn.execute("DELETE FROM filtered_deck WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Number of cards a query would select, shown while editing it.
+#[tauri::command]
+pub fn count_filter_matches(app: tauri::AppHandle, query: FilterQuery) -> Result<u32, String> {
+    let conn = open_db(&app)?;
+    let now = chrono::Utc::now().timestamp();
+    count_matching_cards(&conn, &query, now)
+}
+
+/// All active cards of a filtered deck in study order. Unlike a regular deck
+/// the due state is left to the query, so e.g. recently failed cards can be
+/// repeated before they are due again.
+#[tauri::command]
+pub fn get_filtered_study_queue(app: tauri::AppHandle, id: i64) -> Result<Vec<Card>, String> {
+    let conn = open_db(&app)?;
+    let deck = load_filtered_deck(&conn, id)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let mut cards = expand_cloze_cards(&conn, matching_cards(&conn, &deck.query, now)?)?;
+    cards.retain(|c| c.is_active(now));
+    sort_study_queue(&mut cards);
+    Ok(cards)
+}
+
+/// Cards matching a search query, ordered by name. Parse errors are returned
+/// as their message.
+#[tauri::command]
+pub fn search_cards(app: tauri::AppHandle, query: String) -> Result<Vec<Card>, String> {
+    let query = parse_query(&query).map_err(|e| e.to_string())?;
+    let conn = open_db(&app)?;
+
+    let mut cards = matching_card_ids(&conn, &query, chrono::Utc::now().timestamp())?
+        .into_iter()
+        .map(|id| load_card(&conn, id))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    cards.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    Ok(cards)
+}
+
+/// One page of the cards matching `query`, for the card browser.
+#[tauri::command]
+pub fn list_cards(
+    app: tauri::AppHandle,
+    query: String,
+    sort: CardSort,
+    descending: bool,
+    page: u32,
+) -> Result<CardPage, String> {
+    let query = parse_query(&query).map_err(|e| e.to_string())?;
+    let conn = open_db(&app)?;
+
+    let mut values = Vec::new();
+    let condition = query_sql(&conn, &query, chrono::Utc::now().timestamp(), &mut values)?;
+
+    let total: u32 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM card WHERE card.deleted_at IS NULL AND {condition}"),
+            rusqlite::params_from_iter(values.iter()),
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let direction = if descending { "DESC" } else { "ASC" };
+    let order = match sort {
+        CardSort::Name => format!("card.name COLLATE NOCASE {direction}"),
+        CardSort::Created => format!("card.created_at {direction}"),
+        CardSort::Progress => format!("progress(card.times_seen, card.times_correct) {direction}"),
+        CardSort::TimesSeen => format!("card.times_seen {direction}"),
+        // never studied cards (no due date) last in either direction
+        CardSort::Due => format!("card.due_at IS NULL, card.due_at {direction}"),
+    };
+
+    let limit = bind(&mut values, Value::Integer(CARD_PAGE_SIZE as i64));
+    let offset = bind(&mut values, Value::Integer(page as i64 * CARD_PAGE_SIZE as i64));
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT card.id FROM card WHERE card.deleted_at IS NULL AND {condition}
+             ORDER BY {order}, card.id {direction}
+             LIMIT {limit} OFFSET {offset}"
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let ids: Vec<i64> = stmt
+        .query_map(rusqlite::params_from_iter(values), |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let cards = ids
+        .into_iter()
+        .map(|id| load_card(&conn, id))
+        .collect::<Result<_, _>>()?;
+
+    Ok(CardPage { cards, total, page })
+}
@@ -0,0 +1,60 @@
+use shared::markdown::escape_html;
+use shared::models::{Block, EditorBlock};
+use shared::tex::{math_block_errors, validate_tex, BlockTexError, TexError};
+use std::sync::OnceLock;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+// Some blocks are turned into HTML when they are saved, so that studying
+// needs neither a network connection nor a highlighter in the web view.
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        themes.themes.remove("InspiredGitHub").unwrap_or_default()
+    })
+}
+
+/// Highlighted `<pre>` for source code; unknown languages are shown as plain text.
+pub fn highlight_code(language: &str, source: &str) -> String {
+    let syntaxes = syntax_set();
+    let syntax = syntaxes
+        .find_syntax_by_token(language.trim())
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+
+    highlighted_html_for_string(source, syntaxes, syntax, theme())
+        .unwrap_or_else(|_| format!("<pre>{}</pre>", escape_html(source)))
+}
+
+/// The block with its HTML (re)generated. HTML coming from the editor or an
+/// imported archive is never trusted.
+pub fn render_block_html(block: &Block) -> Block {
+    match block {
+        Block::Code { language, source, .. } => Block::Code {
+            language: language.clone(),
+            source: source.clone(),
+            html: highlight_code(language, source),
+        },
+        _ => block.clone(),
+    }
+}
+
+/// Problems in the TeX of a math block, shown while it is edited.
+#[tauri::command]
+pub fn validate_math(source: String) -> Vec<TexError> {
+    validate_tex(&source)
+}
+
+/// Problems in the TeX of all math blocks of a card, checked when it is saved.
+pub fn card_math_errors(front: &[EditorBlock], back: &[EditorBlock]) -> Vec<BlockTexError> {
+    let mut errors = math_block_errors("front", front.iter().map(|b| &b.block));
+    errors.extend(math_block_errors("back", back.iter().map(|b| &b.block)));
+    errors
+}
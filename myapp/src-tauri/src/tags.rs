@@ -0,0 +1,184 @@
+use crate::db::{open_db, prune_unused_tags, card_from_row, CARD_COLUMNS};
+use rusqlite::{params, Connection};
+use shared::models::{Card, TagInfo};
+use shared::tags::{normalize_tag, tag_matches, TAG_SEPARATOR};
+
+
+// =======================
+// SQL helpers
+// =======================
+
+/// Condition on `card` that is true if the card carries the tag bound to
+/// `param` (e.g. `?1`) or one of its descendants (`ml` → `ml`, `ml::knn`).
+pub fn card_has_tag_sql(param: &str) -> String {
+    format!(
+        "card.id IN (
+            SELECT card_tag.card_id FROM card_tag JOIN tag ON tag.id = card_tag.tag_id
+            WHERE tag.name = {param}
+               OR substr(tag.name, 1, length({param}) + {sep}) = {param} || '{TAG_SEPARATOR}'
+        )",
+        sep = TAG_SEPARATOR.len(),
+    )
+}
+
+fn all_tags(conn: &Connection) -> Result<Vec<(i64, String)>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, name FROM tag ORDER BY name")
+        .map_err(|e| e.to_string())?;
+
+    let tags = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(tags)
+}
+
+/// Renames `old` and all of its descendants. If a new name is already taken
+/// the two tags are merged.
+fn rename_tag_tx(conn: &Connection, old: &str, new: &str) -> Result<(), String> {
+    for (id, name) in all_tags(conn)? {
+        if !tag_matches(&name, old) {
+            continue;
+        }
+
+        let renamed = format!("{}{}", new, &name[old.len()..]);
+        if renamed == name {
+            continue;
+        }
+
+        let existing: Option<i64> = conn
+            .query_row("SELECT id FROM tag WHERE name = ?1", [&renamed], |row| row.get(0))
+            .ok();
+
+        match existing {
+            Some(target_id) => {
+                conn.execute(
+                    "INSERT OR IGNORE INTO card_tag (card_id, tag_id)
+                     SELECT card_id, ?1 FROM card_tag WHERE tag_id = ?2",
+                    params![target_id, id],
+                )
+                .map_err(|e| e.to_string())?;
+                conn.execute("DELETE FROM card_tag WHERE tag_id = ?1", [id])
+                    .map_err(|e| e.to_string())?;
+                conn.execute("DELETE FROM tag WHERE id = ?1", [id])
+                    .map_err(|e| e.to_string())?;
+            }
+            None => {
+                conn.execute("UPDATE tag SET name = ?1 WHERE id = ?2", params![renamed, id])
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn required_tag(raw: &str) -> Result<String, String> {
+    normalize_tag(raw).ok_or_else(|| "Tag name must not be empty".to_string())
+}
+
+
+// =======================
+// Tauri commands
+// =======================
+
+#[tauri::command]
+pub fn list_tags(app: tauri::AppHandle) -> Result<Vec<TagInfo>, String> {
+    let conn = open_db(&app)?;
+
+    let mut stmt = conn
+        .prepare(
+            "
+            SELECT tag.name, COUNT(card.id)
+            FROM tag
+            LEFT JOIN card_tag ON card_tag.tag_id = tag.id
+            LEFT JOIN card ON card.id = card_tag.card_id AND card.deleted_at IS NULL
+            GROUP BY tag.id
+            ORDER BY tag.name
+            ",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let tags = stmt
+        .query_map([], |row| {
+            Ok(TagInfo {
+                name: row.get(0)?,
+                card_count: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(tags)
+}
+
+/// Cards carrying the tag or one of its descendants, across all decks.
+#[tauri::command]
+pub fn get_cards_with_tag(app: tauri::AppHandle, tag: String) -> Result<Vec<Card>, String> {
+    let tag = required_tag(&tag)?;
+    let conn = open_db(&app)?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {CARD_COLUMNS} FROM card WHERE card.deleted_at IS NULL AND {} ORDER BY card.name",
+            card_has_tag_sql("?1"),
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let cards = stmt
+        .query_map([tag], card_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(cards)
+}
+
+/// Renames a tag (and its descendants) on all cards.
+#[tauri::command]
+pub fn rename_tag(app: tauri::AppHandle, old_name: String, new_name: String) -> Result<(), String> {
+    let old = required_tag(&old_name)?;
+    let new = required_tag(&new_name)?;
+
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    rename_tag_tx(&tx, &old, &new)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Merges the source tags into `target`; cards keep a single `target` tag.
+#[tauri::command]
+pub fn merge_tags(app: tauri::AppHandle, sources: Vec<String>, target: String) -> Result<(), String> {
+    let target = required_tag(&target)?;
+
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for source in sources {
+        rename_tag_tx(&tx, &required_tag(&source)?, &target)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Removes a tag (and its descendants) from all cards.
+#[tauri::command]
+pub fn delete_tag(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    let name = required_tag(&name)?;
+
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    for (id, tag) in all_tags(&tx)? {
+        if tag_matches(&tag, &name) {
+            tx.execute("DELETE FROM card_tag WHERE tag_id = ?1", [id])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    prune_unused_tags(&tx)?;
+    tx.commit().map_err(|e| e.to_string())
+}
@@ -0,0 +1,462 @@
+use crate::db::{
+    open_db, deck_subtree_ids_with_trashed, card_file_paths, delete_card_tx, delete_file_from_app_data,
+    deck_from_row, DECK_COLUMNS,
+};
+use crate::undo::undo_actions;
+use crate::history::revision_file_paths;
+use crate::drafts::draft_file_paths;
+use rusqlite::{params, Connection, OptionalExtension};
+use shared::models::{Block, Deck, Trash, TrashedItem, deck_full_name};
+use std::collections::HashSet;
+use tauri::Manager;
+
+// Deleting a card or deck only sets its `deleted_at`. Trashed rows are
+// skipped by every listing and count and can be restored until they are
+// older than the retention period. Only then `purge_trash` removes the rows
+// and the media files for good.
+
+const RETENTION_DAYS_KEY: &str = "trash_retention_days";
+const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+
+// =======================
+// Settings
+// =======================
+
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM setting WHERE key = ?1", [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO setting (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn retention_days(conn: &Connection) -> Result<u32, String> {
+    Ok(get_setting(conn, RETENTION_DAYS_KEY)?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS))
+}
+
+
+// =======================
+// Trash and restore
+// =======================
+
+pub fn trash_cards_tx(conn: &Connection, card_ids: &[i64], now: i64) -> Result<(), String> {
+    for id in card_ids {
+        // deck.card_count is decremented by the card_count_trash trigger
+        conn.execute(
+            "UPDATE card SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Trashes a deck with its sub-decks and all of their cards. They share the
+/// same `deleted_at`, which is how `restore_deck_tx` finds them again.
+pub fn trash_deck_tx(conn: &Connection, deck_id: i64, now: i64) -> Result<(), String> {
+    for id in deck_subtree_ids_with_trashed(conn, deck_id)? {
+        conn.execute(
+            "UPDATE deck SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "UPDATE card SET deleted_at = ?1 WHERE deck_id = ?2 AND deleted_at IS NULL",
+            params![now, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Restores a deck and all of its parents (but not their cards), so that a
+/// restored card or sub-deck is reachable again.
+fn restore_deck_path(conn: &Connection, deck_id: i64) -> Result<(), String> {
+    let mut current = Some(deck_id);
+    let mut seen = HashSet::new();
+
+    while let Some(id) = current {
+        if !seen.insert(id) {
+            break; // defensive: never loop on a broken parent chain
+        }
+
+        conn.execute("UPDATE deck SET deleted_at = NULL WHERE id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+
+        current = conn
+            .query_row("SELECT parent_id FROM deck WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?
+            .flatten();
+    }
+
+    Ok(())
+}
+
+pub fn restore_cards_tx(conn: &Connection, card_ids: &[i64]) -> Result<(), String> {
+    for id in card_ids {
+        let deck_id: i64 = conn
+            .query_row("SELECT deck_id FROM card WHERE id = ?1", [id], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Card {} has already been deleted for good", id))?;
+
+        restore_deck_path(conn, deck_id)?;
+
+        conn.execute("UPDATE card SET deleted_at = NULL WHERE id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+pub fn restore_deck_tx(conn: &Connection, deck_id: i64) -> Result<(), String> {
+    let deleted_at: Option<i64> = conn
+        .query_row("SELECT deleted_at FROM deck WHERE id = ?1", [deck_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Deck {} has already been deleted for good", deck_id))?;
+
+    let Some(deleted_at) = deleted_at else {
+        return Ok(()); // not in the trash
+    };
+
+    for id in deck_subtree_ids_with_trashed(conn, deck_id)? {
+        conn.execute(
+            "UPDATE deck SET deleted_at = NULL WHERE id = ?1 AND deleted_at = ?2",
+            params![id, deleted_at],
+        )
+        .map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "UPDATE card SET deleted_at = NULL WHERE deck_id = ?1 AND deleted_at = ?2",
+            params![id, deleted_at],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    restore_deck_path(conn, deck_id)
+}
+
+
+// =======================
+// Purge
+// =======================
+
+fn query_ids(conn: &Connection, sql: &str, param: i64) -> Result<Vec<i64>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+
+    let ids = stmt
+        .query_map([param], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(ids)
+}
+
+/// Deletes the card rows and returns the media files they referenced.
+fn purge_cards_tx(conn: &Connection, card_ids: &[i64]) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+
+    for id in card_ids {
+        files.extend(card_file_paths(conn, *id)?);
+        delete_card_tx(conn, *id)?;
+    }
+
+    Ok(files)
+}
+
+/// Deletes trashed decks (trashed up to `cutoff`) that have no cards and no
+/// sub-decks left, children before parents.
+fn purge_empty_decks_tx(conn: &Connection, cutoff: i64) -> Result<(), String> {
+    loop {
+        let removed = conn
+            .execute(
+                "
+                DELETE FROM deck
+                WHERE deleted_at IS NOT NULL AND deleted_at <= ?1
+                  AND id NOT IN (SELECT deck_id FROM card)
+                  AND id NOT IN (SELECT parent_id FROM deck WHERE parent_id IS NOT NULL)
+                ",
+                [cutoff],
+            )
+            .map_err(|e| e.to_string())?;
+
+        if removed == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Removes media files that no card (including trashed ones), no revision
+/// and no undo entry references anymore, once they are older than the retention period.
+/// Files of removed blocks end up here instead of being deleted right away.
+fn remove_orphan_files(app: &tauri::AppHandle, conn: &Connection, cutoff: i64) -> Result<(), String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let Ok(entries) = std::fs::read_dir(app_data_dir.join("files")) else {
+        return Ok(()); // no files yet
+    };
+
+    let mut stmt = conn
+        .prepare("SELECT content FROM block")
+        .map_err(|e| e.to_string())?;
+
+    let contents: Vec<String> = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut referenced = HashSet::new();
+    for content in contents {
+        // a block we cannot read may still point at a file; better keep
+        // every file than delete one that is in use
+        let Ok(block) = serde_json::from_str::<Block>(&content) else {
+            eprintln!("Skipping the file sweep: a block could not be read");
+            return Ok(());
+        };
+        referenced.extend(block.file_path().map(str::to_string));
+    }
+
+    for action in undo_actions(conn)? {
+        referenced.extend(action.file_paths());
+    }
+    referenced.extend(revision_file_paths(conn)?);
+    referenced.extend(draft_file_paths(conn)?);
+
+    for entry in entries.flatten() {
+        let virtual_path = format!("files/{}", entry.file_name().to_string_lossy());
+        if referenced.contains(&virtual_path) {
+            continue;
+        }
+
+        let modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+
+        if modified.is_some_and(|m| m <= cutoff) {
+            if let Err(e) = delete_file_from_app_data(app, &virtual_path) {
+                eprintln!("Removing an unused file: {}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Deletes everything that was trashed up to `cutoff` for good, together with
+/// expired undo entries and orphaned media files.
+fn purge_trash(app: &tauri::AppHandle, cutoff: i64) -> Result<(), String> {
+    let mut conn = open_db(app)?;
+    let now = chrono::Utc::now().timestamp();
+    let expired = now - retention_days(&conn)? as i64 * 86_400;
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let card_ids = query_ids(
+        &tx,
+        "SELECT id FROM card WHERE deleted_at IS NOT NULL AND deleted_at <= ?1",
+        cutoff,
+    )?;
+    let files = purge_cards_tx(&tx, &card_ids)?;
+    purge_empty_decks_tx(&tx, cutoff)?;
+
+    tx.execute("DELETE FROM undo_log WHERE created_at <= ?1", [expired])
+        .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    for path in &files {
+        delete_file_from_app_data(app, path)?;
+    }
+
+    remove_orphan_files(app, &conn, expired)
+}
+
+/// Purges everything older than the retention period. Run on startup.
+pub fn purge_expired(app: &tauri::AppHandle) -> Result<(), String> {
+    let conn = open_db(app)?;
+    let cutoff = chrono::Utc::now().timestamp() - retention_days(&conn)? as i64 * 86_400;
+    purge_trash(app, cutoff)
+}
+
+
+// =======================
+// Tauri commands
+// =======================
+
+#[tauri::command]
+pub fn list_trash(app: tauri::AppHandle) -> Result<Trash, String> {
+    let conn = open_db(&app)?;
+
+    let mut stmt = conn
+        .prepare(&format!("SELECT {DECK_COLUMNS} FROM deck"))
+        .map_err(|e| e.to_string())?;
+
+    let all_decks: Vec<Deck> = stmt
+        .query_map([], deck_from_row)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // Sub-decks trashed together with their parent are part of that entry
+    let mut stmt = conn
+        .prepare(
+            "
+            SELECT deck.id, deck.deleted_at FROM deck
+            LEFT JOIN deck AS parent ON parent.id = deck.parent_id
+            WHERE deck.deleted_at IS NOT NULL
+              AND (parent.deleted_at IS NULL OR parent.deleted_at <> deck.deleted_at)
+            ORDER BY deck.deleted_at DESC
+            ",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let decks = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(id, deleted_at)| TrashedItem {
+            id,
+            name: deck_full_name(&all_decks, id),
+            deleted_at,
+        })
+        .collect();
+
+    let mut stmt = conn
+        .prepare(
+            "
+            SELECT card.id, card.name, card.deleted_at FROM card
+            JOIN deck ON deck.id = card.deck_id
+            WHERE card.deleted_at IS NOT NULL
+              AND (deck.deleted_at IS NULL OR deck.deleted_at <> card.deleted_at)
+            ORDER BY card.deleted_at DESC
+            ",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let cards = stmt
+        .query_map([], |row| {
+            Ok(TrashedItem {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                deleted_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(Trash {
+        decks,
+        cards,
+        retention_days: retention_days(&conn)?,
+    })
+}
+
+#[tauri::command]
+pub fn restore_card(app: tauri::AppHandle, id: i64) -> Result<(), String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    restore_cards_tx(&tx, &[id])?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn restore_deck(app: tauri::AppHandle, id: i64) -> Result<(), String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    restore_deck_tx(&tx, id)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Deletes a trashed card and its media files for good.
+#[tauri::command]
+pub fn purge_card(app: tauri::AppHandle, id: i64) -> Result<(), String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let files = purge_cards_tx(
+        &tx,
+        &query_ids(&tx, "SELECT id FROM card WHERE id = ?1 AND deleted_at IS NOT NULL", id)?,
+    )?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    for path in &files {
+        delete_file_from_app_data(&app, path)?;
+    }
+
+    Ok(())
+}
+
+/// Deletes a trashed deck, its trashed sub-decks and their cards for good.
+#[tauri::command]
+pub fn purge_deck(app: tauri::AppHandle, id: i64) -> Result<(), String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut files = Vec::new();
+
+    for deck_id in deck_subtree_ids_with_trashed(&tx, id)?.into_iter().rev() {
+        let trashed: bool = tx
+            .query_row(
+                "SELECT deleted_at IS NOT NULL FROM deck WHERE id = ?1",
+                [deck_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+
+        if !trashed {
+            continue;
+        }
+
+        let card_ids = query_ids(&tx, "SELECT id FROM card WHERE deck_id = ?1", deck_id)?;
+        files.extend(purge_cards_tx(&tx, &card_ids)?);
+
+        tx.execute(
+            "DELETE FROM deck WHERE id = ?1 AND id NOT IN (SELECT parent_id FROM deck WHERE parent_id IS NOT NULL)",
+            [deck_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+
+    for path in &files {
+        delete_file_from_app_data(&app, path)?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn empty_trash(app: tauri::AppHandle) -> Result<(), String> {
+    purge_trash(&app, chrono::Utc::now().timestamp())
+}
+
+#[tauri::command]
+pub fn set_trash_retention_days(app: tauri::AppHandle, days: u32) -> Result<(), String> {
+    let conn = open_db(&app)?;
+    set_setting(&conn, RETENTION_DAYS_KEY, &days.to_string())
+}
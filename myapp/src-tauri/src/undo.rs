@@ -0,0 +1,176 @@
+use crate::db::{open_db, load_card, set_card_tags_tx, write_blocks_tx};
+use crate::trash::{restore_cards_tx, restore_deck_tx};
+use crate::history::record_revision_tx;
+use crate::cloze::{save_cloze_schedule_tx, ClozeSchedule};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use shared::models::Card;
+
+/// Number of actions that can be undone.
+const UNDO_LIMIT: i64 = 50;
+
+/// What is needed to revert one action, stored as JSON in `undo_log`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum UndoAction {
+    /// Scores and scheduling before an answer, plus the review it logged.
+    Grade {
+        card_id: i64,
+        times_seen: u32,
+        times_correct: u32,
+        due_at: Option<i64>,
+        interval_days: u32,
+        review_id: i64,
+        /// The answered cloze deletion, as it was before.
+        #[serde(default)]
+        cloze: Option<ClozeSchedule>,
+    },
+    DeleteCards { card_ids: Vec<i64> },
+    DeleteDeck { deck_id: i64 },
+    /// The complete card (name, tags, blocks) before it was edited.
+    EditCard { card: Card },
+}
+
+impl UndoAction {
+    /// Media files the action would bring back, so the trash purge keeps them.
+    pub fn file_paths(&self) -> Vec<String> {
+        match self {
+            UndoAction::EditCard { card } => card
+                .all_blocks()
+                .filter_map(|b| b.file_path())
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+pub fn record_undo(conn: &Connection, label: &str, action: &UndoAction) -> Result<(), String> {
+    let action = serde_json::to_string(action).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO undo_log (created_at, label, action) VALUES (?1, ?2, ?3)",
+        params![chrono::Utc::now().timestamp(), label, action],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "DELETE FROM undo_log WHERE id NOT IN (SELECT id FROM undo_log ORDER BY id DESC LIMIT ?1)",
+        [UNDO_LIMIT],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// All logged actions, oldest first.
+pub fn undo_actions(conn: &Connection) -> Result<Vec<UndoAction>, String> {
+    let mut stmt = conn
+        .prepare("SELECT action FROM undo_log ORDER BY id")
+        .map_err(|e| e.to_string())?;
+
+    let actions = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+
+    Ok(actions)
+}
+
+fn apply_undo_tx(conn: &Connection, action: UndoAction) -> Result<(), String> {
+    match action {
+        UndoAction::Grade { card_id, times_seen, times_correct, due_at, interval_days, review_id, cloze } => {
+            conn.execute(
+                "
+                UPDATE card
+                SET times_seen = ?1, times_correct = ?2, due_at = ?3, interval_days = ?4
+                WHERE id = ?5
+                ",
+                params![times_seen, times_correct, due_at, interval_days, card_id],
+            )
+            .map_err(|e| e.to_string())?;
+
+            if let Some(schedule) = cloze {
+                save_cloze_schedule_tx(conn, card_id, &schedule)?;
+            }
+
+            conn.execute("DELETE FROM review_log WHERE id = ?1", [review_id])
+                .map_err(|e| e.to_string())?;
+        }
+        UndoAction::DeleteCards { card_ids } => restore_cards_tx(conn, &card_ids)?,
+        UndoAction::DeleteDeck { deck_id } => restore_deck_tx(conn, deck_id)?,
+        UndoAction::EditCard { card } => {
+            // fails if the card has been purged from the trash in the meantime
+            load_card(conn, card.id)?;
+
+            conn.execute(
+                "UPDATE card SET name = ?1 WHERE id = ?2",
+                params![card.name, card.id],
+            )
+            .map_err(|e| e.to_string())?;
+
+            set_card_tags_tx(conn, card.id, &card.tag_list())?;
+            write_blocks_tx(conn, card.id, &card.front_blocks, &card.back_blocks)?;
+            record_revision_tx(conn, card.id)?;
+        }
+    }
+
+    Ok(())
+}
+
+
+// =======================
+// Tauri commands
+// =======================
+
+/// Description of the action `undo_last` would revert, if any.
+#[tauri::command]
+pub fn last_undo(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    let conn = open_db(&app)?;
+
+    conn.query_row("SELECT label FROM undo_log ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+/// Reverts the most recent grading, deletion or edit. Returns a message for
+/// the user, or `None` if there was nothing to undo.
+#[tauri::command]
+pub fn undo_last(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    let mut conn = open_db(&app)?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let last: Option<(i64, String, String)> = tx
+        .query_row(
+            "SELECT id, label, action FROM undo_log ORDER BY id DESC LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let Some((id, label, action)) = last else {
+        return Ok(None);
+    };
+
+    let applied = serde_json::from_str(&action)
+        .map_err(|e| e.to_string())
+        .and_then(|action| apply_undo_tx(&tx, action));
+
+    if let Err(e) = applied {
+        // e.g. the card was purged from the trash since; drop the entry so
+        // it does not block the ones before it
+        drop(tx);
+        conn.execute("DELETE FROM undo_log WHERE id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+        return Ok(Some(format!("Could not undo {}: {}", label, e)));
+    }
+
+    tx.execute("DELETE FROM undo_log WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(Some(format!("Undone: {}", label)))
+}
@@ -1,7 +1,7 @@
 use dioxus::prelude::*;
 use dioxus::document::{Script, Stylesheet};
 // use dioxus_router::prelude::*;
-use crate::components::{ DeckList, CardView, CardListPage, CardEditorEdit, CardEditorNew };
+use crate::components::{ DeckList, CardView, CardListPage, CardEditorEdit, CardEditorNew, CardEditorDraft, NoteEditorNew, NoteEditorEdit, StudySession, FilteredStudySession, TagManager, Search, Trash };
 use shared::models::*;
 
 #[derive(Clone, Debug, PartialEq, Routable)]
@@ -12,6 +12,12 @@ pub enum Route {
     #[route("/deck/:id")] 
     CardListPage {id: i64,},
 
+    #[route("/deck/:id/study")]
+    StudySession { id: i64 },
+
+    #[route("/filtered/:id/study")]
+    FilteredStudySession { id: i64 },
+
     #[route("/card/:id")] 
     CardView { id: i64, },
 
@@ -20,6 +26,24 @@ pub enum Route {
 
     #[route("/card/new/:deck_id")]
     CardEditorNew { deck_id: i64 },
+
+    #[route("/draft/:id")]
+    CardEditorDraft { id: i64 },
+
+    #[route("/note/new/:deck_id")]
+    NoteEditorNew { deck_id: i64 },
+
+    #[route("/note/:id")]
+    NoteEditorEdit { id: i64 },
+
+    #[route("/tags")]
+    TagManager {},
+
+    #[route("/search")]
+    Search {},
+
+    #[route("/trash")]
+    Trash {},
 }
 
 static CSS: Asset = asset!("/assets/styles.css");
@@ -14,7 +14,34 @@ pub mod block_editor;
 pub use block_editor::BlockEditor;
 
 pub mod card_editor;
-pub use card_editor::{CardEditor, CardEditorEdit, CardEditorNew};
+pub use card_editor::{CardEditor, CardEditorEdit, CardEditorNew, CardEditorDraft};
 
 pub mod create_deck;
 pub use create_deck::CreateDeck;
+
+pub mod import_deck;
+pub use import_deck::ImportDeck;
+
+pub mod study;
+pub use study::{CardStudy, StudySession, FilteredStudySession};
+
+pub mod deck_tools;
+pub use deck_tools::{MergeDeck, SplitDeck};
+
+pub mod tag_manager;
+pub use tag_manager::TagManager;
+
+pub mod filtered_decks;
+pub use filtered_decks::{FilteredDeckList, FilteredDeckEditor};
+
+pub mod search;
+pub use search::Search;
+
+pub mod trash;
+pub use trash::Trash;
+
+pub mod note_editor;
+pub use note_editor::{NoteEditorNew, NoteEditorEdit};
+
+pub mod card_history;
+pub use card_history::CardHistory;
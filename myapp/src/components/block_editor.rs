@@ -1,6 +1,11 @@
 use shared::models::*;
 use dioxus::prelude::*;
-use crate::tauri_api::{delete_block_from_app_data, pick_image, pick_archive};
+use shared::cloze::cloze_ordinals;
+use shared::table::{ column_count, padded_row, parse_csv };
+use std::rc::Rc;
+use crate::components::block_view::{ MarkdownBlock, TableBlock, appimg_url_from_virtual_path, mask_style };
+use crate::tauri_api::{pick_image, pick_archive, validate_math};
+use crate::timer::sleep;
 
 #[component]
 pub fn BlockEditor(
@@ -17,6 +22,15 @@ pub fn BlockEditor(
             button { onclick: move |_| handler.call(InsertBlockKind::Math), "＋ Math" }
             button { onclick: move |_| handler.call(InsertBlockKind::Image), "＋ Image" }
             button { onclick: move |_| handler.call(InsertBlockKind::File), "＋ File" }
+            button { onclick: move |_| handler.call(InsertBlockKind::Audio), "＋ Audio" }
+            button { onclick: move |_| handler.call(InsertBlockKind::Video), "＋ Video" }
+            button { onclick: move |_| handler.call(InsertBlockKind::Cloze), "＋ Cloze" }
+            button { onclick: move |_| handler.call(InsertBlockKind::Markdown), "＋ Markdown" }
+            button { onclick: move |_| handler.call(InsertBlockKind::Code), "＋ Code" }
+            button { onclick: move |_| handler.call(InsertBlockKind::MultipleChoice), "＋ Choice" }
+            button { onclick: move |_| handler.call(InsertBlockKind::TypeAnswer), "＋ Typed answer" }
+            button { onclick: move |_| handler.call(InsertBlockKind::Occlusion), "＋ Occlusion" }
+            button { onclick: move |_| handler.call(InsertBlockKind::Table), "＋ Table" }
         }
     );
 
@@ -58,6 +72,224 @@ pub fn BlockEditor(
                     }
                 }
 
+                TexErrors { source: value }
+
+                {insert_menu(on_insert_below.clone())}
+
+                button {
+                    onclick: move |_| on_remove.call(()),
+                    "🗑 Remove"
+                }
+            }
+        ),
+
+        Block::Cloze { value } => {
+            let ordinals = cloze_ordinals([&Block::Cloze { value: value.clone() }]);
+            let status = if ordinals.is_empty() {
+                "No deletions yet".to_string()
+            } else {
+                format!("{} review items", ordinals.len())
+            };
+
+            rsx!(
+                div { class: "block-editor cloze-editor",
+
+                    {insert_menu(on_insert_above.clone())}
+
+                    textarea {
+                        placeholder: "The capital of {{{{c1::France}}}} is {{{{c2::Paris}}}}",
+                        value: "{value}",
+                        oninput: move |evt| {
+                            on_update.call(Block::Cloze {
+                                value: evt.value().to_string()
+                            });
+                        }
+                    }
+
+                    p { class: "cloze-status", "{status}" }
+
+                    {insert_menu(on_insert_below.clone())}
+
+                    button {
+                        onclick: move |_| on_remove.call(()),
+                        "🗑 Remove"
+                    }
+                }
+            )
+        }
+
+        Block::Markdown { value } => rsx!(
+            div { class: "block-editor markdown-editor",
+
+                {insert_menu(on_insert_above.clone())}
+
+                textarea {
+                    placeholder: "**Bold**, *italic*, - lists, [links](https://...) and $math$",
+                    value: "{value}",
+                    oninput: move |evt| {
+                        on_update.call(Block::Markdown {
+                            value: evt.value().to_string()
+                        });
+                    }
+                }
+
+                div { class: "markdown-preview",
+                    MarkdownBlock { value: value.clone() }
+                }
+
+                {insert_menu(on_insert_below.clone())}
+
+                button {
+                    onclick: move |_| on_remove.call(()),
+                    "🗑 Remove"
+                }
+            }
+        ),
+
+        Block::Code { language, source, .. } => {
+            let source_for_language = source.clone();
+            let language_for_source = language.clone();
+
+            rsx!(
+                div { class: "block-editor code-editor",
+
+                    {insert_menu(on_insert_above.clone())}
+
+                    input {
+                        class: "code-language",
+                        placeholder: "Language, e.g. rust, python, sql",
+                        value: "{language}",
+                        oninput: move |evt| {
+                            on_update.call(Block::Code {
+                                language: evt.value(),
+                                source: source_for_language.clone(),
+                                html: String::new(),
+                            });
+                        }
+                    }
+
+                    textarea {
+                        class: "code-source",
+                        spellcheck: false,
+                        value: "{source}",
+                        oninput: move |evt| {
+                            on_update.call(Block::Code {
+                                language: language_for_source.clone(),
+                                source: evt.value(),
+                                html: String::new(),
+                            });
+                        }
+                    }
+
+                    {insert_menu(on_insert_below.clone())}
+
+                    button {
+                        onclick: move |_| on_remove.call(()),
+                        "🗑 Remove"
+                    }
+                }
+            )
+        }
+
+        choice @ Block::MultipleChoice { .. } => rsx!(
+            div { class: "block-editor choice-editor",
+
+                {insert_menu(on_insert_above.clone())}
+
+                MultipleChoiceEditor { block: choice, on_update }
+
+                {insert_menu(on_insert_below.clone())}
+
+                button {
+                    onclick: move |_| on_remove.call(()),
+                    "🗑 Remove"
+                }
+            }
+        ),
+
+        Block::TypeAnswer { expected, case_sensitive, accept } => {
+            let accept_lines = accept.join("\n");
+            let (accept_for_expected, accept_for_case) = (accept.clone(), accept.clone());
+            let (expected_for_case, expected_for_accept) = (expected.clone(), expected.clone());
+
+            rsx!(
+                div { class: "block-editor type-answer-editor",
+
+                    {insert_menu(on_insert_above.clone())}
+
+                    input {
+                        placeholder: "Expected answer",
+                        value: "{expected}",
+                        oninput: move |evt| {
+                            on_update.call(Block::TypeAnswer {
+                                expected: evt.value(),
+                                case_sensitive,
+                                accept: accept_for_expected.clone(),
+                            });
+                        }
+                    }
+
+                    textarea {
+                        placeholder: "Also accepted, one answer per line",
+                        value: "{accept_lines}",
+                        oninput: move |evt| {
+                            on_update.call(Block::TypeAnswer {
+                                expected: expected_for_accept.clone(),
+                                case_sensitive,
+                                // empty lines are kept while typing and ignored when checking
+                                accept: evt.value().split('\n').map(str::to_string).collect(),
+                            });
+                        }
+                    }
+
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: case_sensitive,
+                            onchange: move |evt| {
+                                on_update.call(Block::TypeAnswer {
+                                    expected: expected_for_case.clone(),
+                                    case_sensitive: evt.checked(),
+                                    accept: accept_for_case.clone(),
+                                });
+                            }
+                        }
+                        " Case sensitive"
+                    }
+
+                    {insert_menu(on_insert_below.clone())}
+
+                    button {
+                        onclick: move |_| on_remove.call(()),
+                        "🗑 Remove"
+                    }
+                }
+            )
+        }
+
+        Block::Occlusion { src, masks } => rsx!(
+            div { class: "block-editor occlusion-editor",
+
+                {insert_menu(on_insert_above.clone())}
+
+                OcclusionEditor { src, masks, on_update }
+
+                {insert_menu(on_insert_below.clone())}
+
+                button {
+                    onclick: move |_| on_remove.call(()),
+                    "🗑 Remove Occlusion"
+                }
+            }
+        ),
+
+        Block::Table { headers, rows } => rsx!(
+            div { class: "block-editor table-editor",
+
+                {insert_menu(on_insert_above.clone())}
+
+                TableEditor { headers, rows, on_update }
+
                 {insert_menu(on_insert_below.clone())}
 
                 button {
@@ -67,7 +299,7 @@ pub fn BlockEditor(
             }
         ),
 
-        Block::Image { src } => rsx!(
+        Block::Image { .. } => rsx!(
             div { class: "block-editor image-editor",
 
                 {insert_menu(on_insert_above.clone())}
@@ -76,21 +308,61 @@ pub fn BlockEditor(
 
                 {insert_menu(on_insert_below.clone())}
 
+                // The file stays until the trash is purged, so the edit can be undone
                 button {
-                    onclick: move |_| {
-                        let src = src.clone();
-                        let on_remove = on_remove.clone();
-                        spawn(async move {
-                            delete_block_from_app_data(src).await;
-                            on_remove.call(());
-                        });
-                    },
+                    onclick: move |_| on_remove.call(()),
                     "🗑 Remove Image"
                 }
             }
         ),
 
-        Block::File { path } => rsx!(
+        Block::Audio { src, autoplay } => rsx!(
+            div { class: "block-editor audio-editor",
+
+                {insert_menu(on_insert_above.clone())}
+
+                p { "Audio stored" }
+
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: autoplay,
+                        onchange: move |evt| {
+                            on_update.call(Block::Audio {
+                                src: src.clone(),
+                                autoplay: evt.checked(),
+                            });
+                        }
+                    }
+                    " Play automatically when shown"
+                }
+
+                {insert_menu(on_insert_below.clone())}
+
+                button {
+                    onclick: move |_| on_remove.call(()),
+                    "🗑 Remove Audio"
+                }
+            }
+        ),
+
+        Block::Video { .. } => rsx!(
+            div { class: "block-editor video-editor",
+
+                {insert_menu(on_insert_above.clone())}
+
+                p { "Video stored" }
+
+                {insert_menu(on_insert_below.clone())}
+
+                button {
+                    onclick: move |_| on_remove.call(()),
+                    "🗑 Remove Video"
+                }
+            }
+        ),
+
+        Block::File { .. } => rsx!(
             div { class: "block-editor file-editor",
 
                 {insert_menu(on_insert_above.clone())}
@@ -100,17 +372,495 @@ pub fn BlockEditor(
                 {insert_menu(on_insert_below.clone())}
 
                 button {
-                    onclick: move |_| {
-                        let path = path.clone();
-                        let on_remove = on_remove.clone();
-                        spawn(async move {
-                            delete_block_from_app_data(path).await;
-                            on_remove.call(());
-                        });
-                    },
+                    onclick: move |_| on_remove.call(()),
                     "🗑 Remove File"
                 }
             }
         ),
     }
-}
\ No newline at end of file
+}
+
+
+/// The multiple-choice block with one of its parts changed.
+fn edit_choice(
+    block: &Block,
+    change: impl FnOnce(&mut String, &mut Vec<String>, &mut Vec<usize>, &mut bool),
+) -> Block {
+    let mut block = block.clone();
+    if let Block::MultipleChoice { question, options, correct, shuffle } = &mut block {
+        change(question, options, correct, shuffle);
+    }
+    block
+}
+
+/// Question, options with a checkbox marking the correct ones, and shuffling.
+#[component]
+fn MultipleChoiceEditor(block: Block, on_update: EventHandler<Block>) -> Element {
+    let Block::MultipleChoice { question, options, correct, shuffle } = &block else {
+        return rsx!();
+    };
+
+    let status = match correct.len() {
+        0 => "Mark at least one option as correct".to_string(),
+        1 => "One correct option".to_string(),
+        n => format!("{n} correct options, all have to be picked"),
+    };
+
+    let rows = options.iter().enumerate().map(|(index, option)| {
+        let for_correct = block.clone();
+        let for_text = block.clone();
+        let for_remove = block.clone();
+        let placeholder = format!("Option {}", index + 1);
+
+        rsx!(div { class: "choice-option-editor",
+            input {
+                r#type: "checkbox",
+                title: "Correct",
+                checked: correct.contains(&index),
+                onchange: move |evt| {
+                    on_update.call(edit_choice(&for_correct, |_, _, correct, _| {
+                        correct.retain(|&i| i != index);
+                        if evt.checked() {
+                            correct.push(index);
+                            correct.sort_unstable();
+                        }
+                    }));
+                }
+            }
+            input {
+                value: "{option}",
+                placeholder: "{placeholder}",
+                oninput: move |evt| {
+                    on_update.call(edit_choice(&for_text, |_, options, _, _| {
+                        options[index] = evt.value();
+                    }));
+                }
+            }
+            button {
+                onclick: move |_| {
+                    on_update.call(edit_choice(&for_remove, |_, options, correct, _| {
+                        options.remove(index);
+                        // later options move up by one
+                        *correct = correct
+                            .iter()
+                            .filter(|&&i| i != index)
+                            .map(|&i| if i > index { i - 1 } else { i })
+                            .collect();
+                    }));
+                },
+                "✕"
+            }
+        })
+    });
+
+    let for_question = block.clone();
+    let for_add = block.clone();
+    let for_shuffle = block.clone();
+
+    rsx!(
+        textarea {
+            placeholder: "Question",
+            value: "{question}",
+            oninput: move |evt| {
+                on_update.call(edit_choice(&for_question, |question, _, _, _| {
+                    *question = evt.value();
+                }));
+            }
+        }
+
+        {rows}
+
+        button {
+            class: "button button-secondary",
+            onclick: move |_| {
+                on_update.call(edit_choice(&for_add, |_, options, _, _| options.push(String::new())));
+            },
+            "+ Option"
+        }
+
+        label {
+            input {
+                r#type: "checkbox",
+                checked: *shuffle,
+                onchange: move |evt| {
+                    on_update.call(edit_choice(&for_shuffle, |_, _, _, shuffle| *shuffle = evt.checked()));
+                }
+            }
+            " Shuffle the options"
+        }
+
+        p { class: "choice-status", "{status}" }
+    )
+}
+
+
+/// Masks smaller than this (in pixels) are taken for a click and dropped.
+const MIN_MASK_PIXELS: f64 = 5.0;
+
+/// The image with its masks; masks are drawn by dragging over the image.
+/// Every new mask gets its own number, i.e. its own review item.
+#[component]
+fn OcclusionEditor(src: String, masks: Vec<Rect>, on_update: EventHandler<Block>) -> Element {
+    let mut surface = use_signal(|| None::<Rc<MountedData>>);
+    // Where the mouse went down and where it is now, in pixels of the image
+    let mut drag = use_signal(|| None::<(f64, f64, f64, f64)>);
+
+    let src_for_update = src.clone();
+    let set_masks = use_callback(move |masks: Vec<Rect>| {
+        on_update.call(Block::Occlusion { src: src_for_update.clone(), masks });
+    });
+
+    let url = appimg_url_from_virtual_path(&src);
+    let preview = drag.read().map(|(x0, y0, x1, y1)| {
+        format!(
+            "left: {}px; top: {}px; width: {}px; height: {}px;",
+            x0.min(x1),
+            y0.min(y1),
+            (x1 - x0).abs(),
+            (y1 - y0).abs(),
+        )
+    });
+
+    let masks_for_drawing = masks.clone();
+    let finish_drag = move |_| {
+        let current = *drag.read();
+        drag.set(None);
+        let Some((x0, y0, x1, y1)) = current else {
+            return;
+        };
+        if (x1 - x0).abs() < MIN_MASK_PIXELS || (y1 - y0).abs() < MIN_MASK_PIXELS {
+            return;
+        }
+
+        let mut masks = masks_for_drawing.clone();
+        spawn(async move {
+            let Some(surface) = surface.read().clone() else {
+                return;
+            };
+            let Ok(size) = surface.get_client_rect().await else {
+                return;
+            };
+            let (width, height) = (size.width(), size.height());
+            if width <= 0.0 || height <= 0.0 {
+                return;
+            }
+
+            let ordinal = masks.iter().map(|mask| mask.ordinal).max().unwrap_or(0) + 1;
+            masks.push(Rect {
+                x: (x0.min(x1) / width).clamp(0.0, 1.0),
+                y: (y0.min(y1) / height).clamp(0.0, 1.0),
+                width: ((x1 - x0).abs() / width).min(1.0),
+                height: ((y1 - y0).abs() / height).min(1.0),
+                ordinal,
+            });
+            set_masks.call(masks);
+        });
+    };
+
+    let rows = masks.iter().enumerate().map(|(index, mask)| {
+        let for_ordinal = masks.clone();
+        let for_remove = masks.clone();
+        let ordinal = mask.ordinal;
+        let label = format!("Mask {}, review item ", index + 1);
+
+        rsx!(div { class: "occlusion-mask-editor",
+            label {
+                "{label}"
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: "{ordinal}",
+                    onchange: move |evt| {
+                        if let Ok(ordinal) = evt.value().parse::<u32>() {
+                            let mut masks = for_ordinal.clone();
+                            masks[index].ordinal = ordinal.max(1);
+                            set_masks.call(masks);
+                        }
+                    }
+                }
+            }
+            button {
+                onclick: move |_| {
+                    let mut masks = for_remove.clone();
+                    masks.remove(index);
+                    set_masks.call(masks);
+                },
+                "✕"
+            }
+        })
+    });
+
+    let (for_together, for_separate) = (masks.clone(), masks.clone());
+
+    rsx!(
+        div { class: "block-occlusion occlusion-drawing",
+            img { class: "block-image", src: "{url}", draggable: false }
+
+            for (style, ordinal) in masks.iter().map(|mask| (mask_style(mask), mask.ordinal)) {
+                div { class: "occlusion-mask editing", style: "{style}", "{ordinal}" }
+            }
+
+            if let Some(style) = preview {
+                div { class: "occlusion-mask drawing", style: "{style}" }
+            }
+
+            // on top of image and masks, so coordinates are always relative to it
+            div {
+                class: "occlusion-surface",
+                onmounted: move |evt| surface.set(Some(evt.data())),
+                onmousedown: move |evt| {
+                    let point = evt.element_coordinates();
+                    drag.set(Some((point.x, point.y, point.x, point.y)));
+                },
+                onmousemove: move |evt| {
+                    let point = evt.element_coordinates();
+                    if let Some((x0, y0, _, _)) = *drag.read() {
+                        drag.set(Some((x0, y0, point.x, point.y)));
+                    }
+                },
+                onmouseup: finish_drag.clone(),
+                onmouseleave: finish_drag,
+            }
+        }
+
+        p { class: "occlusion-hint", "Drag over the image to hide a part of it" }
+
+        {rows}
+
+        if masks.len() > 1 {
+            div { class: "occlusion-grouping",
+                button {
+                    class: "button button-secondary",
+                    onclick: move |_| {
+                        let mut masks = for_together.clone();
+                        masks.iter_mut().for_each(|mask| mask.ordinal = 1);
+                        set_masks.call(masks);
+                    },
+                    "One review item for all masks"
+                }
+                button {
+                    class: "button button-secondary",
+                    onclick: move |_| {
+                        let mut masks = for_separate.clone();
+                        for (index, mask) in masks.iter_mut().enumerate() {
+                            mask.ordinal = index as u32 + 1;
+                        }
+                        set_masks.call(masks);
+                    },
+                    "One review item per mask"
+                }
+            }
+        }
+    )
+}
+
+
+/// A grid of inputs for the cells, with a preview. Text copied from a
+/// spreadsheet or a CSV file can be pasted to replace the whole table.
+#[component]
+fn TableEditor(headers: Vec<String>, rows: Vec<Vec<String>>, on_update: EventHandler<Block>) -> Element {
+    let mut csv = use_signal(String::new);
+    let mut first_row_headers = use_signal(|| true);
+
+    let columns = column_count(&headers, &rows).max(1);
+    // every row as wide as the table, so cells can be set by index
+    let headers = padded_row(&headers, columns);
+    let rows: Vec<Vec<String>> = rows.iter().map(|row| padded_row(row, columns)).collect();
+
+    let set_table = move |headers: Vec<String>, rows: Vec<Vec<String>>| {
+        on_update.call(Block::Table { headers, rows });
+    };
+
+    let header_cells = headers.iter().enumerate().map(|(column, cell)| {
+        let (headers, rows) = (headers.clone(), rows.clone());
+        rsx!(th {
+            input {
+                class: "table-cell-input",
+                placeholder: "Header",
+                value: "{cell}",
+                oninput: move |evt| {
+                    let mut headers = headers.clone();
+                    headers[column] = evt.value();
+                    set_table(headers, rows.clone());
+                }
+            }
+        })
+    });
+
+    let remove_column_buttons = (0..columns).map(|column| {
+        let (headers, rows) = (headers.clone(), rows.clone());
+        rsx!(td {
+            button {
+                title: "Remove column",
+                disabled: columns == 1,
+                onclick: move |_| {
+                    let mut headers = headers.clone();
+                    let mut rows = rows.clone();
+                    headers.remove(column);
+                    rows.iter_mut().for_each(|row| { row.remove(column); });
+                    set_table(headers, rows);
+                },
+                "✕"
+            }
+        })
+    });
+
+    let body_rows = rows.iter().enumerate().map(|(index, row)| {
+        let cells = row.iter().enumerate().map(|(column, cell)| {
+            let (headers, rows) = (headers.clone(), rows.clone());
+            rsx!(td {
+                input {
+                    class: "table-cell-input",
+                    value: "{cell}",
+                    oninput: move |evt| {
+                        let mut rows = rows.clone();
+                        rows[index][column] = evt.value();
+                        set_table(headers.clone(), rows);
+                    }
+                }
+            })
+        });
+
+        let (headers, rows) = (headers.clone(), rows.clone());
+        rsx!(tr {
+            {cells}
+            td {
+                button {
+                    title: "Remove row",
+                    onclick: move |_| {
+                        let mut rows = rows.clone();
+                        rows.remove(index);
+                        set_table(headers.clone(), rows);
+                    },
+                    "✕"
+                }
+            }
+        })
+    });
+
+    let (headers_for_row, rows_for_row) = (headers.clone(), rows.clone());
+    let (headers_for_column, rows_for_column) = (headers.clone(), rows.clone());
+
+    rsx!(
+        table { class: "table-grid",
+            thead {
+                tr { {header_cells} }
+            }
+            tbody {
+                {body_rows}
+                tr { class: "table-grid-controls", {remove_column_buttons} }
+            }
+        }
+
+        div { class: "table-grid-actions",
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    let mut rows = rows_for_row.clone();
+                    rows.push(vec![String::new(); columns]);
+                    set_table(headers_for_row.clone(), rows);
+                },
+                "+ Row"
+            }
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    let mut headers = headers_for_column.clone();
+                    let mut rows = rows_for_column.clone();
+                    headers.push(String::new());
+                    rows.iter_mut().for_each(|row| row.push(String::new()));
+                    set_table(headers, rows);
+                },
+                "+ Column"
+            }
+        }
+
+        details { class: "table-csv",
+            summary { "Paste CSV or spreadsheet cells" }
+
+            textarea {
+                placeholder: "Algorithm,Time\nQuicksort,$O(n \\log n)$",
+                value: "{csv}",
+                oninput: move |evt| csv.set(evt.value()),
+            }
+
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: *first_row_headers.read(),
+                    onchange: move |evt| first_row_headers.set(evt.checked()),
+                }
+                " First row is the header"
+            }
+
+            button {
+                class: "button button-secondary",
+                disabled: csv.read().trim().is_empty(),
+                onclick: move |_| {
+                    let mut rows = parse_csv(&csv.read());
+                    let headers = if *first_row_headers.read() && !rows.is_empty() {
+                        rows.remove(0)
+                    } else {
+                        Vec::new()
+                    };
+                    let columns = column_count(&headers, &rows);
+                    set_table(
+                        padded_row(&headers, columns),
+                        rows.iter().map(|row| padded_row(row, columns)).collect(),
+                    );
+                    csv.set(String::new());
+                },
+                "Replace table"
+            }
+        }
+
+        div { class: "table-preview",
+            TableBlock { headers: headers.clone(), rows: rows.clone() }
+        }
+    )
+}
+
+
+/// How long to wait after the last keystroke before checking the TeX.
+const VALIDATE_DELAY_MS: i32 = 400;
+
+/// Problems the backend finds in the TeX of a math block, with the line and
+/// column of each.
+#[component]
+fn TexErrors(source: String) -> Element {
+    let mut errors = use_signal(Vec::new);
+    // Bumped on every change; only the check for the latest source is shown
+    let mut generation = use_signal(|| 0u64);
+
+    use_effect(use_reactive!(|(source,)| {
+        let current = *generation.peek() + 1;
+        generation.set(current);
+
+        spawn(async move {
+            sleep(VALIDATE_DELAY_MS).await;
+            if *generation.peek() != current {
+                return;
+            }
+            let found = validate_math(&source).await;
+            if *generation.peek() == current {
+                errors.set(found);
+            }
+        });
+    }));
+
+    if errors.read().is_empty() {
+        return rsx!();
+    }
+
+    let lines: Vec<String> = errors
+        .read()
+        .iter()
+        .map(|error| format!("Line {}, column {}: {}", error.line, error.column, error.message))
+        .collect();
+
+    rsx!(ul { class: "tex-errors",
+        for line in lines {
+            li { "{line}" }
+        }
+    })
+}
@@ -1,14 +1,23 @@
 use dioxus::prelude::*;
 use urlencoding::encode;
 use shared::models::*;
+use shared::cloze::{ ClozePart, parse_cloze };
+use shared::markdown::{ escape_html, render_markdown };
+use shared::table::to_html;
+use shared::diff::DiffLine;
+use shared::quiz::{ AnswerCheck, AnswerGrade, check_answer, is_correct_choice };
 use crate::tauri_api::{ download_file };
 
 
+/// TeX typeset by MathJax. The source is escaped, so HTML in it shows up as
+/// text instead of ending up in the page.
 #[component]
 pub fn MathBlock(value: String) -> Element {
+    let escaped = escape_html(&value);
+
     rsx!(div {
         class: "block-math",
-        dangerous_inner_html: "{value}",
+        dangerous_inner_html: "{escaped}",
 
         onmounted: move |_| {
             let js = r#"setTimeout(() => {window.renderMath && window.renderMath();}, 50);"#;
@@ -17,7 +26,275 @@ pub fn MathBlock(value: String) -> Element {
     })
 }
 
-fn appimg_url_from_virtual_path(virtual_path: &str) -> String {
+/// Markdown rendered to HTML; math in it is typeset again whenever the text
+/// changes (e.g. in the editor preview).
+#[component]
+pub fn MarkdownBlock(value: String) -> Element {
+    let html = render_markdown(&value);
+
+    use_effect(use_reactive!(|(value,)| {
+        if value.contains('$') {
+            let js = r#"setTimeout(() => {window.renderMath && window.renderMath();}, 50);"#;
+            let _ = dioxus::document::eval(js);
+        }
+    }));
+
+    rsx!(div {
+        class: "block-markdown",
+        dangerous_inner_html: "{html}",
+    })
+}
+
+/// A table; cells are rendered like Markdown, so math in them is typeset.
+#[component]
+pub fn TableBlock(headers: Vec<String>, rows: Vec<Vec<String>>) -> Element {
+    let html = to_html(&headers, &rows);
+    let typeset = html.clone();
+
+    use_effect(use_reactive!(|(typeset,)| {
+        if typeset.contains("\\(") {
+            let js = r#"setTimeout(() => {window.renderMath && window.renderMath();}, 50);"#;
+            let _ = dioxus::document::eval(js);
+        }
+    }));
+
+    rsx!(div {
+        class: "block-table",
+        dangerous_inner_html: "{html}",
+    })
+}
+
+/// Cloze text with the deletions of `active` (all of them if `None`) hidden
+/// until `revealed`. Deletions that are not asked are shown as plain text.
+#[component]
+pub fn ClozeBlock(value: String, active: Option<u32>, revealed: bool) -> Element {
+    rsx!(p { class: "block-cloze",
+        for part in parse_cloze(&value) {
+            match part {
+                ClozePart::Text(text) => rsx!(span { "{text}" }),
+                ClozePart::Deletion { ordinal, answer, hint } => {
+                    let asked = active.is_none_or(|a| a == ordinal);
+                    if !asked {
+                        rsx!(span { "{answer}" })
+                    } else if revealed {
+                        rsx!(span { class: "cloze-answer", "{answer}" })
+                    } else {
+                        let hint = hint.unwrap_or_else(|| "...".into());
+                        rsx!(span { class: "cloze-hidden", "[{hint}]" })
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Option indices in the order they are shown, shuffled if asked to.
+fn choice_order(count: usize, shuffle: bool) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..count).collect();
+    if shuffle {
+        // Fisher-Yates
+        for i in (1..count).rev() {
+            let j = (js_sys::Math::random() * (i + 1) as f64) as usize;
+            order.swap(i, j.min(i));
+        }
+    }
+    order
+}
+
+/// A multiple-choice question. While studying (`on_answered` given) the
+/// learner picks options and checks them, which reports whether exactly the
+/// correct ones were picked; otherwise the correct options are marked.
+#[component]
+pub fn MultipleChoiceBlock(
+    question: String,
+    options: Vec<String>,
+    correct: Vec<usize>,
+    shuffle: bool,
+    on_answered: Option<EventHandler<bool>>,
+) -> Element {
+    let order = use_hook(|| choice_order(options.len(), shuffle));
+    let mut chosen = use_signal(Vec::<usize>::new);
+    let mut checked = use_signal(|| false);
+
+    let answering = on_answered.is_some() && !*checked.read();
+    let several = correct.len() > 1;
+    let hint = if several { "Pick all that apply" } else { "Pick one" };
+
+    let correct_for_check = correct.clone();
+
+    rsx!(div { class: "block-choice",
+        p { class: "choice-question", "{question}" }
+
+        if answering {
+            p { class: "choice-hint", "{hint}" }
+        }
+
+        ul { class: "choice-options",
+            for index in order.iter().copied().filter(|&i| i < options.len()) {
+                {
+                    let label = options[index].clone();
+                    let is_chosen = chosen.read().contains(&index);
+                    let is_correct = correct.contains(&index);
+                    let state = if answering {
+                        if is_chosen { "chosen" } else { "" }
+                    } else if is_correct {
+                        "correct"
+                    } else if is_chosen {
+                        "wrong"
+                    } else {
+                        ""
+                    };
+
+                    rsx!(li {
+                        class: "choice-option {state}",
+                        onclick: move |_| {
+                            if !answering {
+                                return;
+                            }
+                            let mut chosen = chosen.write();
+                            if let Some(position) = chosen.iter().position(|&i| i == index) {
+                                chosen.remove(position);
+                            } else if several {
+                                chosen.push(index);
+                            } else {
+                                *chosen = vec![index];
+                            }
+                        },
+                        "{label}"
+                    })
+                }
+            }
+        }
+
+        if answering {
+            button {
+                class: "button button-primary",
+                disabled: chosen.read().is_empty(),
+                onclick: move |_| {
+                    checked.set(true);
+                    if let Some(handler) = on_answered {
+                        handler.call(is_correct_choice(&correct_for_check, &chosen.read()));
+                    }
+                },
+                "Check"
+            }
+        }
+    })
+}
+
+/// An answer typed while studying (`on_answered` given). Checking it shows
+/// how it differs from the closest accepted answer and reports the grade to
+/// propose; otherwise the expected answer is shown.
+#[component]
+pub fn TypeAnswerBlock(
+    expected: String,
+    case_sensitive: bool,
+    accept: Vec<String>,
+    on_answered: Option<EventHandler<bool>>,
+) -> Element {
+    let mut typed = use_signal(String::new);
+    let mut check = use_signal(|| None::<AnswerCheck>);
+
+    let expected_for_check = expected.clone();
+    let accept_for_check = accept.clone();
+    let submit = use_callback(move |()| {
+        if check.read().is_some() {
+            return;
+        }
+        let result = check_answer(&typed.read(), &expected_for_check, &accept_for_check, case_sensitive);
+        if let Some(handler) = on_answered {
+            handler.call(result.proposed());
+        }
+        check.set(Some(result));
+    });
+
+    if on_answered.is_none() {
+        let also = accept
+            .iter()
+            .filter(|answer| !answer.trim().is_empty())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+        return rsx!(div { class: "block-type-answer",
+            p { class: "type-answer-expected", "{expected}" }
+            if !also.is_empty() {
+                p { class: "type-answer-also", "Also accepted: {also}" }
+            }
+        });
+    }
+
+    match check.read().clone() {
+        None => rsx!(div { class: "block-type-answer",
+            input {
+                class: "type-answer-input",
+                placeholder: "Type the answer",
+                autofocus: true,
+                value: "{typed}",
+                oninput: move |evt| typed.set(evt.value()),
+                onkeydown: move |evt| {
+                    if evt.key() == Key::Enter {
+                        submit.call(());
+                    }
+                },
+            }
+            button {
+                class: "button button-primary",
+                onclick: move |_| submit.call(()),
+                "Check"
+            }
+        }),
+        Some(result) => {
+            let verdict = match result.grade {
+                AnswerGrade::Correct => "Correct",
+                AnswerGrade::Close => "Almost",
+                AnswerGrade::Wrong => "Wrong",
+            };
+            let closest = result.closest.clone();
+
+            rsx!(div { class: "block-type-answer",
+                p { class: "type-answer-diff",
+                    for part in result.diff {
+                        match part {
+                            DiffLine::Same(text) => rsx!(span { "{text}" }),
+                            DiffLine::Removed(text) => rsx!(del { class: "type-answer-wrong", "{text}" }),
+                            DiffLine::Added(text) => rsx!(ins { class: "type-answer-missing", "{text}" }),
+                        }
+                    }
+                }
+                p { class: "type-answer-verdict", "{verdict}: {closest}" }
+            })
+        }
+    }
+}
+
+/// Position of a mask over its image, in percent so it scales with the image.
+pub fn mask_style(mask: &Rect) -> String {
+    format!(
+        "left: {:.3}%; top: {:.3}%; width: {:.3}%; height: {:.3}%;",
+        mask.x * 100.0,
+        mask.y * 100.0,
+        mask.width * 100.0,
+        mask.height * 100.0,
+    )
+}
+
+/// An image with the masks of `active` (all of them if `None`) covering it
+/// until `revealed`, then only outlined. Masks that are not asked are left out.
+#[component]
+pub fn OcclusionBlock(src: String, masks: Vec<Rect>, active: Option<u32>, revealed: bool) -> Element {
+    let url = appimg_url_from_virtual_path(&src);
+    let class = if revealed { "occlusion-mask revealed" } else { "occlusion-mask" };
+
+    rsx!(div { class: "block-occlusion",
+        img { class: "block-image", src: "{url}" }
+
+        for mask in masks.iter().filter(|mask| active.is_none_or(|a| a == mask.ordinal)) {
+            div { class: class, style: mask_style(mask) }
+        }
+    })
+}
+
+pub fn appimg_url_from_virtual_path(virtual_path: &str) -> String {
     let encoded = virtual_path
         .split('/')
         .map(|seg| urlencoding::encode(seg))
@@ -38,6 +315,41 @@ pub fn render_block(block: &Block) -> Element {
 
         Block::Math { value } => { rsx!(MathBlock { value: value.clone() }) }
 
+        Block::Cloze { value } => { rsx!(ClozeBlock { value: value.clone(), revealed: true }) }
+
+        Block::Markdown { value } => { rsx!(MarkdownBlock { value: value.clone() }) }
+
+        // highlighted by the backend on save, see `render.rs` there
+        Block::Code { source, html, .. } if html.is_empty() => {
+            rsx!(pre { class: "block-code", code { "{source}" } })
+        }
+        Block::Code { html, .. } => { rsx!(div { class: "block-code", dangerous_inner_html: "{html}" }) }
+
+        Block::MultipleChoice { question, options, correct, shuffle } => {
+            rsx!(MultipleChoiceBlock {
+                question: question.clone(),
+                options: options.clone(),
+                correct: correct.clone(),
+                shuffle: *shuffle,
+            })
+        }
+
+        Block::TypeAnswer { expected, case_sensitive, accept } => {
+            rsx!(TypeAnswerBlock {
+                expected: expected.clone(),
+                case_sensitive: *case_sensitive,
+                accept: accept.clone(),
+            })
+        }
+
+        Block::Occlusion { src, masks } => {
+            rsx!(OcclusionBlock { src: src.clone(), masks: masks.clone(), revealed: true })
+        }
+
+        Block::Table { headers, rows } => {
+            rsx!(TableBlock { headers: headers.clone(), rows: rows.clone() })
+        }
+
         Block::Image { src } => {
             let url = appimg_url_from_virtual_path(&src);
             rsx!(img {
@@ -46,6 +358,27 @@ pub fn render_block(block: &Block) -> Element {
             })
         }
 
+        Block::Audio { src, autoplay } => {
+            let url = appimg_url_from_virtual_path(src);
+            rsx!(audio {
+                class: "block-audio",
+                src: "{url}",
+                controls: true,
+                autoplay: *autoplay,
+            })
+        }
+
+        // streamed in parts by the appimg protocol, so seeking works
+        Block::Video { src } => {
+            let url = appimg_url_from_virtual_path(src);
+            rsx!(video {
+                class: "block-video",
+                src: "{url}",
+                controls: true,
+                preload: "metadata",
+            })
+        }
+
         Block::File { path } => {
             let path0: String = path.clone();
 
@@ -64,3 +397,15 @@ pub fn render_block(block: &Block) -> Element {
         }
     }
 }
+
+/// A block while studying: cloze deletions and occlusion masks of the asked
+/// number are hidden until the answer is shown, all other blocks render as usual.
+pub fn render_study_block(block: &Block, cloze: Option<u32>, revealed: bool) -> Element {
+    match block {
+        Block::Cloze { value } => rsx!(ClozeBlock { value: value.clone(), active: cloze, revealed }),
+        Block::Occlusion { src, masks } => {
+            rsx!(OcclusionBlock { src: src.clone(), masks: masks.clone(), active: cloze, revealed })
+        }
+        _ => render_block(block),
+    }
+}
@@ -1,20 +1,76 @@
 use dioxus::prelude::*;
-use shared::models::{ Card, Block, Deck, InsertBlockKind };
-use crate::components::BlockEditor;
+use shared::models::{ Card, Block, Deck, InsertBlockKind, EditorBlock, CardBlocks, Draft };
+use shared::tags::normalize_tags;
+use shared::tex::BlockTexError;
+use crate::components::{ BlockEditor, CardHistory };
+use crate::components::card_history::format_time;
 use crate::app::Route;
+use crate::timer::sleep;
 use crate::tauri_api::{
     get_card,
+    get_card_blocks,
     add_card,
     update_card_metadata,
     save_card_blocks,
+    save_card,
+    save_draft,
+    get_draft,
+    get_card_draft,
+    delete_draft,
     pick_image,
     pick_archive,
+    pick_audio,
+    pick_video,
 };
 
+/// `Front block 2, line 1, column 5: ...`
+fn describe_tex_error(error: &BlockTexError) -> String {
+    let side = if error.side == "front" { "Front" } else { "Back" };
+    format!(
+        "{side} block {}, line {}, column {}: {}",
+        error.index + 1,
+        error.error.line,
+        error.error.column,
+        error.error.message
+    )
+}
+
+/// A question with two empty options, the first one correct.
+fn new_multiple_choice() -> Block {
+    Block::MultipleChoice {
+        question: String::new(),
+        options: vec![String::new(), String::new()],
+        correct: vec![0],
+        shuffle: true,
+    }
+}
+
+fn new_type_answer() -> Block {
+    Block::TypeAnswer { expected: String::new(), case_sensitive: false, accept: Vec::new() }
+}
+
+/// Two columns with one empty row.
+fn new_table() -> Block {
+    Block::Table {
+        headers: vec![String::new(), String::new()],
+        rows: vec![vec![String::new(), String::new()]],
+    }
+}
+
 async fn create_block(kind: InsertBlockKind) -> Option<Block> {
     match kind {
         InsertBlockKind::Text => Some(Block::Text { value: "".into() }),
         InsertBlockKind::Math => Some(Block::Math { value: "".into() }),
+        InsertBlockKind::Cloze => Some(Block::Cloze { value: "".into() }),
+        InsertBlockKind::Markdown => Some(Block::Markdown { value: "".into() }),
+        InsertBlockKind::Code => Some(Block::Code { language: "".into(), source: "".into(), html: "".into() }),
+        InsertBlockKind::MultipleChoice => Some(new_multiple_choice()),
+        InsertBlockKind::TypeAnswer => Some(new_type_answer()),
+        InsertBlockKind::Table => Some(new_table()),
+        InsertBlockKind::Occlusion => {
+            let src = pick_image().await;
+            (!src.is_empty()).then(|| Block::Occlusion { src, masks: Vec::new() })
+        }
         InsertBlockKind::Image => {
             let path = pick_image().await;
             (!path.is_empty()).then(|| Block::Image { src: path })
@@ -23,25 +79,19 @@ async fn create_block(kind: InsertBlockKind) -> Option<Block> {
             let path = pick_archive().await;
             (!path.is_empty()).then(|| Block::File { path })
         }
+        InsertBlockKind::Audio => {
+            let src = pick_audio().await;
+            (!src.is_empty()).then(|| Block::Audio { src, autoplay: false })
+        }
+        InsertBlockKind::Video => {
+            let src = pick_video().await;
+            (!src.is_empty()).then(|| Block::Video { src })
+        }
     }
 }
 
-
-fn normalize_tags(raw: &str) -> Option<String> {
-    let tags: Vec<String> = raw
-        .split(',')
-        .map(|t| t.trim().to_lowercase())
-        .filter(|t| !t.is_empty())
-        .collect();
-
-    if tags.is_empty() {
-        None
-    } else {
-        Some(tags.join(","))
-    }
-}
-
-
+/// How long the editor waits after the last change before autosaving a draft.
+const AUTOSAVE_DELAY_MS: i32 = 2000;
 
 #[derive(Clone, PartialEq, Copy)]
 pub enum EditorMode {
@@ -71,12 +121,37 @@ pub fn CardEditorEdit(id: i64) -> Element {
     }
 }
 
+/// Opens the editor on a draft left over from an earlier session.
+#[component]
+pub fn CardEditorDraft(id: i64) -> Element {
+    let draft = use_resource(move || get_draft(id));
+
+    let Some(draft) = draft.read().clone() else {
+        return rsx! {
+            div { class: "loading",
+                "Loading draft..."
+            }
+        };
+    };
+
+    let mode = match draft.card_id {
+        Some(card_id) => EditorMode::Edit { card_id },
+        None => EditorMode::New { deck_id: draft.deck_id },
+    };
+
+    rsx! {
+        CardEditor { mode, draft }
+    }
+}
+
 #[component]
-pub fn CardEditor(mode: EditorMode) -> Element {
+pub fn CardEditor(mode: EditorMode, draft: Option<Draft>) -> Element {
     let nav = navigator();
 
     // State for the card being edited
-    let mut card = use_signal(|| None::<Card>);
+    let mut card = use_signal(|| None::<(Card, CardBlocks)>);
+    // Unsaved changes the editor was opened with
+    let mut restored = use_signal(|| draft.clone());
 
     //
     // MODE-DEPENDENT INITIALIZATION
@@ -85,14 +160,18 @@ pub fn CardEditor(mode: EditorMode) -> Element {
     match mode {
         EditorMode::New { deck_id } => {
             // Create a fresh card right away
-            card.set(Some(Card::new_empty(deck_id)));
+            card.set(Some((Card::new_empty(deck_id), CardBlocks::default())));
         }
         EditorMode::Edit { card_id } => {
-            // Load from DB once on mount
+            // Load from DB once on mount, together with a draft of the card if one was left behind
             use_effect(move || {
                 spawn(async move {
                     let loaded = get_card(card_id).await;
-                    card.set(Some(loaded));
+                    let blocks = get_card_blocks(card_id).await;
+                    if restored.peek().is_none() {
+                        restored.set(get_card_draft(card_id).await);
+                    }
+                    card.set(Some((loaded, blocks)));
                 });
             });
         }
@@ -112,11 +191,87 @@ pub fn CardEditor(mode: EditorMode) -> Element {
     //
     // Destructure card – now it’s guaranteed to be Some
     //
-    let c = card.read().as_ref().unwrap().clone();
-    let mut card_tags = use_signal(|| c.tags.clone().unwrap_or_default());
-    let mut card_name = use_signal(|| c.name.clone());
-    let mut front_blocks = use_signal(|| c.front_blocks.clone());
-    let mut back_blocks = use_signal(|| c.back_blocks.clone());
+    let (c, blocks) = card.read().as_ref().unwrap().clone();
+    let card_id = match mode {
+        EditorMode::New { .. } => None,
+        EditorMode::Edit { card_id } => Some(card_id),
+    };
+
+    // The card as it is stored, and what the editor starts with: the stored
+    // card or the restored draft
+    let mut saved = use_signal(|| (c.name.clone(), c.tags.clone().unwrap_or_default(), blocks.clone()));
+    let start = restored.peek().clone().unwrap_or_else(|| Draft {
+        id: 0,
+        card_id,
+        deck_id: c.deck_id,
+        base_version: card_id.map(|_| c.version),
+        name: c.name.clone(),
+        tags: c.tags.clone().unwrap_or_default(),
+        blocks: blocks.clone(),
+        updated_at: 0,
+    });
+
+    let mut card_tags = use_signal(|| start.tags.clone());
+    let mut card_name = use_signal(|| start.name.clone());
+    let mut front_blocks = use_signal(|| start.blocks.front.clone());
+    let mut back_blocks = use_signal(|| start.blocks.back.clone());
+    let mut show_history = use_signal(|| false);
+    let mut draft_id = use_signal(|| start.id);
+    // Version of the card the edits are based on, to notice changes made elsewhere
+    let mut base_version = use_signal(|| start.base_version);
+    // The card as changed elsewhere, when saving was refused because of it
+    let mut conflict = use_signal(|| None::<Card>);
+    // The saved card and the problems found in its math blocks
+    let mut math_warnings = use_signal(|| None::<(i64, Vec<BlockTexError>)>);
+    // Bumped on every change; a pending autosave only runs if nothing changed while it waited
+    let mut generation = use_signal(|| 0u64);
+
+    //
+    // AUTOSAVE
+    //
+    let deck_id = c.deck_id;
+    use_effect(move || {
+        let name = card_name.read().clone();
+        let tags = card_tags.read().clone();
+        let blocks = CardBlocks {
+            front: front_blocks.read().clone(),
+            back: back_blocks.read().clone(),
+        };
+
+        let current = *generation.peek() + 1;
+        generation.set(current);
+
+        spawn(async move {
+            sleep(AUTOSAVE_DELAY_MS).await;
+            if *generation.peek() != current {
+                return;
+            }
+
+            let id = *draft_id.peek();
+            if *saved.peek() == (name.clone(), tags.clone(), blocks.clone()) {
+                // Back to the stored card, a draft is no longer needed
+                if id != 0 {
+                    delete_draft(id).await;
+                    draft_id.set(0);
+                }
+                return;
+            }
+
+            let draft = Draft {
+                id,
+                card_id,
+                deck_id,
+                base_version: *base_version.peek(),
+                name,
+                tags,
+                blocks,
+                updated_at: 0,
+            };
+            draft_id.set(save_draft(&draft).await);
+        });
+    });
+
+    let restored_at = restored.read().as_ref().map(|d| format_time(d.updated_at));
 
     //
     // RENDER
@@ -131,6 +286,45 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                 }
             }
 
+            if let Some(time) = restored_at {
+                div { class: "draft-notice",
+                    span { "Restored unsaved changes from {time}." }
+
+                    button {
+                        class: "button button-secondary",
+                        onclick: move |_| {
+                            let id = *draft_id.peek();
+                            let (name, tags, blocks) = saved.peek().clone();
+                            draft_id.set(0);
+                            restored.set(None);
+                            base_version.set(card_id.map(|_| c.version));
+                            card_name.set(name);
+                            card_tags.set(tags);
+                            front_blocks.set(blocks.front);
+                            back_blocks.set(blocks.back);
+                            if id != 0 {
+                                spawn(async move {
+                                    delete_draft(id).await;
+                                });
+                            }
+                        },
+                        "Discard draft"
+                    }
+                }
+            }
+
+            if let Some(note_id) = c.note_id {
+                div { class: "draft-notice",
+                    span { "This card is generated from a note; saving the note replaces changes made here." }
+
+                    button {
+                        class: "button button-secondary",
+                        onclick: move |_| { nav.push(Route::NoteEditorEdit { id: note_id }); },
+                        "Edit note"
+                    }
+                }
+            }
+
             // Card name
             div { class: "card-field",
                 label { "Card Name" }
@@ -142,9 +336,9 @@ pub fn CardEditor(mode: EditorMode) -> Element {
 
             // Card tags
             div { class: "card-field",
-                label { "Tags (comma separated)" }
+                label { "Tags (comma separated, nest with ::)" }
                 input {
-                    placeholder: "e.g. ml, knn, classification",
+                    placeholder: "e.g. ml::knn, classification",
                     value: "{card_tags}",
                     oninput: move |evt| card_tags.set(evt.value())
                 }
@@ -156,14 +350,14 @@ pub fn CardEditor(mode: EditorMode) -> Element {
             //
             h2 { "Front Blocks" }
 
-            for (i, block) in front_blocks.read().iter().cloned().enumerate() {
+            for (i, block) in front_blocks.read().iter().map(|b| b.block.clone()).enumerate() {
                 BlockEditor {
                     block,
 
                     on_update: {
                         let mut front_blocks = front_blocks.clone();
                         move |new_block| {
-                            front_blocks.write()[i] = new_block;
+                            front_blocks.write()[i].block = new_block;
                         }
                     },
 
@@ -180,7 +374,7 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                             let mut front_blocks = front_blocks.clone();
                             spawn(async move {
                                 if let Some(block) = create_block(kind).await {
-                                    front_blocks.write().insert(i, block);
+                                    front_blocks.write().insert(i, EditorBlock::new(block));
                                 }
                             });
                         }
@@ -192,7 +386,7 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                             let mut front_blocks = front_blocks.clone();
                             spawn(async move {
                                 if let Some(block) = create_block(kind).await {
-                                    front_blocks.write().insert(i + 1, block);
+                                    front_blocks.write().insert(i + 1, EditorBlock::new(block));
                                 }
                             });
                         }
@@ -208,7 +402,7 @@ pub fn CardEditor(mode: EditorMode) -> Element {
             button {
                 class: "button button-secondary",
                 onclick: move |_| {
-                    front_blocks.write().push(Block::Text { value: "".into() });
+                    front_blocks.write().push(EditorBlock::new(Block::Text { value: "".into() }));
                 },
                 "+ Add Text Block"
             }
@@ -216,11 +410,77 @@ pub fn CardEditor(mode: EditorMode) -> Element {
             button {
                 class: "button button-secondary",
                 onclick: move |_| {
-                    front_blocks.write().push(Block::Math { value: "".into() });
+                    front_blocks.write().push(EditorBlock::new(Block::Math { value: "".into() }));
                 },
                 "+ Add Math Block"
             }
 
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    front_blocks.write().push(EditorBlock::new(Block::Cloze { value: "".into() }));
+                },
+                "+ Add Cloze Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    front_blocks.write().push(EditorBlock::new(Block::Markdown { value: "".into() }));
+                },
+                "+ Add Markdown Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    front_blocks.write().push(EditorBlock::new(Block::Code {
+                        language: "".into(),
+                        source: "".into(),
+                        html: "".into(),
+                    }));
+                },
+                "+ Add Code Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    front_blocks.write().push(EditorBlock::new(new_table()));
+                },
+                "+ Add Table Block"
+            }
+
+            // answered on the front, so there are no buttons for the back
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    front_blocks.write().push(EditorBlock::new(new_multiple_choice()));
+                },
+                "+ Add Multiple Choice Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    front_blocks.write().push(EditorBlock::new(new_type_answer()));
+                },
+                "+ Add Typed Answer Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    spawn(async move {
+                        let src = pick_image().await;
+                        if !src.is_empty() {
+                            front_blocks.write().push(EditorBlock::new(Block::Occlusion { src, masks: Vec::new() }));
+                        }
+                    });
+                },
+                "+ Add Occlusion Block"
+            }
+
             button {
                 class: "button button-secondary",
                 onclick: move |_| {
@@ -229,7 +489,7 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                         let path = pick_image().await;
                         if path != "" {
                             // Insert a new Block::Image into the editor
-                            front_blocks.write().push(Block::Image { src: path });
+                            front_blocks.write().push(EditorBlock::new(Block::Image { src: path }));
                         }
                     });
                 },
@@ -243,7 +503,7 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                         // Call the plugin
                         let path = pick_archive().await;
                         if path != "" {
-                            front_blocks.write().push(Block::File { path: path });
+                            front_blocks.write().push(EditorBlock::new(Block::File { path: path }));
                         }
                     });
 
@@ -251,6 +511,32 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                 "+ Add File Block"
             }
 
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    spawn(async move {
+                        let src = pick_audio().await;
+                        if !src.is_empty() {
+                            front_blocks.write().push(EditorBlock::new(Block::Audio { src, autoplay: false }));
+                        }
+                    });
+                },
+                "+ Add Audio Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    spawn(async move {
+                        let src = pick_video().await;
+                        if !src.is_empty() {
+                            front_blocks.write().push(EditorBlock::new(Block::Video { src }));
+                        }
+                    });
+                },
+                "+ Add Video Block"
+            }
+
         }
 
 
@@ -259,14 +545,14 @@ pub fn CardEditor(mode: EditorMode) -> Element {
             //
             h2 { "Back Blocks" }
 
-            for (i, block) in back_blocks.read().iter().cloned().enumerate() {
+            for (i, block) in back_blocks.read().iter().map(|b| b.block.clone()).enumerate() {
                 BlockEditor {
                     block,
 
                     on_update: {
                         let mut back_blocks = back_blocks.clone();
                         move |new_block| {
-                            back_blocks.write()[i] = new_block;
+                            back_blocks.write()[i].block = new_block;
                         }
                     },
 
@@ -283,7 +569,7 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                             let mut back_blocks = back_blocks.clone();
                             spawn(async move {
                                 if let Some(block) = create_block(kind).await {
-                                    back_blocks.write().insert(i, block);
+                                    back_blocks.write().insert(i, EditorBlock::new(block));
                                 }
                             });
                         }
@@ -295,7 +581,7 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                             let mut back_blocks = back_blocks.clone();
                             spawn(async move {
                                 if let Some(block) = create_block(kind).await {
-                                    back_blocks.write().insert(i + 1, block);
+                                    back_blocks.write().insert(i + 1, EditorBlock::new(block));
                                 }
                             });
                         }
@@ -309,7 +595,7 @@ pub fn CardEditor(mode: EditorMode) -> Element {
             button {
                 class: "button button-secondary",
                 onclick: move |_| {
-                    back_blocks.write().push(Block::Text { value: "".into() });
+                    back_blocks.write().push(EditorBlock::new(Block::Text { value: "".into() }));
                 },
                 "+ Add Text Block"
             }
@@ -317,11 +603,47 @@ pub fn CardEditor(mode: EditorMode) -> Element {
             button {
                 class: "button button-secondary",
                 onclick: move |_| {
-                    back_blocks.write().push(Block::Math { value: "".into() });
+                    back_blocks.write().push(EditorBlock::new(Block::Math { value: "".into() }));
                 },
                 "+ Add Math Block"
             }
 
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    back_blocks.write().push(EditorBlock::new(Block::Cloze { value: "".into() }));
+                },
+                "+ Add Cloze Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    back_blocks.write().push(EditorBlock::new(Block::Markdown { value: "".into() }));
+                },
+                "+ Add Markdown Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    back_blocks.write().push(EditorBlock::new(Block::Code {
+                        language: "".into(),
+                        source: "".into(),
+                        html: "".into(),
+                    }));
+                },
+                "+ Add Code Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    back_blocks.write().push(EditorBlock::new(new_table()));
+                },
+                "+ Add Table Block"
+            }
+
             button {
                 class: "button button-secondary",
                 onclick: move |_| {
@@ -329,7 +651,7 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                         let path = pick_image().await;
                         if path != "" {
                             // Insert a new Block::Image into the editor
-                            back_blocks.write().push(Block::Image { src: path });
+                            back_blocks.write().push(EditorBlock::new(Block::Image { src: path }));
                         }
                     });
                 },
@@ -347,13 +669,39 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                             path
                         )));
                         if path != "" {
-                            back_blocks.write().push(Block::File { path: path });
+                            back_blocks.write().push(EditorBlock::new(Block::File { path: path }));
                         }
                     });
 
                 },
                 "+ Add File Block"
             }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    spawn(async move {
+                        let src = pick_audio().await;
+                        if !src.is_empty() {
+                            back_blocks.write().push(EditorBlock::new(Block::Audio { src, autoplay: false }));
+                        }
+                    });
+                },
+                "+ Add Audio Block"
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    spawn(async move {
+                        let src = pick_video().await;
+                        if !src.is_empty() {
+                            back_blocks.write().push(EditorBlock::new(Block::Video { src }));
+                        }
+                    });
+                },
+                "+ Add Video Block"
+            }
         }
 
             button {
@@ -364,6 +712,10 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                     let tags = normalize_tags(&card_tags.read());
                     let front = front_blocks.read().clone();
                     let back = back_blocks.read().clone();
+                    let base = *base_version.read();
+
+                    // Cancel a pending autosave, the draft is not needed after saving
+                    *generation.write() += 1;
 
                     spawn(async move {
                         match mode {
@@ -375,15 +727,30 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                                 update_card_metadata(id, name, tags).await;
 
                                 // 3️⃣ Save blocks
-                                save_card_blocks(id, &front, &back).await;
+                                let report = save_card_blocks(id, &front, &back).await;
+
+                                let draft = *draft_id.peek();
+                                if draft != 0 {
+                                    delete_draft(draft).await;
+                                }
+
+                                if !report.math_errors.is_empty() {
+                                    math_warnings.set(Some((id, report.math_errors)));
+                                    return;
+                                }
                             }
 
                             EditorMode::Edit { card_id } => {
-                                // 1️⃣ Update metadata
-                                update_card_metadata(card_id, name, tags).await;
-
-                                // 2️⃣ Save blocks
-                                save_card_blocks(card_id, &front, &back).await;
+                                // One step, so the edit can be undone as a whole
+                                let report = save_card(card_id, name, tags, &front, &back, base).await;
+                                if let Some(current) = report.conflict {
+                                    conflict.set(Some(current));
+                                    return;
+                                }
+                                if !report.math_errors.is_empty() {
+                                    math_warnings.set(Some((card_id, report.math_errors)));
+                                    return;
+                                }
                             }
                         }
 
@@ -394,6 +761,113 @@ pub fn CardEditor(mode: EditorMode) -> Element {
                 "Save Card"
             }
 
+            if let Some((saved_id, errors)) = math_warnings.read().clone() {
+                div { class: "save-conflict",
+
+                    h2 { "Saved, but some math will not render" }
+
+                    ul { class: "tex-errors",
+                        for line in errors.iter().map(describe_tex_error) {
+                            li { "{line}" }
+                        }
+                    }
+
+                    div { class: "delete-actions",
+
+                        button {
+                            class: "button",
+                            onclick: move |_| { nav.push(Route::CardView { id: saved_id }); },
+                            "Open card"
+                        }
+
+                        button {
+                            class: "button button-secondary",
+                            onclick: move |_| { nav.push(Route::CardListPage { id: c.deck_id }); },
+                            "Back to deck"
+                        }
+                    }
+                }
+            }
+
+            if let (Some(current), EditorMode::Edit { card_id }) = (conflict.read().clone(), mode) {
+                div { class: "save-conflict",
+
+                    h2 { "This card was changed elsewhere" }
+                    p { "\"{current.name}\" was changed since you started editing. Save your version anyway?" }
+
+                    div { class: "delete-actions",
+
+                        button {
+                            class: "button button-danger",
+                            onclick: move |_| {
+                                let name = card_name.read().clone();
+                                let tags = normalize_tags(&card_tags.read());
+                                let front = front_blocks.read().clone();
+                                let back = back_blocks.read().clone();
+
+                                spawn(async move {
+                                    let report = save_card(card_id, name, tags, &front, &back, None).await;
+                                    conflict.set(None);
+                                    if !report.math_errors.is_empty() {
+                                        math_warnings.set(Some((card_id, report.math_errors)));
+                                        return;
+                                    }
+                                    nav.push(Route::CardListPage { id: c.deck_id });
+                                });
+                            },
+                            "Overwrite"
+                        }
+
+                        button {
+                            class: "button",
+                            onclick: move |_| {
+                                let id = *draft_id.peek();
+                                *generation.write() += 1;
+                                spawn(async move {
+                                    if id != 0 {
+                                        delete_draft(id).await;
+                                    }
+                                    nav.push(Route::CardView { id: card_id });
+                                });
+                            },
+                            "Discard my changes"
+                        }
+
+                        button {
+                            class: "button button-secondary",
+                            onclick: move |_| conflict.set(None),
+                            "Cancel"
+                        }
+                    }
+                }
+            }
+
+            if let EditorMode::Edit { card_id } = mode {
+                button {
+                    class: "button button-secondary",
+                    onclick: move |_| show_history.toggle(),
+                    if *show_history.read() { "Hide history" } else { "History" }
+                }
+
+                if *show_history.read() {
+                    CardHistory {
+                        card_id,
+                        on_restore: move |restored: Card| {
+                            base_version.set(Some(restored.version));
+                            spawn(async move {
+                                let blocks = get_card_blocks(card_id).await;
+                                let tags = restored.tags.unwrap_or_default();
+                                saved.set((restored.name.clone(), tags.clone(), blocks.clone()));
+                                card_name.set(restored.name);
+                                card_tags.set(tags);
+                                front_blocks.set(blocks.front);
+                                back_blocks.set(blocks.back);
+                            });
+                        },
+                    }
+                }
+            }
+
         }
     }
 }
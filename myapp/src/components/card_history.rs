@@ -0,0 +1,107 @@
+use dioxus::prelude::*;
+use shared::diff::{ DiffLine, diff_lines };
+use shared::models::{ Card, CardRevision };
+use crate::tauri_api::{ get_card_history, restore_revision };
+
+pub fn format_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Saved versions of a card with a diff between two of them. Restoring a
+/// version hands the restored card to `on_restore`.
+#[component]
+pub fn CardHistory(card_id: i64, on_restore: EventHandler<Card>) -> Element {
+    let mut revisions = use_signal(|| Vec::<CardRevision>::new());
+    // Indexes into `revisions` (newest first): the version looked at and the
+    // one it is compared with
+    let mut selected = use_signal(|| 0usize);
+    let mut base = use_signal(|| 1usize);
+
+    use_future(move || async move {
+        revisions.set(get_card_history(card_id).await);
+    });
+
+    let list = revisions.read().clone();
+
+    let diff = match (list.get(*selected.read()), list.get(*base.read())) {
+        (Some(new), Some(old)) => diff_lines(&old.lines(), &new.lines()),
+        (Some(new), None) => new.lines().into_iter().map(DiffLine::Added).collect(),
+        _ => Vec::new(),
+    };
+
+    let rows: Vec<(usize, CardRevision, String)> = list
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, r)| {
+            let time = format_time(r.created_at);
+            (i, r, time)
+        })
+        .collect();
+
+    rsx! {
+        div { class: "card-history",
+
+            h2 { "History" }
+
+            if rows.is_empty() {
+                p { "No saved versions yet." }
+            }
+
+            for (i, revision, time) in rows {
+                div {
+                    key: "{revision.id}",
+                    class: if i == *selected.read() { "history-row history-selected" } else { "history-row" },
+
+                    button {
+                        class: "history-time",
+                        onclick: move |_| {
+                            selected.set(i);
+                            base.set(i + 1);
+                        },
+                        "{time}"
+                        if i == 0 { " (current)" }
+                    }
+
+                    if i != *selected.read() {
+                        button {
+                            class: "button",
+                            onclick: move |_| base.set(i),
+                            "Compare"
+                        }
+                    }
+
+                    if i > 0 {
+                        button {
+                            class: "button",
+                            onclick: move |_| {
+                                spawn(async move {
+                                    let card = restore_revision(revision.id).await;
+                                    revisions.set(get_card_history(card_id).await);
+                                    selected.set(0);
+                                    base.set(1);
+                                    on_restore.call(card);
+                                });
+                            },
+                            "Restore"
+                        }
+                    }
+                }
+            }
+
+            if !diff.is_empty() {
+                div { class: "history-diff",
+                    for line in diff {
+                        match line {
+                            DiffLine::Same(text) => rsx! { pre { class: "diff-same", "  {text}" } },
+                            DiffLine::Added(text) => rsx! { pre { class: "diff-added", "+ {text}" } },
+                            DiffLine::Removed(text) => rsx! { pre { class: "diff-removed", "- {text}" } },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
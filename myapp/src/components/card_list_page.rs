@@ -1,41 +1,168 @@
 use dioxus::prelude::*;
 // use dioxus_router::prelude::*;
-use shared::models::{ Deck, Block, Card };
+use shared::models::{ Deck, BulkAction, CardPage, CardSort, deck_full_name, deck_tree };
+use shared::search::parse_query;
 use crate::app::Route;
-use crate::tauri_api::{ get_cards };
+use crate::tauri_api::{ list_cards, get_decks, move_cards, copy_cards, bulk_update_cards, suspend_cards, bury_cards };
+
+const SORTS: [(CardSort, &str); 5] = [
+    (CardSort::Created, "Created"),
+    (CardSort::Name, "Name"),
+    (CardSort::Progress, "Progress"),
+    (CardSort::TimesSeen, "Times seen"),
+    (CardSort::Due, "Due date"),
+];
 
 #[component]
 pub fn CardListPage(id: i64) -> Element {
     let nav = navigator();
-    let mut cards = use_signal(|| Vec::<Card>::new());
+    let mut page = use_signal(CardPage::default);
+    let mut selected = use_signal(|| Vec::<i64>::new());
+    let mut search = use_signal(String::new);
+    let mut sort = use_signal(CardSort::default);
+    let mut descending = use_signal(|| true);
+    let mut page_number = use_signal(|| 0u32);
+
+    let search_error = parse_query(&search.read()).err();
+
+    // Reruns whenever the search, sort or page changes
+    let load = move || {
+        let text = search.read().trim().to_string();
+        if parse_query(&text).is_err() {
+            return;
+        }
+        let query = if text.is_empty() { format!("did:{id}") } else { format!("did:{id} ({text})") };
+        let (sort, descending, number) = (*sort.read(), *descending.read(), *page_number.read());
 
-    use_effect(move || {
         spawn(async move {
-            let loaded = get_cards(id).await;
-            cards.set(loaded);
+            page.set(list_cards(query, sort, descending, number).await);
         });
-    });
+    };
+
+    use_effect(move || load());
 
-    let card_views: Vec<(i64, String, u8, Option<String>)> = cards
+    let now = chrono::Utc::now().timestamp();
+    let card_views: Vec<(i64, String, u8, Option<String>, Option<&str>)> = page
         .read()
+        .cards
         .iter()
-        .map(|c| (c.id, c.name.clone(), c.progress_percent(), c.tags.clone(),))
+        .map(|c| {
+            let state = if c.suspended {
+                Some("suspended")
+            } else if !c.is_active(now) {
+                Some("buried")
+            } else {
+                None
+            };
+            (c.id, c.name.clone(), c.progress_percent(), c.tags.clone(), state)
+        })
         .collect();
 
+    let page_ids: Vec<i64> = card_views.iter().map(|(id, ..)| *id).collect();
+    let all_selected = !page_ids.is_empty() && page_ids.iter().all(|id| selected.read().contains(id));
+    let (total, page_count) = (page.read().total, page.read().page_count());
+    let current_page = *page_number.read();
+
     rsx! {
         div { class: "card-list-page",
 
             // TODO:
             // Create a heading here by passing in the deck name and setting it as h1
 
+            div { class: "card-browser-controls",
+                input {
+                    class: "deck-input",
+                    placeholder: "Search, e.g. tag:exam -is:new",
+                    value: "{search}",
+                    oninput: move |evt| {
+                        search.set(evt.value());
+                        page_number.set(0);
+                    },
+                }
+
+                select {
+                    class: "deck-input",
+                    onchange: move |evt| {
+                        let value = evt.value();
+                        if let Some((s, _)) = SORTS.iter().find(|(_, label)| *label == value) {
+                            sort.set(*s);
+                            page_number.set(0);
+                        }
+                    },
+                    for (s, label) in SORTS {
+                        option { value: "{label}", selected: *sort.read() == s, "{label}" }
+                    }
+                }
+
+                button {
+                    class: "button",
+                    onclick: move |_| {
+                        let flipped = !*descending.read();
+                        descending.set(flipped);
+                        page_number.set(0);
+                    },
+                    if *descending.read() { "↓" } else { "↑" }
+                }
+            }
+
+            if let Some(error) = search_error {
+                p { class: "search-error", "{error}" }
+            }
+
+            div { class: "card-browser-summary",
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: all_selected,
+                        onchange: move |evt| {
+                            let mut selected = selected.write();
+                            selected.retain(|id| !page_ids.contains(id));
+                            if evt.checked() {
+                                selected.extend(page_ids.iter().copied());
+                            }
+                        },
+                    }
+                    "Select page"
+                }
+                span { "{total} cards" }
+            }
+
+            if !selected.read().is_empty() {
+                SelectionActions {
+                    deck_id: id,
+                    card_ids: selected.read().clone(),
+                    on_done: move |_| {
+                        selected.set(vec![]);
+                        load();
+                    },
+                }
+            }
+
             div { class: "cards-container",
 
-                for (card_id, card_name, progress, tags) in card_views {
+                for (card_id, card_name, progress, tags, state) in card_views {
                         div { key: "{card_id}", class: "card-preview",
 
+                        input {
+                            class: "card-select",
+                            r#type: "checkbox",
+                            checked: selected.read().contains(&card_id),
+                            onchange: move |evt| {
+                                if evt.checked() {
+                                    selected.write().push(card_id);
+                                } else {
+                                    selected.write().retain(|id| *id != card_id);
+                                }
+                            },
+                        }
+
                         div { class: "card-main",
                             h2 { class: "card-title", "{card_name}" }
-                            
+
+                            if let Some(state) = state {
+                                span { class: "card-state", "{state}" }
+                            }
+
                             if let Some(tags) = tags {
                                 div { class: "card-tags",
                                     for tag in tags.split(',') {
@@ -61,6 +188,24 @@ pub fn CardListPage(id: i64) -> Element {
                 }
             }
 
+            if page_count > 1 {
+                div { class: "card-pagination",
+                    button {
+                        class: "button",
+                        disabled: current_page == 0,
+                        onclick: move |_| page_number -= 1,
+                        "Previous"
+                    }
+                    span { "Page {current_page + 1} of {page_count}" }
+                    button {
+                        class: "button",
+                        disabled: current_page + 1 >= page_count,
+                        onclick: move |_| page_number += 1,
+                        "Next"
+                    }
+                }
+            }
+
             div { class: "cardlist-buttons",
                 button {
                     class: "back-button",
@@ -70,14 +215,219 @@ pub fn CardListPage(id: i64) -> Element {
 
                 button {
                     class: "add-card-button",
-                    // we use 1 for now but later we need to get the current deck id when 
+                    // we use 1 for now but later we need to get the current deck id when
                     // listing the cards
                     onclick: move |_| { nav.push(Route::CardEditorNew { deck_id: id } ); },
                     "Add Card"
                 }
+
+                button {
+                    class: "add-card-button",
+                    onclick: move |_| { nav.push(Route::NoteEditorNew { deck_id: id } ); },
+                    "Add Note"
+                }
+            }
+
+
+        }
+    }
+}
+
+
+/// Bulk actions on the selected cards: move or copy into another deck,
+/// tag, untag, reset progress and delete.
+#[component]
+fn SelectionActions(deck_id: i64, card_ids: Vec<i64>, on_done: EventHandler<()>) -> Element {
+    let mut decks = use_signal(|| Vec::<Deck>::new());
+    let mut target = use_signal(|| None::<i64>);
+    let mut reset_progress = use_signal(|| false);
+    let mut tag = use_signal(String::new);
+    let mut confirm_delete = use_signal(|| false);
+
+    use_future(move || async move {
+        let loaded = get_decks().await;
+        target.set(loaded.iter().map(|d| d.id).find(|id| *id != deck_id));
+        decks.set(loaded);
+    });
+
+    let targets: Vec<(i64, String)> = deck_tree(&decks.read())
+        .into_iter()
+        .filter(|(_, d)| d.id != deck_id)
+        .map(|(_, d)| (d.id, deck_full_name(&decks.read(), d.id)))
+        .collect();
+
+    let count = card_ids.len();
+    let move_ids = card_ids.clone();
+    let copy_ids = card_ids.clone();
+    let suspend_ids = card_ids.clone();
+    let bury_ids = card_ids.clone();
+
+    let suspend = move |suspended: bool| {
+        let ids = suspend_ids.clone();
+        spawn(async move {
+            suspend_cards(ids, suspended).await;
+            on_done.call(());
+        });
+    };
+    // None unburies
+    let bury = move |days: Option<u32>| {
+        let ids = bury_ids.clone();
+        spawn(async move {
+            bury_cards(ids, days).await;
+            on_done.call(());
+        });
+    };
+
+    let apply = move |action: BulkAction| {
+        let ids = card_ids.clone();
+        spawn(async move {
+            bulk_update_cards(ids, action).await;
+            on_done.call(());
+        });
+    };
+    let tag_value = move || tag.read().trim().to_string();
+
+    rsx! {
+        div { class: "selection-actions",
+
+            span { "{count} selected" }
+
+            select {
+                class: "deck-input",
+                onchange: move |evt| target.set(evt.value().parse().ok()),
+                for (id, name) in targets {
+                    option {
+                        value: "{id}",
+                        selected: *target.read() == Some(id),
+                        "{name}"
+                    }
+                }
+            }
+
+            button {
+                class: "button",
+                disabled: target.read().is_none(),
+                onclick: move |_| {
+                    let Some(target) = *target.read() else { return };
+                    let ids = move_ids.clone();
+                    spawn(async move {
+                        move_cards(ids, target).await;
+                        on_done.call(());
+                    });
+                },
+                "Move"
             }
 
+            button {
+                class: "button",
+                disabled: target.read().is_none(),
+                onclick: move |_| {
+                    let Some(target) = *target.read() else { return };
+                    let ids = copy_ids.clone();
+                    let reset = *reset_progress.read();
+                    spawn(async move {
+                        copy_cards(ids, target, reset).await;
+                        on_done.call(());
+                    });
+                },
+                "Copy"
+            }
+
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: *reset_progress.read(),
+                    onchange: move |evt| reset_progress.set(evt.checked()),
+                }
+                "Reset progress of copies"
+            }
+
+            div { class: "selection-bulk",
+                input {
+                    class: "deck-input",
+                    placeholder: "Tag",
+                    value: "{tag}",
+                    oninput: move |evt| tag.set(evt.value()),
+                }
+
+                button {
+                    class: "button",
+                    disabled: tag_value().is_empty(),
+                    onclick: {
+                        let apply = apply.clone();
+                        move |_| apply(BulkAction::AddTag { tag: tag_value() })
+                    },
+                    "Tag"
+                }
+
+                button {
+                    class: "button",
+                    disabled: tag_value().is_empty(),
+                    onclick: {
+                        let apply = apply.clone();
+                        move |_| apply(BulkAction::RemoveTag { tag: tag_value() })
+                    },
+                    "Untag"
+                }
+
+                button {
+                    class: "button",
+                    onclick: {
+                        let apply = apply.clone();
+                        move |_| apply(BulkAction::ResetProgress)
+                    },
+                    "Reset progress"
+                }
+
+                button {
+                    class: "button",
+                    onclick: {
+                        let suspend = suspend.clone();
+                        move |_| suspend(true)
+                    },
+                    "Suspend"
+                }
+
+                button {
+                    class: "button",
+                    onclick: move |_| suspend(false),
+                    "Unsuspend"
+                }
 
+                button {
+                    class: "button",
+                    onclick: {
+                        let bury = bury.clone();
+                        move |_| bury(Some(1))
+                    },
+                    "Bury for a day"
+                }
+
+                button {
+                    class: "button",
+                    onclick: move |_| bury(None),
+                    "Unbury"
+                }
+
+                if *confirm_delete.read() {
+                    button {
+                        class: "button button-danger",
+                        onclick: move |_| apply(BulkAction::Delete),
+                        "Delete {count} cards forever"
+                    }
+                    button {
+                        class: "button button-secondary",
+                        onclick: move |_| confirm_delete.set(false),
+                        "Cancel"
+                    }
+                } else {
+                    button {
+                        class: "button button-danger",
+                        onclick: move |_| confirm_delete.set(true),
+                        "Delete"
+                    }
+                }
+            }
         }
     }
 }
@@ -1,10 +1,10 @@
 use dioxus::prelude::*;
 use shared::models::{ Card };
-use crate::components::block_view::render_block;
+use crate::components::study::CardStudy;
 use crate::components::card_list_page::CardListPage;
 use crate::components::{ CardEditorEdit };
 use crate::app::Route;
-use crate::tauri_api::{ get_card, delete_card, update_score };
+use crate::tauri_api::{ get_card, delete_card, update_score, reset_progress, suspend_cards };
 
 
 
@@ -13,7 +13,7 @@ pub fn DeleteCard(card_id: i64, on_done: EventHandler<()>) -> Element {
     rsx! {
         div { class: "delete-card",
 
-            h1 { "Sure you want to delete the card forever?" }
+            h1 { "Move this card to the trash?" }
 
             div { class: "delete-actions",
 
@@ -42,7 +42,6 @@ pub fn DeleteCard(card_id: i64, on_done: EventHandler<()>) -> Element {
 
 #[component]
 pub fn CardView(id: i64) -> Element {
-    let mut show_answer = use_signal(|| false);
     let mut deleting = use_signal(|| false);
     let nav = navigator();
 
@@ -55,67 +54,29 @@ pub fn CardView(id: i64) -> Element {
         });
     });
 
+    let reload = move || {
+        spawn(async move {
+            card_signal.set(get_card(id).await);
+        });
+    };
+
     let card = card_signal.read();
     let deck_id = card.deck_id;
+    let suspended = card.suspended;
 
     rsx! {
     div { class: "card-view",
 
         h1 { class: "card-title", "{card.name}" }
 
-        // Study area
-        div { class: "card-study",
-
-            div { class: "card-surface",
-                for block in &card.front_blocks {
-                    { render_block(block) }
-                }
-            }
-
-        if !*show_answer.read() {
-            div { class: "show-answer-container",
-                button {
-                    class: "button button-primary",
-                    onclick: move |_| show_answer.set(true),
-                    "Show answer"
-                }
-            }
-        }
-
-
-            if *show_answer.read() {
-                div { class: "answer-surface",
-                    for block in &card.back_blocks {
-                        { render_block(block) }
-                    }
-                }
-
-                // Rating buttons
-                div { class: "card-rating",
-
-                    button {
-                        class: "button button-bad",
-                        onclick: move |_| {
-                            spawn(async move {
-                                let updated = update_score(id, false).await;
-                                 nav.push(Route::CardListPage { id: deck_id });
-                            });
-                        },
-                        "BAD"
-                    }
-
-                    button {
-                        class: "button button-good",
-                        onclick: move |_| {
-                            spawn(async move {
-                                let updated = update_score(id, true).await;
-                                nav.push(Route::CardListPage { id: deck_id });
-                            });
-                        },
-                        "GOOD"
-                    }
-                }
-            }
+        CardStudy {
+            card: card.clone(),
+            on_graded: move |correct| {
+                spawn(async move {
+                    let updated = update_score(id, correct, None).await;
+                    nav.push(Route::CardListPage { id: deck_id });
+                });
+            },
         }
 
         // Actions
@@ -129,6 +90,28 @@ pub fn CardView(id: i64) -> Element {
                 "Edit"
             }
 
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    spawn(async move {
+                        suspend_cards(vec![id], !suspended).await;
+                        reload();
+                    });
+                },
+                if suspended { "Unsuspend" } else { "Suspend" }
+            }
+
+            button {
+                class: "button button-secondary",
+                onclick: move |_| {
+                    spawn(async move {
+                        reset_progress(vec![id]).await;
+                        reload();
+                    });
+                },
+                "Reset progress"
+            }
+
             button {
                 class: "button button-danger",
                 onclick: move |_| deleting.set(true),
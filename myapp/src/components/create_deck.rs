@@ -12,7 +12,7 @@ pub fn CreateDeck(on_done: EventHandler<()>) -> Element {
 
             input {
                 class: "deck-input",
-                placeholder: "e.g. Calculus",
+                placeholder: "e.g. Math::Calculus",
                 value: "{deck_name}",
                 oninput: move |ev| deck_name.set(ev.value()),
             }
@@ -1,8 +1,9 @@
 use dioxus::prelude::*;
 use shared::models::*;
 use crate::app::Route;
-use crate::components::{ CreateDeck };
-use crate::tauri_api::{ init_db, get_decks, export_deck, import_deck, get_cards, delete_card, delete_deck, rename_deck };
+use crate::components::{ CreateDeck, ImportDeck, MergeDeck, SplitDeck, FilteredDeckList };
+use crate::components::card_history::format_time;
+use crate::tauri_api::{ init_db, get_decks, export_deck, export_deck_text, stage_import, get_cards, delete_card, delete_deck, rename_deck, last_undo, undo_last, list_drafts, delete_draft };
 
 
 #[component]
@@ -10,7 +11,7 @@ pub fn DeleteDeck(deck_id: i64, on_done: EventHandler<()>) -> Element {
     rsx! {
         div { class: "delete-card",
 
-            h1 { "Sure you want to delete the deck forever?" }
+            h1 { "Move this deck and its sub-decks to the trash?" }
 
             div { class: "delete-actions",
 
@@ -41,20 +42,38 @@ pub fn DeckList() -> Element {
     let nav = navigator();
     let mut creating = use_signal(|| false);
     let mut deleting: Signal<Option<i64>> = use_signal(|| None);
+    let mut merging: Signal<Option<i64>> = use_signal(|| None);
+    let mut splitting: Signal<Option<i64>> = use_signal(|| None);
     let mut decks = use_signal(|| Vec::<Deck>::new());
     let mut renaming: Signal<Option<i64>> = use_signal(|| None);
     let mut rename_value = use_signal(String::new);
+    let mut importing = use_signal(|| None::<ImportSummary>);
+    let mut undo_label = use_signal(|| None::<String>);
+    let mut undo_message = use_signal(|| None::<String>);
+    let mut drafts = use_signal(|| Vec::<Draft>::new());
 
     use_future(move || async move {
         init_db().await;
         let loaded = get_decks().await;
         decks.set(loaded);
+        undo_label.set(last_undo().await);
+        drafts.set(list_drafts().await);
     });
 
-    let deck_views: Vec<(i64, String)> = decks
+    // Sub-decks are listed below their parent, indented by depth
+    let deck_views: Vec<(i64, usize, String, Deck)> = deck_tree(&decks.read())
+        .into_iter()
+        .map(|(depth, d)| (d.id, depth, d.name.clone(), d.clone()))
+        .collect();
+
+    let draft_views: Vec<(i64, String, String)> = drafts
         .read()
         .iter()
-        .map(|d| (d.id, d.name.clone()))
+        .map(|d| {
+            let name = if d.name.is_empty() { "Untitled card".to_string() } else { d.name.clone() };
+            let kind = if d.card_id.is_some() { "changes to" } else { "new card" };
+            (d.id, format!("{kind} \"{name}\""), format_time(d.updated_at))
+        })
         .collect();
 
     rsx! {
@@ -62,11 +81,46 @@ pub fn DeckList() -> Element {
 
             div { class: "deck-list",
 
+                // Editor state left behind by a previous session
+                if !draft_views.is_empty() {
+                    div { class: "drafts-prompt",
+                        h2 { "Unsaved drafts" }
+
+                        for (id, label, time) in draft_views {
+                            div {
+                                key: "{id}",
+                                class: "draft-row",
+
+                                span { "Unsaved {label} from {time}" }
+
+                                button {
+                                    class: "button button-primary",
+                                    onclick: move |_| { nav.push(Route::CardEditorDraft { id }); },
+                                    "Restore"
+                                }
+
+                                button {
+                                    class: "button button-secondary",
+                                    onclick: move |_| {
+                                        spawn(async move {
+                                            delete_draft(id).await;
+                                            drafts.set(list_drafts().await);
+                                        });
+                                    },
+                                    "Discard"
+                                }
+                            }
+                        }
+                    }
+                }
+
                 h1 { "Select a Deck" }
 
-                for (id, name) in deck_views {
+                for (id, depth, name, counts) in deck_views {
 
-                    div { class: "deck-card",
+                    div {
+                        class: "deck-card",
+                        style: "margin-left: {depth * 24}px;",
 
                         button {
                             class: "deck-main",
@@ -74,10 +128,29 @@ pub fn DeckList() -> Element {
                                 nav.push(Route::CardListPage { id });
                             },
                             "{name}"
+                            span { class: "deck-counts",
+                                span { class: "deck-count-new", "{counts.new_count} new" }
+                                " · "
+                                span { class: "deck-count-due", "{counts.due_count} due" }
+                                " · "
+                                span { class: "deck-count-learned", "{counts.learned_count} learned" }
+                                if counts.inactive_count > 0 {
+                                    " · {counts.inactive_count} suspended/buried"
+                                }
+                                " · {counts.card_count} cards"
+                            }
                         }
 
 
                         div { class: "deck-actions",
+                            button {
+                                class: "button button-primary",
+                                onclick: move |_| {
+                                    nav.push(Route::StudySession { id });
+                                },
+                                "Study"
+                            }
+
                             button {
                                 class: "button",
                                 onclick: move |_| {
@@ -88,6 +161,26 @@ pub fn DeckList() -> Element {
                                 "Export"
                             }
 
+                            button {
+                                class: "button",
+                                onclick: move |_| {
+                                    spawn(async move {
+                                        export_deck_text(id, TextExportFormat::AnkiCsv).await;
+                                    });
+                                },
+                                "Export for Anki"
+                            }
+
+                            button {
+                                class: "button",
+                                onclick: move |_| {
+                                    spawn(async move {
+                                        export_deck_text(id, TextExportFormat::Markdown).await;
+                                    });
+                                },
+                                "Export Markdown"
+                            }
+
                             button {
                                 class: "button button-danger",
                                 onclick: move |_| deleting.set(Some(id)),
@@ -102,6 +195,43 @@ pub fn DeckList() -> Element {
                                 },
                                 "Rename"
                             }
+
+                            button {
+                                class: "button",
+                                onclick: move |_| merging.set(Some(id)),
+                                "Merge"
+                            }
+
+                            button {
+                                class: "button",
+                                onclick: move |_| splitting.set(Some(id)),
+                                "Split"
+                            }
+                        }
+                        if merging.read().as_ref() == Some(&id) {
+                            MergeDeck {
+                                deck_id: id,
+                                decks: decks.read().clone(),
+                                on_done: move |_| {
+                                    merging.set(None);
+                                    spawn(async move {
+                                        let loaded = get_decks().await;
+                                        decks.set(loaded);
+                                    });
+                                }
+                            }
+                        }
+                        if splitting.read().as_ref() == Some(&id) {
+                            SplitDeck {
+                                deck_id: id,
+                                on_done: move |_| {
+                                    splitting.set(None);
+                                    spawn(async move {
+                                        let loaded = get_decks().await;
+                                        decks.set(loaded);
+                                    });
+                                }
+                            }
                         }
                         if deleting.read().as_ref() == Some(&id) {
                             DeleteDeck {
@@ -111,6 +241,7 @@ pub fn DeckList() -> Element {
                                     spawn(async move {
                                         let loaded = get_decks().await;
                                         decks.set(loaded);
+                                        undo_label.set(last_undo().await);
                                     });
                                 }
                             }
@@ -178,16 +309,66 @@ pub fn DeckList() -> Element {
                         class: "button",
                         onclick: move |_| {
                             spawn(async move {
-                                let new_deck_id = import_deck().await;
-                                if new_deck_id > 0 {
-                                    nav.push(Route::CardListPage { id: new_deck_id });
-                                }
-                                let loaded = get_decks().await;
-                                decks.set(loaded);
+                                importing.set(stage_import().await);
                             });
                         },
                         "Import deck"
                     }
+
+                    button {
+                        class: "button",
+                        onclick: move |_| { nav.push(Route::TagManager {}); },
+                        "Tags"
+                    }
+
+                    button {
+                        class: "button",
+                        onclick: move |_| { nav.push(Route::Search {}); },
+                        "Search"
+                    }
+
+                    button {
+                        class: "button",
+                        onclick: move |_| { nav.push(Route::Trash {}); },
+                        "Trash"
+                    }
+
+                    if let Some(label) = undo_label.read().clone() {
+                        button {
+                            class: "button",
+                            onclick: move |_| {
+                                spawn(async move {
+                                    undo_message.set(undo_last().await);
+                                    decks.set(get_decks().await);
+                                    undo_label.set(last_undo().await);
+                                });
+                            },
+                            "Undo: {label}"
+                        }
+                    }
+                }
+
+                if let Some(message) = undo_message.read().clone() {
+                    p { class: "undo-message", "{message}" }
+                }
+
+                FilteredDeckList { decks: decks.read().clone() }
+
+                if let Some(summary) = importing.read().clone() {
+                    ImportDeck {
+                        summary,
+                        decks: decks.read().clone(),
+                        on_done: move |new_deck_id: Option<i64>| {
+                            importing.set(None);
+                            if let Some(id) = new_deck_id {
+                                nav.push(Route::CardListPage { id });
+                            }
+                            spawn(async move {
+                                let loaded = get_decks().await;
+                                decks.set(loaded);
+                            });
+                        }
+                    }
                 }
 
                 if *creating.read() {
@@ -0,0 +1,100 @@
+use dioxus::prelude::*;
+use shared::models::{ Deck, deck_full_name, deck_tree };
+use crate::tauri_api::{ merge_decks, split_deck };
+
+
+/// Merges `deck_id` (and its sub-decks) into another deck.
+#[component]
+pub fn MergeDeck(deck_id: i64, decks: Vec<Deck>, on_done: EventHandler<()>) -> Element {
+    let targets: Vec<(i64, String)> = deck_tree(&decks)
+        .into_iter()
+        .filter(|(_, d)| d.id != deck_id)
+        .map(|(_, d)| (d.id, deck_full_name(&decks, d.id)))
+        .collect();
+
+    let mut target = use_signal(|| targets.first().map(|(id, _)| *id));
+    let name = deck_full_name(&decks, deck_id);
+
+    rsx! {
+        div { class: "delete-card",
+
+            h1 { "Merge \"{name}\" into" }
+
+            select {
+                class: "deck-input",
+                onchange: move |evt| target.set(evt.value().parse().ok()),
+                for (id, name) in targets {
+                    option {
+                        value: "{id}",
+                        selected: *target.read() == Some(id),
+                        "{name}"
+                    }
+                }
+            }
+
+            div { class: "delete-actions",
+
+                button {
+                    class: "button button-primary",
+                    disabled: target.read().is_none(),
+                    onclick: move |_| {
+                        let Some(target) = *target.read() else { return };
+                        spawn(async move {
+                            merge_decks(vec![deck_id], target).await;
+                            on_done.call(());
+                        });
+                    },
+                    "Merge"
+                }
+
+                button {
+                    class: "button button-secondary",
+                    onclick: move |_| on_done.call(()),
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
+
+
+/// Splits a deck into one sub-deck per tag (or just the one entered).
+#[component]
+pub fn SplitDeck(deck_id: i64, on_done: EventHandler<()>) -> Element {
+    let mut tag = use_signal(String::new);
+
+    rsx! {
+        div { class: "delete-card",
+
+            h1 { "Split deck by tag" }
+
+            input {
+                class: "deck-input",
+                placeholder: "Tag (empty: one deck per tag)",
+                value: "{tag}",
+                oninput: move |evt| tag.set(evt.value()),
+            }
+
+            div { class: "delete-actions",
+
+                button {
+                    class: "button button-primary",
+                    onclick: move |_| {
+                        let by_tag = Some(tag.read().trim().to_string()).filter(|t| !t.is_empty());
+                        spawn(async move {
+                            split_deck(deck_id, by_tag).await;
+                            on_done.call(());
+                        });
+                    },
+                    "Split"
+                }
+
+                button {
+                    class: "button button-secondary",
+                    onclick: move |_| on_done.call(()),
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
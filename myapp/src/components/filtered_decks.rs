@@ -0,0 +1,310 @@
+use dioxus::prelude::*;
+use shared::models::{ Deck, DueFilter, FilterQuery, FilteredDeck, deck_full_name, deck_tree };
+use shared::search::parse_query;
+use shared::tags::parse_tags;
+use crate::app::Route;
+use crate::tauri_api::{ list_filtered_decks, save_filtered_deck, delete_filtered_deck, count_filter_matches };
+
+
+/// Saved queries listed below the regular decks.
+#[component]
+pub fn FilteredDeckList(decks: Vec<Deck>) -> Element {
+    let nav = navigator();
+    let mut filtered = use_signal(|| Vec::<FilteredDeck>::new());
+    // Some(None) = creating a new filtered deck
+    let mut editing = use_signal(|| None::<Option<FilteredDeck>>);
+    let mut deleting = use_signal(|| None::<i64>);
+
+    use_future(move || async move {
+        filtered.set(list_filtered_decks().await);
+    });
+
+    let reload = move || {
+        spawn(async move {
+            filtered.set(list_filtered_decks().await);
+        });
+    };
+
+    rsx! {
+        h2 { "Filtered decks" }
+
+        for deck in filtered.read().clone() {
+            div { key: "{deck.id}", class: "deck-card",
+
+                button {
+                    class: "deck-main",
+                    onclick: move |_| { nav.push(Route::FilteredStudySession { id: deck.id }); },
+                    "{deck.name}"
+                    span { class: "deck-counts", "{deck.card_count} cards" }
+                }
+
+                div { class: "deck-actions",
+                    button {
+                        class: "button",
+                        onclick: move |_| { nav.push(Route::FilteredStudySession { id: deck.id }); },
+                        "Study"
+                    }
+                    button {
+                        class: "button",
+                        onclick: {
+                            let deck = deck.clone();
+                            move |_| editing.set(Some(Some(deck.clone())))
+                        },
+                        "Edit"
+                    }
+                    button {
+                        class: "button button-danger",
+                        onclick: move |_| deleting.set(Some(deck.id)),
+                        "Delete"
+                    }
+                }
+            }
+        }
+
+        div { class: "deck-global-actions",
+            button {
+                class: "button",
+                onclick: move |_| editing.set(Some(None)),
+                "Add filtered deck"
+            }
+        }
+
+        if let Some(id) = *deleting.read() {
+            div { class: "delete-card",
+                h1 { "Delete this filtered deck? Its cards stay in their decks." }
+                div { class: "delete-actions",
+                    button {
+                        class: "button button-danger",
+                        onclick: move |_| {
+                            deleting.set(None);
+                            spawn(async move {
+                                delete_filtered_deck(id).await;
+                                reload();
+                            });
+                        },
+                        "YES"
+                    }
+                    button {
+                        class: "button button-secondary",
+                        onclick: move |_| deleting.set(None),
+                        "NO"
+                    }
+                }
+            }
+        }
+
+        if let Some(deck) = editing.read().clone() {
+            FilteredDeckEditor {
+                deck,
+                decks: decks.clone(),
+                on_done: move |_| {
+                    editing.set(None);
+                    reload();
+                },
+            }
+        }
+    }
+}
+
+
+fn parse_optional<T: std::str::FromStr>(value: &str) -> Option<T> {
+    value.trim().parse().ok()
+}
+
+fn optional_text<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+
+/// Dialog to create (`deck` = None) or edit a filtered deck.
+#[component]
+pub fn FilteredDeckEditor(deck: Option<FilteredDeck>, decks: Vec<Deck>, on_done: EventHandler<()>) -> Element {
+    let id = deck.as_ref().map(|d| d.id);
+    let initial = deck.as_ref().map(|d| d.query.clone()).unwrap_or_default();
+
+    let mut name = use_signal(|| deck.as_ref().map(|d| d.name.clone()).unwrap_or_default());
+    let mut tags = use_signal(|| initial.tags.join(", "));
+    let mut deck_ids = use_signal(|| initial.deck_ids.clone());
+    let mut min_progress = use_signal(|| optional_text(initial.min_progress));
+    let mut max_progress = use_signal(|| optional_text(initial.max_progress));
+    let mut seen_within = use_signal(|| optional_text(initial.seen_within_days));
+    let mut failed_within = use_signal(|| optional_text(initial.failed_within_days));
+    let mut due = use_signal(|| initial.due);
+    let mut search = use_signal(|| initial.search.clone());
+
+    let query = move || FilterQuery {
+        tags: parse_tags(&tags.read()),
+        deck_ids: deck_ids.read().clone(),
+        min_progress: parse_optional(&min_progress.read()),
+        max_progress: parse_optional(&max_progress.read()),
+        seen_within_days: parse_optional(&seen_within.read()),
+        failed_within_days: parse_optional(&failed_within.read()),
+        due: *due.read(),
+        search: search.read().clone(),
+    };
+
+    let search_error = parse_query(&search.read()).err();
+
+    // The backend rejects invalid queries, so only count valid ones
+    let matches = use_resource(move || {
+        let query = query();
+        async move {
+            match parse_query(&query.search) {
+                Ok(_) => Some(count_filter_matches(query).await),
+                Err(_) => None,
+            }
+        }
+    });
+
+    let deck_options: Vec<(i64, String)> = deck_tree(&decks)
+        .into_iter()
+        .map(|(_, d)| (d.id, deck_full_name(&decks, d.id)))
+        .collect();
+
+    let due_options = [
+        (DueFilter::Any, "Any"),
+        (DueFilter::Due, "Due"),
+        (DueFilter::New, "New"),
+        (DueFilter::Learned, "Learned"),
+    ];
+
+    rsx! {
+        div { class: "delete-card filtered-deck-editor",
+
+            h1 { if id.is_some() { "Edit filtered deck" } else { "New filtered deck" } }
+
+            input {
+                class: "deck-input",
+                placeholder: "Name",
+                value: "{name}",
+                oninput: move |evt| name.set(evt.value()),
+            }
+
+            label { "Tags (comma separated, any of them)" }
+            input {
+                class: "deck-input",
+                placeholder: "exam1, math::algebra",
+                value: "{tags}",
+                oninput: move |evt| tags.set(evt.value()),
+            }
+
+            label { "Decks (none selected: all decks)" }
+            div { class: "filter-decks",
+                for (deck_id, deck_name) in deck_options {
+                    label { key: "{deck_id}",
+                        input {
+                            r#type: "checkbox",
+                            checked: deck_ids.read().contains(&deck_id),
+                            onchange: move |evt| {
+                                if evt.checked() {
+                                    deck_ids.write().push(deck_id);
+                                } else {
+                                    deck_ids.write().retain(|id| *id != deck_id);
+                                }
+                            },
+                        }
+                        "{deck_name}"
+                    }
+                }
+            }
+
+            div { class: "filter-row",
+                label { "Progress from" }
+                input {
+                    class: "deck-input",
+                    r#type: "number",
+                    placeholder: "0",
+                    value: "{min_progress}",
+                    oninput: move |evt| min_progress.set(evt.value()),
+                }
+                label { "to" }
+                input {
+                    class: "deck-input",
+                    r#type: "number",
+                    placeholder: "100",
+                    value: "{max_progress}",
+                    oninput: move |evt| max_progress.set(evt.value()),
+                }
+            }
+
+            div { class: "filter-row",
+                label { "Seen in the last" }
+                input {
+                    class: "deck-input",
+                    r#type: "number",
+                    value: "{seen_within}",
+                    oninput: move |evt| seen_within.set(evt.value()),
+                }
+                label { "days" }
+            }
+
+            div { class: "filter-row",
+                label { "Failed in the last" }
+                input {
+                    class: "deck-input",
+                    r#type: "number",
+                    value: "{failed_within}",
+                    oninput: move |evt| failed_within.set(evt.value()),
+                }
+                label { "days" }
+            }
+
+            div { class: "filter-row",
+                label { "Due state" }
+                select {
+                    class: "deck-input",
+                    onchange: move |evt| {
+                        let value = evt.value();
+                        if let Some((filter, _)) = due_options.iter().find(|(_, label)| *label == value) {
+                            due.set(*filter);
+                        }
+                    },
+                    for (filter, label) in due_options {
+                        option {
+                            value: "{label}",
+                            selected: *due.read() == filter,
+                            "{label}"
+                        }
+                    }
+                }
+            }
+
+            label { "Search (e.g. type:math -tag:done)" }
+            input {
+                class: "deck-input",
+                value: "{search}",
+                oninput: move |evt| search.set(evt.value()),
+            }
+            if let Some(error) = &search_error {
+                p { class: "search-error", "{error}" }
+            }
+
+            match (*matches.read()).flatten() {
+                Some(count) => rsx! { p { class: "study-remaining", "{count} cards match" } },
+                None => rsx! {},
+            }
+
+            div { class: "delete-actions",
+
+                button {
+                    class: "button button-primary",
+                    disabled: name.read().trim().is_empty() || search_error.is_some(),
+                    onclick: move |_| {
+                        let (name, query) = (name.read().clone(), query());
+                        spawn(async move {
+                            save_filtered_deck(id, name, query).await;
+                            on_done.call(());
+                        });
+                    },
+                    "Save"
+                }
+
+                button {
+                    class: "button button-secondary",
+                    onclick: move |_| on_done.call(()),
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use dioxus::prelude::*;
+use shared::models::{ Deck, ImportMode, ImportPreview, ImportSummary, RemovedCards };
+use crate::tauri_api::{ preview_import, import_deck };
+
+#[derive(Clone, Copy, PartialEq)]
+enum ModeChoice {
+    NewDeck,
+    Merge,
+    Replace,
+}
+
+#[component]
+pub fn ImportDeck(
+    summary: ImportSummary,
+    decks: Vec<Deck>,
+    on_done: EventHandler<Option<i64>>,
+) -> Element {
+    let mut choice = use_signal(|| ModeChoice::NewDeck);
+    let mut target = use_signal(|| decks.first().map(|d| d.id));
+    let mut removed = use_signal(|| RemovedCards::Keep);
+    let mut preview = use_signal(|| None::<ImportPreview>);
+
+    let mode = move || -> Option<ImportMode> {
+        match *choice.read() {
+            ModeChoice::NewDeck => Some(ImportMode::NewDeck),
+            ModeChoice::Merge => target.read().map(|deck_id| ImportMode::Merge {
+                deck_id,
+                removed: *removed.read(),
+            }),
+            ModeChoice::Replace => target.read().map(|deck_id| ImportMode::Replace { deck_id }),
+        }
+    };
+
+    rsx! {
+        div { class: "import-deck",
+
+            h1 { "Import \"{summary.deck_name}\"" }
+            p { "{summary.card_count} cards in the archive" }
+
+            div { class: "import-modes",
+                for (text, value) in [
+                    ("New deck", ModeChoice::NewDeck),
+                    ("Merge into existing deck", ModeChoice::Merge),
+                    ("Replace existing deck", ModeChoice::Replace),
+                ] {
+                    label {
+                        input {
+                            r#type: "radio",
+                            name: "import-mode",
+                            checked: *choice.read() == value,
+                            onchange: move |_| {
+                                choice.set(value);
+                                preview.set(None);
+                            },
+                        }
+                        "{text}"
+                    }
+                }
+            }
+
+            if *choice.read() != ModeChoice::NewDeck {
+                select {
+                    class: "deck-input",
+                    onchange: move |evt| {
+                        target.set(evt.value().parse().ok());
+                        preview.set(None);
+                    },
+                    for deck in decks.iter() {
+                        option {
+                            value: "{deck.id}",
+                            selected: *target.read() == Some(deck.id),
+                            "{deck.name}"
+                        }
+                    }
+                }
+            }
+
+            if *choice.read() == ModeChoice::Merge {
+                select {
+                    class: "deck-input",
+                    onchange: move |evt| {
+                        removed.set(match evt.value().as_str() {
+                            "archive" => RemovedCards::Archive,
+                            "delete" => RemovedCards::Delete,
+                            _ => RemovedCards::Keep,
+                        });
+                        preview.set(None);
+                    },
+                    option { value: "keep", "Keep cards missing from the archive" }
+                    option { value: "archive", "Move them to an archive deck" }
+                    option { value: "delete", "Move them to the trash" }
+                }
+            }
+
+            if let Some(p) = preview.read().as_ref() {
+                div { class: "import-preview",
+                    p { "{p.added.len()} new, {p.updated.len()} changed, {p.removed.len()} removed, {p.unchanged} unchanged" }
+
+                    for (heading, names) in [("New", &p.added), ("Changed", &p.updated), ("Removed", &p.removed)] {
+                        if !names.is_empty() {
+                            h3 { "{heading}" }
+                            ul {
+                                for name in names.iter() {
+                                    li { "{name}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { class: "delete-actions",
+
+                if preview.read().is_none() {
+                    button {
+                        class: "button button-primary",
+                        disabled: mode().is_none(),
+                        onclick: move |_| {
+                            let Some(mode) = mode() else { return };
+                            spawn(async move {
+                                preview.set(Some(preview_import(mode).await));
+                            });
+                        },
+                        "Preview"
+                    }
+                } else {
+                    button {
+                        class: "button button-primary",
+                        onclick: move |_| {
+                            let Some(mode) = mode() else { return };
+                            spawn(async move {
+                                let deck_id = import_deck(mode).await;
+                                on_done.call(Some(deck_id));
+                            });
+                        },
+                        "Import"
+                    }
+                }
+
+                button {
+                    class: "button button-secondary",
+                    onclick: move |_| on_done.call(None),
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
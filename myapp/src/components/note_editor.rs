@@ -0,0 +1,184 @@
+use dioxus::prelude::*;
+use shared::models::{ Block, Note, NoteType };
+use shared::notes::{ card_name, generate_card };
+use crate::app::Route;
+use crate::components::render_block;
+use crate::tauri_api::{ add_note, get_note, list_note_types, update_note };
+
+
+/// Creates a note, and with it its cards, in a deck.
+#[component]
+pub fn NoteEditorNew(deck_id: i64) -> Element {
+    let note_types = use_resource(list_note_types);
+
+    match note_types.read().clone() {
+        Some(note_types) => rsx! { NoteEditor { deck_id, note_types } },
+        None => rsx! {
+            div { class: "loading",
+                "Loading note types..."
+            }
+        },
+    }
+}
+
+
+/// Edits the fields of a note; saving updates all cards generated from it.
+#[component]
+pub fn NoteEditorEdit(id: i64) -> Element {
+    let loaded = use_resource(move || async move { (get_note(id).await, list_note_types().await) });
+
+    match loaded.read().clone() {
+        Some((note, note_types)) => rsx! {
+            NoteEditor { deck_id: note.deck_id, note_types, note }
+        },
+        None => rsx! {
+            div { class: "loading",
+                "Loading note..."
+            }
+        },
+    }
+}
+
+
+/// Fields of the note and a preview of the cards they make.
+#[component]
+fn NoteEditor(deck_id: i64, note_types: Vec<NoteType>, note: Option<Note>) -> Element {
+    let nav = navigator();
+
+    let note_id = note.as_ref().map(|n| n.id);
+    let mut type_id = use_signal(|| {
+        note.as_ref()
+            .map(|n| n.note_type_id)
+            .or(note_types.first().map(|t| t.id))
+            .unwrap_or_default()
+    });
+    let mut fields = use_signal(|| note.as_ref().map(|n| n.fields.clone()).unwrap_or_default());
+
+    let Some(note_type) = note_types.iter().find(|t| t.id == *type_id.read()).cloned() else {
+        return rsx! {
+            div { class: "loading",
+                "No note types"
+            }
+        };
+    };
+
+    // one value per field of the chosen type
+    let mut values = fields.read().clone();
+    values.resize(note_type.fields.len(), String::new());
+
+    let type_options: Vec<(String, String, bool)> = note_types
+        .iter()
+        .map(|t| (t.id.to_string(), t.name.clone(), t.id == note_type.id))
+        .collect();
+
+    let field_rows: Vec<(usize, String, String)> = note_type
+        .fields
+        .iter()
+        .cloned()
+        .zip(values.iter().cloned())
+        .enumerate()
+        .map(|(index, (name, value))| (index, name, value))
+        .collect();
+
+    let previews: Vec<(String, Vec<Block>, Vec<Block>)> = note_type
+        .templates
+        .iter()
+        .filter_map(|template| {
+            generate_card(&note_type, template, &values)
+                .map(|(front, back)| (card_name(&note_type, template, &values), front, back))
+        })
+        .collect();
+
+    let summary = match previews.len() {
+        0 => "No cards yet: fill in the fields the cards ask for".to_string(),
+        1 => "Makes 1 card".to_string(),
+        n => format!("Makes {n} cards"),
+    };
+    let can_save = !previews.is_empty();
+
+    rsx! {
+        div { class: "card-editor-page note-editor",
+
+            h1 {
+                if note_id.is_some() { "Edit Note" } else { "Create New Note" }
+            }
+
+            // the type of an existing note is fixed, its cards depend on it
+            if note_id.is_none() {
+                div { class: "card-field",
+                    label { "Note type" }
+                    select {
+                        onchange: move |evt| {
+                            if let Ok(id) = evt.value().parse::<i64>() {
+                                type_id.set(id);
+                            }
+                        },
+                        for (id, name, selected) in type_options {
+                            option { value: "{id}", selected, "{name}" }
+                        }
+                    }
+                }
+            }
+
+            for (index, name, value) in field_rows {
+                div { class: "card-field",
+                    label { "{name}" }
+                    textarea {
+                        value: "{value}",
+                        oninput: move |evt| {
+                            let mut fields = fields.write();
+                            if fields.len() <= index {
+                                fields.resize(index + 1, String::new());
+                            }
+                            fields[index] = evt.value();
+                        }
+                    }
+                }
+            }
+
+            h2 { "Cards" }
+            p { class: "note-summary", "{summary}" }
+
+            for (name, front, back) in previews {
+                div { class: "note-card-preview",
+                    h3 { "{name}" }
+                    div { class: "card-surface",
+                        for block in front.iter() {
+                            { render_block(block) }
+                        }
+                    }
+                    div { class: "answer-surface",
+                        for block in back.iter() {
+                            { render_block(block) }
+                        }
+                    }
+                }
+            }
+
+            div { class: "card-actions",
+                button {
+                    class: "button button-secondary",
+                    onclick: move |_| { nav.push(Route::CardListPage { id: deck_id }); },
+                    "Back"
+                }
+
+                button {
+                    class: "button button-primary",
+                    disabled: !can_save,
+                    onclick: move |_| {
+                        let values = values.clone();
+                        let note_type_id = *type_id.read();
+                        spawn(async move {
+                            match note_id {
+                                Some(id) => { update_note(id, values).await; }
+                                None => { add_note(deck_id, note_type_id, values).await; }
+                            }
+                            nav.push(Route::CardListPage { id: deck_id });
+                        });
+                    },
+                    "Save"
+                }
+            }
+        }
+    }
+}
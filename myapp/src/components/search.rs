@@ -0,0 +1,91 @@
+use dioxus::prelude::*;
+use shared::models::{ Card, BLOCK_TYPES };
+use shared::search::parse_query;
+use crate::app::Route;
+use crate::tauri_api::search_cards;
+
+
+/// Searches all cards with the query language from `shared::search`.
+#[component]
+pub fn Search() -> Element {
+    let nav = navigator();
+    let mut query = use_signal(String::new);
+    let mut results = use_signal(|| None::<Vec<Card>>);
+
+    let parse_error = parse_query(&query.read()).err();
+    let block_types = BLOCK_TYPES.join("|");
+
+    let run = move || {
+        let query = query.read().clone();
+        if parse_query(&query).is_err() {
+            return;
+        }
+        spawn(async move {
+            results.set(Some(search_cards(query).await));
+        });
+    };
+
+    rsx! {
+        div { class: "page",
+            div { class: "deck-list",
+
+                h1 { "Search" }
+
+                div { class: "search-bar",
+                    input {
+                        class: "deck-input",
+                        placeholder: "deck:Math tag:exam -tag:done is:due progress:<50 \"eigen value\"",
+                        value: "{query}",
+                        autofocus: true,
+                        oninput: move |e| query.set(e.value()),
+                        onkeydown: move |e| {
+                            if e.key() == Key::Enter {
+                                run();
+                            }
+                        },
+                    }
+                    button {
+                        class: "button button-primary",
+                        disabled: parse_error.is_some(),
+                        onclick: move |_| run(),
+                        "Search"
+                    }
+                }
+
+                if let Some(error) = parse_error {
+                    p { class: "search-error", "{error}" }
+                }
+
+                p { class: "search-help",
+                    "Fields: deck:, tag:, is:due|new|learned|suspended|buried, progress:<50, type:{block_types}, seen:N, failed:N. "
+                    "Use -term to exclude, OR between terms and parentheses to group."
+                }
+
+                if let Some(cards) = results.read().clone() {
+                    p { class: "study-remaining", "{cards.len()} cards" }
+
+                    for card in cards {
+                        div { key: "{card.id}", class: "card-preview",
+                            div { class: "card-main",
+                                h2 { class: "card-title", "{card.name}" }
+                            }
+                            button {
+                                class: "card-open-button",
+                                onclick: move |_| { nav.push(Route::CardView { id: card.id }); },
+                                "Open"
+                            }
+                        }
+                    }
+                }
+
+                div { class: "deck-global-actions",
+                    button {
+                        class: "button",
+                        onclick: move |_| { nav.push(Route::DeckList); },
+                        "Back"
+                    }
+                }
+            }
+        }
+    }
+}
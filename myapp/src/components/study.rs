@@ -0,0 +1,242 @@
+use dioxus::prelude::*;
+use shared::models::{Block, Card};
+use shared::quiz::quiz_count;
+use crate::components::block_view::{render_study_block, MultipleChoiceBlock, TypeAnswerBlock};
+use crate::app::Route;
+use crate::tauri_api::{ get_study_queue, get_filtered_study_queue, update_score, suspend_cards, bury_cards, undo_last };
+
+
+/// Front side, "Show answer", back side and the rating buttons of one card.
+/// Cards with multiple-choice questions are graded by the answers given
+/// instead of the rating buttons; for typed answers the grade is only
+/// proposed, as the learner may know better (e.g. a synonym).
+#[component]
+pub fn CardStudy(card: Card, on_graded: EventHandler<bool>) -> Element {
+    let mut show_answer = use_signal(|| false);
+    // One entry per question answered so far, whether it was right
+    let mut results = use_signal(Vec::<bool>::new);
+
+    let quizzes = quiz_count(&card.front_blocks);
+    let answered = quizzes > 0 && results.read().len() >= quizzes;
+    let passed = results.read().iter().all(|&correct| correct);
+    let revealed = *show_answer.read() || answered;
+    let proposed_only = card.front_blocks.iter().any(|block| matches!(block, Block::TypeAnswer { .. }));
+    let suggested = |good: bool| if answered && passed == good { " suggested" } else { "" };
+    let bad_class = format!("button button-bad{}", suggested(false));
+    let good_class = format!("button button-good{}", suggested(true));
+    let (result_class, result_text) = if passed {
+        ("quiz-result passed", "Correct")
+    } else {
+        ("quiz-result failed", "Not quite")
+    };
+
+    rsx! {
+        // Study area
+        div { class: "card-study",
+
+            div { class: "card-surface",
+                for block in &card.front_blocks {
+                    if let Block::MultipleChoice { question, options, correct, shuffle } = block {
+                        MultipleChoiceBlock {
+                            question: question.clone(),
+                            options: options.clone(),
+                            correct: correct.clone(),
+                            shuffle: *shuffle,
+                            on_answered: move |correct| results.write().push(correct),
+                        }
+                    } else if let Block::TypeAnswer { expected, case_sensitive, accept } = block {
+                        TypeAnswerBlock {
+                            expected: expected.clone(),
+                            case_sensitive: *case_sensitive,
+                            accept: accept.clone(),
+                            on_answered: move |correct| results.write().push(correct),
+                        }
+                    } else {
+                        { render_study_block(block, card.cloze, revealed) }
+                    }
+                }
+            }
+
+            if !revealed && quizzes == 0 {
+                div { class: "show-answer-container",
+                    button {
+                        class: "button button-primary",
+                        onclick: move |_| show_answer.set(true),
+                        "Show answer"
+                    }
+                }
+            }
+
+            if revealed {
+                div { class: "answer-surface",
+                    for block in &card.back_blocks {
+                        { render_study_block(block, card.cloze, true) }
+                    }
+                }
+            }
+
+            if answered && !proposed_only {
+                div { class: "card-rating",
+                    p { class: "{result_class}", "{result_text}" }
+
+                    button {
+                        class: "button button-primary",
+                        onclick: move |_| on_graded.call(passed),
+                        "Continue"
+                    }
+                }
+            } else if revealed {
+                // Rating buttons
+                div { class: "card-rating",
+
+                    button {
+                        class: "{bad_class}",
+                        onclick: move |_| on_graded.call(false),
+                        "BAD"
+                    }
+
+                    button {
+                        class: "{good_class}",
+                        onclick: move |_| on_graded.call(true),
+                        "GOOD"
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+/// Studies all due cards of a deck, including its sub-decks, one after another.
+#[component]
+pub fn StudySession(id: i64) -> Element {
+    let mut queue = use_signal(|| None::<Vec<Card>>);
+
+    use_effect(move || {
+        spawn(async move {
+            let loaded = get_study_queue(id).await;
+            queue.set(Some(loaded));
+        });
+    });
+
+    match queue.read().clone() {
+        Some(cards) => rsx! { StudyQueue { cards } },
+        None => rsx! {
+            div { class: "loading",
+                "Loading cards..."
+            }
+        },
+    }
+}
+
+
+/// Studies the cards currently matching a filtered deck.
+#[component]
+pub fn FilteredStudySession(id: i64) -> Element {
+    let mut queue = use_signal(|| None::<Vec<Card>>);
+
+    use_effect(move || {
+        spawn(async move {
+            let loaded = get_filtered_study_queue(id).await;
+            queue.set(Some(loaded));
+        });
+    });
+
+    match queue.read().clone() {
+        Some(cards) => rsx! { StudyQueue { cards } },
+        None => rsx! {
+            div { class: "loading",
+                "Loading cards..."
+            }
+        },
+    }
+}
+
+
+/// Goes through the given cards one after another.
+#[component]
+fn StudyQueue(cards: Vec<Card>) -> Element {
+    let nav = navigator();
+    let mut position = use_signal(|| 0usize);
+    // Position of the card answered last, while its answer can be undone
+    let mut last_graded = use_signal(|| None::<usize>);
+
+    let current = cards.get(*position.read()).cloned();
+    let remaining = cards.len().saturating_sub(*position.read());
+    // siblings of a cloze card share the card id
+    let cloze_key = current.as_ref().and_then(|c| c.cloze).unwrap_or(0);
+
+    rsx! {
+        div { class: "card-view",
+
+            match current {
+                Some(card) => rsx! {
+                    p { class: "study-remaining", "{remaining} cards left" }
+                    h1 { class: "card-title", "{card.name}" }
+
+                    CardStudy {
+                        key: "{card.id}-{cloze_key}",
+                        card: card.clone(),
+                        on_graded: move |correct| {
+                            let card_id = card.id;
+                            let cloze = card.cloze;
+                            spawn(async move {
+                                update_score(card_id, correct, cloze).await;
+                                last_graded.set(Some(*position.read()));
+                                position += 1;
+                            });
+                        },
+                    }
+
+                    div { class: "study-skip",
+                        button {
+                            class: "button button-secondary",
+                            onclick: move |_| {
+                                spawn(async move {
+                                    bury_cards(vec![card.id], Some(1)).await;
+                                    position += 1;
+                                });
+                            },
+                            "Bury until tomorrow"
+                        }
+                        button {
+                            class: "button button-secondary",
+                            onclick: move |_| {
+                                spawn(async move {
+                                    suspend_cards(vec![card.id], true).await;
+                                    position += 1;
+                                });
+                            },
+                            "Suspend"
+                        }
+                    }
+                },
+                None => rsx! {
+                    h1 { "Nothing left to study" }
+                },
+            }
+
+            div { class: "card-actions",
+                button {
+                    class: "button button-secondary",
+                    onclick: move |_| { nav.push(Route::DeckList); },
+                    "Back"
+                }
+
+                if let Some(previous) = *last_graded.read() {
+                    button {
+                        class: "button button-secondary",
+                        onclick: move |_| {
+                            spawn(async move {
+                                undo_last().await;
+                                last_graded.set(None);
+                                position.set(previous);
+                            });
+                        },
+                        "Undo last answer"
+                    }
+                }
+            }
+        }
+    }
+}
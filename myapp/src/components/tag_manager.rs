@@ -0,0 +1,227 @@
+use dioxus::prelude::*;
+use shared::models::{ Card, TagInfo };
+use shared::tags::TAG_SEPARATOR;
+use crate::app::Route;
+use crate::tauri_api::{ list_tags, get_cards_with_tag, rename_tag, merge_tags, delete_tag };
+
+#[derive(Clone, PartialEq)]
+enum TagAction {
+    Rename(String),
+    Merge(String),
+    Delete(String),
+}
+
+#[component]
+pub fn TagManager() -> Element {
+    let nav = navigator();
+    let mut tags = use_signal(|| Vec::<TagInfo>::new());
+    let mut action = use_signal(|| None::<TagAction>);
+    let mut input = use_signal(String::new);
+    let mut shown = use_signal(|| None::<(String, Vec<Card>)>);
+
+    let reload = move || {
+        spawn(async move {
+            tags.set(list_tags().await);
+        });
+    };
+
+    use_future(move || async move {
+        tags.set(list_tags().await);
+    });
+
+    let tag_views: Vec<(String, usize, String, u32)> = tags
+        .read()
+        .iter()
+        .map(|t| {
+            let depth = t.name.matches(TAG_SEPARATOR).count();
+            let leaf = t.name.rsplit(TAG_SEPARATOR).next().unwrap_or_default().to_string();
+            (t.name.clone(), depth, leaf, t.card_count)
+        })
+        .collect();
+
+    let all_names: Vec<String> = tags.read().iter().map(|t| t.name.clone()).collect();
+
+    rsx! {
+        div { class: "page",
+            div { class: "deck-list",
+
+                h1 { "Tags" }
+
+                if tag_views.is_empty() {
+                    p { "No tags yet." }
+                }
+
+                for (name, depth, leaf, count) in tag_views {
+                    div {
+                        key: "{name}",
+                        class: "tag-row",
+                        style: "margin-left: {depth * 24}px;",
+
+                        button {
+                            class: "card-tag",
+                            onclick: {
+                                let name = name.clone();
+                                move |_| {
+                                    let name = name.clone();
+                                    spawn(async move {
+                                        let cards = get_cards_with_tag(name.clone()).await;
+                                        shown.set(Some((name, cards)));
+                                    });
+                                }
+                            },
+                            "{leaf}"
+                        }
+                        span { class: "deck-counts", "{count} cards" }
+
+                        div { class: "deck-actions",
+                            button {
+                                class: "button",
+                                onclick: {
+                                    let name = name.clone();
+                                    move |_| {
+                                        input.set(name.clone());
+                                        action.set(Some(TagAction::Rename(name.clone())));
+                                    }
+                                },
+                                "Rename"
+                            }
+                            button {
+                                class: "button",
+                                onclick: {
+                                    let name = name.clone();
+                                    let first_other = all_names.iter().find(|n| **n != name).cloned();
+                                    move |_| {
+                                        input.set(first_other.clone().unwrap_or_default());
+                                        action.set(Some(TagAction::Merge(name.clone())));
+                                    }
+                                },
+                                "Merge"
+                            }
+                            button {
+                                class: "button button-danger",
+                                onclick: {
+                                    let name = name.clone();
+                                    move |_| action.set(Some(TagAction::Delete(name.clone())))
+                                },
+                                "Delete"
+                            }
+                        }
+
+                        match action.read().clone() {
+                            Some(TagAction::Rename(tag)) if tag == name => rsx! {
+                                div { class: "rename-deck",
+                                    input {
+                                        class: "rename_input",
+                                        value: "{input}",
+                                        oninput: move |e| input.set(e.value()),
+                                    }
+                                    div { class: "rename-actions",
+                                        button {
+                                            class: "button button-primary",
+                                            onclick: move |_| {
+                                                let (old, new) = (tag.clone(), input.read().clone());
+                                                action.set(None);
+                                                spawn(async move {
+                                                    rename_tag(old, new).await;
+                                                    reload();
+                                                });
+                                            },
+                                            "Save"
+                                        }
+                                        button {
+                                            class: "button button-secondary",
+                                            onclick: move |_| action.set(None),
+                                            "Cancel"
+                                        }
+                                    }
+                                }
+                            },
+                            Some(TagAction::Merge(tag)) if tag == name => rsx! {
+                                div { class: "rename-deck",
+                                    select {
+                                        class: "deck-input",
+                                        onchange: move |e| input.set(e.value()),
+                                        for other in all_names.iter().filter(|n| **n != name) {
+                                            option {
+                                                value: "{other}",
+                                                selected: *input.read() == *other,
+                                                "{other}"
+                                            }
+                                        }
+                                    }
+                                    div { class: "rename-actions",
+                                        button {
+                                            class: "button button-primary",
+                                            onclick: move |_| {
+                                                let (source, target) = (tag.clone(), input.read().clone());
+                                                action.set(None);
+                                                spawn(async move {
+                                                    merge_tags(vec![source], target).await;
+                                                    reload();
+                                                });
+                                            },
+                                            "Merge"
+                                        }
+                                        button {
+                                            class: "button button-secondary",
+                                            onclick: move |_| action.set(None),
+                                            "Cancel"
+                                        }
+                                    }
+                                }
+                            },
+                            Some(TagAction::Delete(tag)) if tag == name => rsx! {
+                                div { class: "delete-actions",
+                                    button {
+                                        class: "button button-danger",
+                                        onclick: move |_| {
+                                            let tag = tag.clone();
+                                            action.set(None);
+                                            spawn(async move {
+                                                delete_tag(tag).await;
+                                                reload();
+                                            });
+                                        },
+                                        "Remove \"{name}\" from all cards"
+                                    }
+                                    button {
+                                        class: "button button-secondary",
+                                        onclick: move |_| action.set(None),
+                                        "Cancel"
+                                    }
+                                }
+                            },
+                            _ => rsx! {},
+                        }
+                    }
+                }
+
+                if let Some((tag, cards)) = shown.read().clone() {
+                    div { class: "tag-cards",
+                        h2 { "Cards tagged \"{tag}\"" }
+                        for card in cards {
+                            div { key: "{card.id}", class: "card-preview",
+                                div { class: "card-main",
+                                    h2 { class: "card-title", "{card.name}" }
+                                }
+                                button {
+                                    class: "card-open-button",
+                                    onclick: move |_| { nav.push(Route::CardView { id: card.id }); },
+                                    "Open"
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "deck-global-actions",
+                    button {
+                        class: "button",
+                        onclick: move |_| { nav.push(Route::DeckList); },
+                        "Back"
+                    }
+                }
+            }
+        }
+    }
+}
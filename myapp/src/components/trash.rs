@@ -0,0 +1,180 @@
+use dioxus::prelude::*;
+use shared::models::{ Trash as TrashContents, TrashedItem };
+use crate::app::Route;
+use crate::tauri_api::{
+    list_trash, restore_card, restore_deck, purge_card, purge_deck, empty_trash,
+    set_trash_retention_days,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum ItemKind {
+    Deck,
+    Card,
+}
+
+/// "deleted 3 days ago, removed in 27 days"
+fn age_text(item: &TrashedItem, retention_days: u32) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let age_days = (now - item.deleted_at).max(0) / 86_400;
+    let left = (retention_days as i64 - age_days).max(0);
+
+    let deleted = match age_days {
+        0 => "deleted today".to_string(),
+        1 => "deleted yesterday".to_string(),
+        n => format!("deleted {n} days ago"),
+    };
+
+    format!("{deleted}, removed in {left} days")
+}
+
+#[component]
+pub fn Trash() -> Element {
+    let nav = navigator();
+    let mut trash = use_signal(TrashContents::default);
+    let mut retention = use_signal(String::new);
+    let mut emptying = use_signal(|| false);
+
+    use_future(move || async move {
+        let loaded = list_trash().await;
+        retention.set(loaded.retention_days.to_string());
+        trash.set(loaded);
+    });
+
+    let reload = move || {
+        spawn(async move {
+            trash.set(list_trash().await);
+        });
+    };
+
+    let retention_days = trash.read().retention_days;
+    let items: Vec<(String, ItemKind, TrashedItem, String)> = trash
+        .read()
+        .decks
+        .iter()
+        .map(|d| (format!("deck-{}", d.id), ItemKind::Deck, d.clone()))
+        .chain(trash.read().cards.iter().map(|c| (format!("card-{}", c.id), ItemKind::Card, c.clone())))
+        .map(|(key, kind, item)| {
+            let age = age_text(&item, retention_days);
+            (key, kind, item, age)
+        })
+        .collect();
+
+    rsx! {
+        div { class: "page",
+            div { class: "deck-list",
+
+                h1 { "Trash" }
+
+                if items.is_empty() {
+                    p { "The trash is empty." }
+                }
+
+                for (key, kind, item, age) in items {
+                    div {
+                        key: "{key}",
+                        class: "trash-row",
+
+                        div { class: "card-main",
+                            h2 { class: "card-title",
+                                if kind == ItemKind::Deck { "Deck: {item.name}" } else { "{item.name}" }
+                            }
+                            span { class: "deck-counts", "{age}" }
+                        }
+
+                        div { class: "deck-actions",
+                            button {
+                                class: "button",
+                                onclick: move |_| {
+                                    spawn(async move {
+                                        match kind {
+                                            ItemKind::Deck => restore_deck(item.id).await,
+                                            ItemKind::Card => restore_card(item.id).await,
+                                        }
+                                        reload();
+                                    });
+                                },
+                                "Restore"
+                            }
+                            button {
+                                class: "button button-danger",
+                                onclick: move |_| {
+                                    spawn(async move {
+                                        match kind {
+                                            ItemKind::Deck => purge_deck(item.id).await,
+                                            ItemKind::Card => purge_card(item.id).await,
+                                        }
+                                        reload();
+                                    });
+                                },
+                                "Delete forever"
+                            }
+                        }
+                    }
+                }
+
+                div { class: "filter-row",
+                    label { "Delete items for good after" }
+                    input {
+                        class: "deck-input",
+                        r#type: "number",
+                        min: "1",
+                        value: "{retention}",
+                        oninput: move |evt| retention.set(evt.value()),
+                    }
+                    label { "days" }
+                    button {
+                        class: "button",
+                        disabled: !retention.read().trim().parse::<u32>().is_ok_and(|d| d > 0),
+                        onclick: move |_| {
+                            let Ok(days) = retention.read().trim().parse::<u32>() else {
+                                return;
+                            };
+                            spawn(async move {
+                                set_trash_retention_days(days).await;
+                                reload();
+                            });
+                        },
+                        "Save"
+                    }
+                }
+
+                if *emptying.read() {
+                    div { class: "delete-card",
+                        h1 { "Delete everything in the trash forever?" }
+                        div { class: "delete-actions",
+                            button {
+                                class: "button button-danger",
+                                onclick: move |_| {
+                                    emptying.set(false);
+                                    spawn(async move {
+                                        empty_trash().await;
+                                        reload();
+                                    });
+                                },
+                                "YES"
+                            }
+                            button {
+                                class: "button button-secondary",
+                                onclick: move |_| emptying.set(false),
+                                "NO"
+                            }
+                        }
+                    }
+                }
+
+                div { class: "deck-global-actions",
+                    button {
+                        class: "button",
+                        onclick: move |_| { nav.push(Route::DeckList); },
+                        "Back"
+                    }
+                    button {
+                        class: "button button-danger",
+                        onclick: move |_| emptying.set(true),
+                        "Empty trash"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 mod components;
 mod app;
 mod tauri_api;
+mod timer;
 
 use app::App;
 use dioxus::prelude::*;
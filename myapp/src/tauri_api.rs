@@ -1,9 +1,10 @@
 use wasm_bindgen::{prelude::*};
 use serde::{Serialize, de::DeserializeOwned, Deserialize};
 use serde_wasm_bindgen;
-use shared::models::{Deck, Card, Block};
+use shared::models::{Deck, Card, Block, TextExportFormat, ImportMode, ImportPreview, ImportSummary, TagInfo, FilterQuery, FilteredDeck, CardPage, CardSort, BulkAction, Trash, CardRevision, EditorBlock, CardBlocks, SaveReport, Draft, Note, NoteType};
 use wasm_bindgen::JsValue;
 use shared::FileResponse;
+use shared::tex::TexError;
 
 /// Bind to Tauri’s real invoke()
 #[wasm_bindgen]
@@ -56,6 +57,26 @@ pub async fn pick_image() -> String {
     }
 }
 
+pub async fn pick_audio() -> String {
+    let ret: Option<FileResponse> =
+        tauri("plugin:bliet|pick_audio", ()).await;
+
+    match ret {
+        Some(audio) => audio.path,
+        None => String::new(), // ← user cancelled
+    }
+}
+
+pub async fn pick_video() -> String {
+    let ret: Option<FileResponse> =
+        tauri("plugin:bliet|pick_video", ()).await;
+
+    match ret {
+        Some(video) => video.path,
+        None => String::new(), // ← user cancelled
+    }
+}
+
 pub async fn pick_archive() -> String {
     let ret: Option<FileResponse> =
         tauri("plugin:bliet|pick_archive", ()).await;
@@ -102,9 +123,36 @@ pub async fn export_deck(deck_id: i64) {
     let _: () = tauri("export_deck", ExportDeckArgs { deck_id } ).await;
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportDeckTextArgs {
+    deck_id: i64,
+    format: TextExportFormat,
+}
+
+/// CSV for Anki or a Markdown document, without media files.
+pub async fn export_deck_text(deck_id: i64, format: TextExportFormat) {
+    let _: () = tauri("export_deck_text", ExportDeckTextArgs { deck_id, format }).await;
+}
+
 
-pub async fn import_deck() -> i64 {
-    tauri("import_deck", ()).await
+pub async fn stage_import() -> Option<ImportSummary> {
+    tauri("stage_import", ()).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportModeArgs {
+    mode: ImportMode,
+}
+
+pub async fn preview_import(mode: ImportMode) -> ImportPreview {
+    tauri("preview_import", ImportModeArgs { mode }).await
+}
+
+pub async fn import_deck(mode: ImportMode) -> i64 {
+    tauri("import_deck", ImportModeArgs { mode }).await
 }
 
 
@@ -182,19 +230,44 @@ pub async fn get_card(id: i64) -> Card {
 // Save blocks
 //
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GetCardBlocksArgs {
+    card_id: i64,
+}
+
+/// Blocks of a card with their ids, for the editor.
+pub async fn get_card_blocks(card_id: i64) -> CardBlocks {
+    tauri("get_card_blocks", GetCardBlocksArgs { card_id }).await
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct SaveCardBlocksArgs<'a> {
     card_id: i64,
-    front: &'a Vec<Block>,
-    back: &'a Vec<Block>,
+    front: &'a Vec<EditorBlock>,
+    back: &'a Vec<EditorBlock>,
 }
 
-pub async fn save_card_blocks(card_id: i64, front: &Vec<Block>, back: &Vec<Block>) {
-    let _: () = tauri(
+/// Reports problems in the TeX of math blocks; they are saved anyway.
+pub async fn save_card_blocks(card_id: i64, front: &Vec<EditorBlock>, back: &Vec<EditorBlock>) -> SaveReport {
+    tauri(
         "save_card_blocks",
         SaveCardBlocksArgs { card_id, front, back }
-    ).await;
+    ).await
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveBlockArgs<'a> {
+    card_id: i64,
+    id: i64,
+    block: &'a Block,
+}
+
+/// Autosaves a single (already saved) block of the card.
+pub async fn save_block(card_id: i64, id: i64, block: &Block) {
+    let _: () = tauri("save_block", SaveBlockArgs { card_id, id, block }).await;
 }
 
 
@@ -222,6 +295,32 @@ pub async fn update_card_metadata(
 }
 
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveCardArgs<'a> {
+    id: i64,
+    name: String,
+    tags: Option<String>,
+    front: &'a Vec<EditorBlock>,
+    back: &'a Vec<EditorBlock>,
+    base_version: Option<i64>,
+}
+
+/// Saves name, tags and blocks of an edited card as one undoable step.
+/// If the card changed since `base_version` nothing is saved and the current
+/// card is returned as the conflict; pass `None` to overwrite it anyway.
+pub async fn save_card(
+    id: i64,
+    name: String,
+    tags: Option<String>,
+    front: &Vec<EditorBlock>,
+    back: &Vec<EditorBlock>,
+    base_version: Option<i64>,
+) -> SaveReport {
+    tauri("save_card", SaveCardArgs { id, name, tags, front, back, base_version }).await
+}
+
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct GetCardsArgs {
@@ -257,27 +356,451 @@ pub async fn delete_deck(deck_id: i64) {
 struct UpdateCardScoreArgs {
     card_id: i64,
     correct: bool,
+    cloze: Option<u32>,
 }
 
 
-pub async fn update_score(card_id: i64, correct: bool) -> Card {
+/// `cloze` is the deletion that was asked when studying a cloze card.
+pub async fn update_score(card_id: i64, correct: bool, cloze: Option<u32>) -> Card {
     tauri(
         "update_score",
-        UpdateCardScoreArgs {card_id, correct}
+        UpdateCardScoreArgs {card_id, correct, cloze}
     ).await
 }
 
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-struct DeleteBlockArgs {
-    virtual_path: String,
+struct GetStudyQueueArgs {
+    deck_id: i64,
 }
 
-pub async fn delete_block_from_app_data(virtual_path: String) {
-    let _: () = tauri(
-        "delete_block_from_app_data",
-        DeleteBlockArgs { virtual_path },
-    )
-    .await;
-}
\ No newline at end of file
+pub async fn get_study_queue(deck_id: i64) -> Vec<Card> {
+    tauri("get_study_queue", GetStudyQueueArgs { deck_id }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveCardsArgs {
+    card_ids: Vec<i64>,
+    target_deck_id: i64,
+}
+
+pub async fn move_cards(card_ids: Vec<i64>, target_deck_id: i64) {
+    let _: () = tauri("move_cards", MoveCardsArgs { card_ids, target_deck_id }).await;
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyCardsArgs {
+    card_ids: Vec<i64>,
+    target_deck_id: i64,
+    reset_progress: bool,
+}
+
+pub async fn copy_cards(card_ids: Vec<i64>, target_deck_id: i64, reset_progress: bool) -> Vec<i64> {
+    tauri("copy_cards", CopyCardsArgs { card_ids, target_deck_id, reset_progress }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeDecksArgs {
+    source_ids: Vec<i64>,
+    target_id: i64,
+}
+
+/// Returns the number of duplicate cards that were moved to the trash.
+pub async fn merge_decks(source_ids: Vec<i64>, target_id: i64) -> u32 {
+    tauri("merge_decks", MergeDecksArgs { source_ids, target_id }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SplitDeckArgs {
+    deck_id: i64,
+    by_tag: Option<String>,
+}
+
+pub async fn split_deck(deck_id: i64, by_tag: Option<String>) -> Vec<i64> {
+    tauri("split_deck", SplitDeckArgs { deck_id, by_tag }).await
+}
+
+
+//
+// ─────────────────────────────────────────────
+//   Tags
+// ─────────────────────────────────────────────
+//
+
+pub async fn list_tags() -> Vec<TagInfo> {
+    tauri("list_tags", ()).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TagArgs {
+    tag: String,
+}
+
+pub async fn get_cards_with_tag(tag: String) -> Vec<Card> {
+    tauri("get_cards_with_tag", TagArgs { tag }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameTagArgs {
+    old_name: String,
+    new_name: String,
+}
+
+pub async fn rename_tag(old_name: String, new_name: String) {
+    let _: () = tauri("rename_tag", RenameTagArgs { old_name, new_name }).await;
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeTagsArgs {
+    sources: Vec<String>,
+    target: String,
+}
+
+pub async fn merge_tags(sources: Vec<String>, target: String) {
+    let _: () = tauri("merge_tags", MergeTagsArgs { sources, target }).await;
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteTagArgs {
+    name: String,
+}
+
+pub async fn delete_tag(name: String) {
+    let _: () = tauri("delete_tag", DeleteTagArgs { name }).await;
+}
+
+
+pub async fn list_filtered_decks() -> Vec<FilteredDeck> {
+    tauri("list_filtered_decks", ()).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FilteredDeckArgs {
+    id: i64,
+}
+
+pub async fn get_filtered_deck(id: i64) -> FilteredDeck {
+    tauri("get_filtered_deck", FilteredDeckArgs { id }).await
+}
+
+pub async fn delete_filtered_deck(id: i64) {
+    let _: () = tauri("delete_filtered_deck", FilteredDeckArgs { id }).await;
+}
+
+pub async fn get_filtered_study_queue(id: i64) -> Vec<Card> {
+    tauri("get_filtered_study_queue", FilteredDeckArgs { id }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveFilteredDeckArgs {
+    id: Option<i64>,
+    name: String,
+    query: FilterQuery,
+}
+
+pub async fn save_filtered_deck(id: Option<i64>, name: String, query: FilterQuery) -> i64 {
+    tauri("save_filtered_deck", SaveFilteredDeckArgs { id, name, query }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CountFilterMatchesArgs {
+    query: FilterQuery,
+}
+
+pub async fn count_filter_matches(query: FilterQuery) -> u32 {
+    tauri("count_filter_matches", CountFilterMatchesArgs { query }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchCardsArgs {
+    query: String,
+}
+
+/// Callers validate the query with `shared::search::parse_query` first.
+pub async fn search_cards(query: String) -> Vec<Card> {
+    tauri("search_cards", SearchCardsArgs { query }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListCardsArgs {
+    query: String,
+    sort: CardSort,
+    descending: bool,
+    page: u32,
+}
+
+/// Callers validate the query with `shared::search::parse_query` first.
+pub async fn list_cards(query: String, sort: CardSort, descending: bool, page: u32) -> CardPage {
+    tauri("list_cards", ListCardsArgs { query, sort, descending, page }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BulkUpdateCardsArgs {
+    card_ids: Vec<i64>,
+    action: BulkAction,
+}
+
+pub async fn bulk_update_cards(card_ids: Vec<i64>, action: BulkAction) {
+    let _: () = tauri("bulk_update_cards", BulkUpdateCardsArgs { card_ids, action }).await;
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResetProgressArgs {
+    card_ids: Vec<i64>,
+}
+
+pub async fn reset_progress(card_ids: Vec<i64>) {
+    let _: () = tauri("reset_progress", ResetProgressArgs { card_ids }).await;
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SuspendCardsArgs {
+    card_ids: Vec<i64>,
+    suspended: bool,
+}
+
+pub async fn suspend_cards(card_ids: Vec<i64>, suspended: bool) {
+    let _: () = tauri("suspend_cards", SuspendCardsArgs { card_ids, suspended }).await;
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuryCardsArgs {
+    card_ids: Vec<i64>,
+    days: Option<u32>,
+}
+
+pub async fn bury_cards(card_ids: Vec<i64>, days: Option<u32>) {
+    let _: () = tauri("bury_cards", BuryCardsArgs { card_ids, days }).await;
+}
+
+
+//
+// ─────────────────────────────────────────────
+//   Trash and undo
+// ─────────────────────────────────────────────
+//
+
+pub async fn list_trash() -> Trash {
+    tauri("list_trash", ()).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashItemArgs {
+    id: i64,
+}
+
+pub async fn restore_card(id: i64) {
+    let _: () = tauri("restore_card", TrashItemArgs { id }).await;
+}
+
+pub async fn restore_deck(id: i64) {
+    let _: () = tauri("restore_deck", TrashItemArgs { id }).await;
+}
+
+pub async fn purge_card(id: i64) {
+    let _: () = tauri("purge_card", TrashItemArgs { id }).await;
+}
+
+pub async fn purge_deck(id: i64) {
+    let _: () = tauri("purge_deck", TrashItemArgs { id }).await;
+}
+
+pub async fn empty_trash() {
+    let _: () = tauri("empty_trash", ()).await;
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetTrashRetentionDaysArgs {
+    days: u32,
+}
+
+pub async fn set_trash_retention_days(days: u32) {
+    let _: () = tauri("set_trash_retention_days", SetTrashRetentionDaysArgs { days }).await;
+}
+
+
+/// Description of the action `undo_last` would revert.
+pub async fn last_undo() -> Option<String> {
+    tauri("last_undo", ()).await
+}
+
+/// Reverts the last grading, deletion or edit and returns a message for the user.
+pub async fn undo_last() -> Option<String> {
+    tauri("undo_last", ()).await
+}
+
+
+//
+// ─────────────────────────────────────────────
+//   Edit history
+// ─────────────────────────────────────────────
+//
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GetCardHistoryArgs {
+    card_id: i64,
+}
+
+/// Revisions of a card, newest first.
+pub async fn get_card_history(card_id: i64) -> Vec<CardRevision> {
+    tauri("get_card_history", GetCardHistoryArgs { card_id }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreRevisionArgs {
+    revision_id: i64,
+}
+
+/// Puts an old revision back on the card and returns the restored card.
+pub async fn restore_revision(revision_id: i64) -> Card {
+    tauri("restore_revision", RestoreRevisionArgs { revision_id }).await
+}
+
+
+//
+// ─────────────────────────────────────────────
+//   Drafts
+// ─────────────────────────────────────────────
+//
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveDraftArgs<'a> {
+    draft: &'a Draft,
+}
+
+/// Stores the editor state and returns the id of the draft.
+pub async fn save_draft(draft: &Draft) -> i64 {
+    tauri("save_draft", SaveDraftArgs { draft }).await
+}
+
+
+/// Drafts left over from earlier sessions, newest first.
+pub async fn list_drafts() -> Vec<Draft> {
+    tauri("list_drafts", ()).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DraftArgs {
+    id: i64,
+}
+
+pub async fn get_draft(id: i64) -> Draft {
+    tauri("get_draft", DraftArgs { id }).await
+}
+
+pub async fn delete_draft(id: i64) {
+    let _: () = tauri("delete_draft", DraftArgs { id }).await;
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GetCardDraftArgs {
+    card_id: i64,
+}
+
+/// Unsaved changes to an existing card, if any.
+pub async fn get_card_draft(card_id: i64) -> Option<Draft> {
+    tauri("get_card_draft", GetCardDraftArgs { card_id }).await
+}
+
+
+//
+// Notes
+//
+
+pub async fn list_note_types() -> Vec<NoteType> {
+    tauri("list_note_types", ()).await
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddNoteArgs {
+    deck_id: i64,
+    note_type_id: i64,
+    fields: Vec<String>,
+}
+
+/// Creates the note and its cards; returns the ids of the cards.
+pub async fn add_note(deck_id: i64, note_type_id: i64, fields: Vec<String>) -> Vec<i64> {
+    tauri("add_note", AddNoteArgs { deck_id, note_type_id, fields }).await
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GetNoteArgs {
+    id: i64,
+}
+
+pub async fn get_note(id: i64) -> Note {
+    tauri("get_note", GetNoteArgs { id }).await
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateNoteArgs {
+    id: i64,
+    fields: Vec<String>,
+}
+
+/// Saves the fields and rewrites all cards generated from the note.
+pub async fn update_note(id: i64, fields: Vec<String>) -> Vec<i64> {
+    tauri("update_note", UpdateNoteArgs { id, fields }).await
+}
+
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidateMathArgs<'a> {
+    source: &'a str,
+}
+
+/// Problems in the TeX of a math block, with their positions.
+pub async fn validate_math(source: &str) -> Vec<TexError> {
+    tauri("validate_math", ValidateMathArgs { source }).await
+}
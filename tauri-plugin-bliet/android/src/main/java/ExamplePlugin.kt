@@ -23,6 +23,8 @@ class ExamplePlugin(private val activity: Activity) : Plugin(activity) {
     private val PICK_IMAGE_REQUEST_CODE = 1
     private val PICK_ARCHIVE_REQUEST_CODE = 2
     private val PICK_IMPORT_REQUEST_CODE = 3   // ✅ unique
+    private val PICK_AUDIO_REQUEST_CODE = 4
+    private val PICK_VIDEO_REQUEST_CODE = 5
 
     private var pendingInvoke: Invoke? = null
 
@@ -66,6 +68,47 @@ class ExamplePlugin(private val activity: Activity) : Plugin(activity) {
         activity.startActivityForResult(intent, PICK_ARCHIVE_REQUEST_CODE)
     }
 
+    // ================================
+    // pickAudio
+    // ================================
+    @Command
+    fun pickAudio(invoke: Invoke) {
+        pendingInvoke = invoke
+
+        val intent = Intent(Intent.ACTION_GET_CONTENT).apply {
+            type = "audio/*"
+            addCategory(Intent.CATEGORY_OPENABLE)
+            putExtra(
+                Intent.EXTRA_MIME_TYPES,
+                arrayOf(
+                    "audio/mpeg",
+                    "audio/ogg",
+                    "audio/wav",
+                    "audio/x-wav",
+                    "audio/mp4",
+                    "audio/x-m4a"
+                )
+            )
+        }
+
+        activity.startActivityForResult(intent, PICK_AUDIO_REQUEST_CODE)
+    }
+
+    // ================================
+    // pickVideo
+    // ================================
+    @Command
+    fun pickVideo(invoke: Invoke) {
+        pendingInvoke = invoke
+
+        val intent = Intent(Intent.ACTION_GET_CONTENT).apply {
+            type = "video/*"
+            addCategory(Intent.CATEGORY_OPENABLE)
+        }
+
+        activity.startActivityForResult(intent, PICK_VIDEO_REQUEST_CODE)
+    }
+
     // ================================
     // pickImportFile (BYTES)
     // ================================
@@ -119,9 +162,11 @@ class ExamplePlugin(private val activity: Activity) : Plugin(activity) {
                 }
             }
 
-            // ---------- IMAGE / ARCHIVE (path) ----------
+            // ---------- IMAGE / ARCHIVE / AUDIO / VIDEO (path) ----------
             PICK_IMAGE_REQUEST_CODE,
-            PICK_ARCHIVE_REQUEST_CODE -> {
+            PICK_ARCHIVE_REQUEST_CODE,
+            PICK_AUDIO_REQUEST_CODE,
+            PICK_VIDEO_REQUEST_CODE -> {
                 val result = copyUriToFilesDir(uri)
                 if (result == null) {
                     invoke.reject("Failed to import file")
@@ -16,4 +16,18 @@ pub(crate) async fn pick_archive<R: Runtime>(
     app: AppHandle<R>,
 ) -> crate::Result<Option<FileResponse>> {
     app.bliet().pick_archive().await
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub(crate) async fn pick_audio<R: Runtime>(
+    app: AppHandle<R>,
+) -> crate::Result<Option<FileResponse>> {
+    app.bliet().pick_audio().await
+}
+
+#[tauri::command]
+pub(crate) async fn pick_video<R: Runtime>(
+    app: AppHandle<R>,
+) -> crate::Result<Option<FileResponse>> {
+    app.bliet().pick_video().await
+}
@@ -18,6 +18,8 @@ pub fn init<R: Runtime, C: DeserializeOwned>(
 pub enum PickKind {
     Image,
     Archive,
+    Audio,
+    Video,
 }
 
 impl PickKind {
@@ -25,6 +27,8 @@ impl PickKind {
         match self {
             PickKind::Image => ("Images", &["png", "jpg", "jpeg", "webp"]),
             PickKind::Archive => ("Archives", &["zip", "tar", "gz", "7z"]),
+            PickKind::Audio => ("Audio", &["mp3", "ogg", "wav", "m4a"]),
+            PickKind::Video => ("Videos", &["mp4", "webm", "mov", "m4v"]),
         }
     }
 
@@ -35,6 +39,8 @@ impl PickKind {
             .unwrap_or(match self {
                 PickKind::Image => "png",
                 PickKind::Archive => "zip",
+                PickKind::Audio => "mp3",
+                PickKind::Video => "mp4",
             })
             .to_string()
     }
@@ -101,6 +107,14 @@ impl<R: Runtime> Bliet<R> {
     pub async fn pick_archive(&self) -> crate::Result<Option<FileResponse>> {
         self.pick_file(PickKind::Archive).await
     }
+
+    pub async fn pick_audio(&self) -> crate::Result<Option<FileResponse>> {
+        self.pick_file(PickKind::Audio).await
+    }
+
+    pub async fn pick_video(&self) -> crate::Result<Option<FileResponse>> {
+        self.pick_file(PickKind::Video).await
+    }
     
 
     pub async fn pick_import_file(
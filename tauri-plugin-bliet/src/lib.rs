@@ -34,7 +34,7 @@ impl<R: Runtime, T: Manager<R>> crate::BlietExt<R> for T {
 /// Initializes the plugin.
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
   Builder::new("bliet")
-    .invoke_handler(tauri::generate_handler![commands::pick_image, commands::pick_archive])
+    .invoke_handler(tauri::generate_handler![commands::pick_image, commands::pick_archive, commands::pick_audio, commands::pick_video])
     .setup(|app, api| {
       #[cfg(mobile)]
       let bliet = mobile::init(app, api)?;
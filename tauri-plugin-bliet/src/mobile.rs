@@ -56,6 +56,30 @@ impl<R: Runtime> Bliet<R> {
         Ok(resp.path.map(|path| FileResponse { path }))
     }
 
+    pub async fn pick_audio(&self) -> crate::Result<Option<FileResponse>> {
+        #[derive(serde::Deserialize)]
+        struct PickFileResponse {
+            path: Option<String>,
+        }
+
+        let resp: PickFileResponse =
+            self.0.run_mobile_plugin("pickAudio", json!({}))?;
+
+        Ok(resp.path.map(|path| FileResponse { path }))
+    }
+
+    pub async fn pick_video(&self) -> crate::Result<Option<FileResponse>> {
+        #[derive(serde::Deserialize)]
+        struct PickFileResponse {
+            path: Option<String>,
+        }
+
+        let resp: PickFileResponse =
+            self.0.run_mobile_plugin("pickVideo", json!({}))?;
+
+        Ok(resp.path.map(|path| FileResponse { path }))
+    }
+
     pub async fn pick_import_file(&self) -> crate::Result<Option<Vec<u8>>> {
         #[derive(serde::Deserialize)]
         struct PickImportResponse {